@@ -1,8 +1,13 @@
 extern crate gcc;
 
+use std::env;
+
 fn main() {
-    gcc::Config::new().file("src/stb_truetype.c").
-        define("STB_TRUETYPE_IMPLEMENTATION", None).
-        compile("libstb_truetype.a");
+    //the font code is only used by the SDL debug overlay
+    if env::var("CARGO_FEATURE_SDL").is_ok() {
+        gcc::Config::new().file("src/stb_truetype.c").
+            define("STB_TRUETYPE_IMPLEMENTATION", None).
+            compile("libstb_truetype.a");
+    }
 
 }
@@ -0,0 +1,151 @@
+/*
+ * Runs a ROM without a window, sound or the debug font, for CI and batch testing.
+ * Only uses the core library, so it builds without SDL (cargo build --no-default-features --bin gbemu-headless)
+ * and works in containers with no display
+ *
+ */
+#![allow(non_snake_case)]
+
+extern crate gbEmu;
+
+use std::env;
+use std::fs::File;
+use std::io::Write;
+use std::process;
+
+use gbEmu::gb_gameboy::*;
+use gbEmu::gb_memory::*;
+use gbEmu::gb_lcd::*;
+use gbEmu::gb_mbc::*;
+use gbEmu::gb_serial::*;
+
+static USAGE: &'static str = "Usage: gbemu-headless path_to_rom [options]
+    -s                  skip the boot screen
+    --frames N          number of frames to run (default 3600)
+    --break-at ADDR     stop early once PC reaches ADDR (hex)
+    --screenshot FILE   write the final screen to FILE as a PGM image
+    --serial-out FILE   write every byte sent over the link port to FILE";
+
+const DEFAULT_FRAMES_TO_RUN: u32 = 60 * 60;
+
+struct HeadlessOptions {
+    romFileName: String,
+    shouldSkipBootScreen: bool,
+    framesToRun: u32,
+    breakAt: Option<u16>,
+    screenshotFileName: Option<String>,
+    serialOutFileName: Option<String>
+}
+
+fn printUsageAndExit() -> ! {
+    println!("{}", USAGE);
+    process::exit(2)
+}
+
+fn exitWithError(message: String) -> ! {
+    let _ = writeln!(&mut std::io::stderr(), "{}", message);
+    process::exit(1)
+}
+
+//value following an option like --frames 60
+fn optionValue(value: Option<String>) -> String {
+    value.unwrap_or_else(|| printUsageAndExit())
+}
+
+fn parseArgs() -> HeadlessOptions {
+    let mut args = env::args().skip(1);
+    let mut romFileName = None;
+
+    let mut options = HeadlessOptions {
+        romFileName: String::new(),
+        shouldSkipBootScreen: false,
+        framesToRun: DEFAULT_FRAMES_TO_RUN,
+        breakAt: None,
+        screenshotFileName: None,
+        serialOutFileName: None
+    };
+
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "-s" => options.shouldSkipBootScreen = true,
+            "--frames" => {
+                options.framesToRun = optionValue(args.next()).parse().unwrap_or_else(|_| printUsageAndExit())
+            },
+            "--break-at" => {
+                let value = optionValue(args.next());
+                let addr = u16::from_str_radix(value.trim_left_matches("0x"), 16);
+                options.breakAt = Some(addr.unwrap_or_else(|_| printUsageAndExit()))
+            },
+            "--screenshot" => options.screenshotFileName = Some(optionValue(args.next())),
+            "--serial-out" => options.serialOutFileName = Some(optionValue(args.next())),
+            _ if !arg.starts_with("-") && romFileName.is_none() => romFileName = Some(arg.clone()),
+            _ => printUsageAndExit()
+        }
+    }
+
+    match romFileName {
+        Some(romFileName) => options.romFileName = romFileName,
+        None => printUsageAndExit()
+    }
+
+    options
+}
+
+fn writeFile(fileName: &str, data: &[u8]) {
+    let result = File::create(fileName).and_then(|mut f| f.write_all(data));
+
+    if let Err(err) = result {
+        exitWithError(format!("Could not write {}. Error: {}", fileName, err));
+    }
+}
+
+fn main() {
+    let options = parseArgs();
+    let mut gb = Box::new(GameBoyState::new());
+
+    let romData = match openROM(&options.romFileName) {
+        Ok(data) => data,
+        Err(err) => exitWithError(format!("Could not open {}. Error: {}", options.romFileName, err))
+    };
+
+    gb.mem.cartridge = match cartridgeFromROM(romData) {
+        Ok(cartridge) => cartridge,
+        Err(err) => exitWithError(err)
+    };
+
+    if options.shouldSkipBootScreen {
+        gb.skipBootScreen();
+    }
+
+    let serialCapture = SerialCapture::new();
+    let serialOutput = serialCapture.output.clone();
+    gb.mem.serial.device = Box::new(serialCapture);
+
+    if let Some(addr) = options.breakAt {
+        gb.breakpoints.push(addr);
+    }
+
+    let mut framesRun = 0;
+
+    while framesRun < options.framesToRun {
+        let events = gb.run_frame();
+        gb.takeAudioSamples(); //nothing plays them, so don't let them pile up
+
+        if events.isBreakpointHit {
+            println!("Hit breakpoint at {:X}", gb.cpu.PC);
+            break;
+        }
+
+        framesRun += 1;
+    }
+
+    println!("Ran {} frames. PC: {:X}, total cycles: {}", framesRun, gb.cpu.PC, gb.cpu.totalCycles);
+
+    if let Some(ref fileName) = options.screenshotFileName {
+        writeFile(fileName, &screenToPGM(&gb.mem.lcd.screen)[..]);
+    }
+
+    if let Some(ref fileName) = options.serialOutFileName {
+        writeFile(fileName, &serialOutput.borrow()[..]);
+    }
+}
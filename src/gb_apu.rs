@@ -0,0 +1,857 @@
+/*
+ * All sound related state and functions go into this module
+ *
+ */
+use gb_cpu::CLOCK_SPEED_HZ;
+
+//how many cycles pass between each stereo sample that is output
+pub const CYCLES_PER_SAMPLE: u32 = 64;
+//the rate at which samples are put into APUState.sampleBuffer (65536 Hz)
+pub const SAMPLE_RATE_HZ: u32 = CLOCK_SPEED_HZ as u32 / CYCLES_PER_SAMPLE;
+
+const SQUARE_LENGTH: u16 = 64;
+const WAVE_LENGTH: u16 = 256;
+const NOISE_LENGTH: u16 = 64;
+const MAX_FREQUENCY: u16 = 2047;
+const WAVE_SAMPLES: usize = 32;
+
+//each row is one of the 4 duty cycles selected by bits 6-7 of NRx1
+static DUTY_PATTERNS: [[u8;8];4] = [
+    [0, 0, 0, 0, 0, 0, 0, 1], //12.5%
+    [1, 0, 0, 0, 0, 0, 0, 1], //25%
+    [1, 0, 0, 0, 0, 1, 1, 1], //50%
+    [0, 1, 1, 1, 1, 1, 1, 0]  //75%
+];
+
+//noise channel divisors selected by bits 0-2 of NR43
+static NOISE_DIVISORS: [u32;8] = [8, 16, 32, 48, 64, 80, 96, 112];
+
+//Volume envelope used by both square channels and the noise channel (NRx2)
+#[derive(Copy, Clone)]
+pub struct Envelope {
+    pub initialVolume: u8, //Bits 4-7
+    pub isIncreasing: bool, //Bit 3
+    pub period: u8, //Bits 0-2
+
+    pub volume: u8, //current volume of the channel
+    pub timer: u8
+}
+
+impl Envelope {
+    pub fn new() -> Envelope {
+        Envelope {
+            initialVolume: 0,
+            isIncreasing: false,
+            period: 0,
+
+            volume: 0,
+            timer: 0
+        }
+    }
+}
+
+//Channels 1 and 2.  Only channel 1 uses the sweep fields
+#[derive(Copy, Clone)]
+pub struct SquareChannel {
+    pub isEnabled: bool, //status bit in NR52
+    pub isDACEnabled: bool,
+
+    pub duty: u8,
+    pub dutyStep: usize,
+
+    pub lengthCounter: u16,
+    pub isLengthEnabled: bool,
+
+    pub envelope: Envelope,
+
+    pub frequency: u16, //11-bit frequency from NRx3 and NRx4
+    pub frequencyTimer: u32,
+
+    pub sweepPeriod: u8,
+    pub isSweepNegated: bool,
+    pub sweepShift: u8,
+    pub sweepTimer: u8,
+    pub shadowFrequency: u16,
+    pub isSweepEnabled: bool
+}
+
+impl SquareChannel {
+    pub fn new() -> SquareChannel {
+        SquareChannel {
+            isEnabled: false,
+            isDACEnabled: false,
+
+            duty: 0,
+            dutyStep: 0,
+
+            lengthCounter: 0,
+            isLengthEnabled: false,
+
+            envelope: Envelope::new(),
+
+            frequency: 0,
+            frequencyTimer: squarePeriod(0),
+
+            sweepPeriod: 0,
+            isSweepNegated: false,
+            sweepShift: 0,
+            sweepTimer: 0,
+            shadowFrequency: 0,
+            isSweepEnabled: false
+        }
+    }
+}
+
+//Channel 3
+#[derive(Copy, Clone)]
+pub struct WaveChannel {
+    pub isEnabled: bool, //status bit in NR52
+    pub isDACEnabled: bool, //NR30 Bit 7
+
+    pub lengthCounter: u16,
+    pub isLengthEnabled: bool,
+
+    pub volumeCode: u8, //NR32 Bits 5-6
+
+    pub frequency: u16,
+    pub frequencyTimer: u32,
+
+    pub position: usize, //which of the 32 4-bit samples is being played
+    pub waveRAM: [u8;0x10]
+}
+
+impl WaveChannel {
+    pub fn new() -> WaveChannel {
+        WaveChannel {
+            isEnabled: false,
+            isDACEnabled: false,
+
+            lengthCounter: 0,
+            isLengthEnabled: false,
+
+            volumeCode: 0,
+
+            frequency: 0,
+            frequencyTimer: wavePeriod(0),
+
+            position: 0,
+            waveRAM: [0;0x10]
+        }
+    }
+}
+
+//Channel 4
+#[derive(Copy, Clone)]
+pub struct NoiseChannel {
+    pub isEnabled: bool, //status bit in NR52
+    pub isDACEnabled: bool,
+
+    pub lengthCounter: u16,
+    pub isLengthEnabled: bool,
+
+    pub envelope: Envelope,
+
+    pub clockShift: u8, //NR43 Bits 4-7
+    pub isWidth7Bit: bool, //NR43 Bit 3
+    pub divisorCode: u8, //NR43 Bits 0-2
+    pub frequencyTimer: u32,
+
+    pub lfsr: u16 //15-bit linear feedback shift register
+}
+
+impl NoiseChannel {
+    pub fn new() -> NoiseChannel {
+        NoiseChannel {
+            isEnabled: false,
+            isDACEnabled: false,
+
+            lengthCounter: 0,
+            isLengthEnabled: false,
+
+            envelope: Envelope::new(),
+
+            clockShift: 0,
+            isWidth7Bit: false,
+            divisorCode: 0,
+            frequencyTimer: NOISE_DIVISORS[0],
+
+            lfsr: 0x7FFF
+        }
+    }
+}
+
+pub struct APUState {
+    pub isEnabled: bool, //NR52 Bit 7
+
+    pub square1: SquareChannel,
+    pub square2: SquareChannel,
+    pub wave: WaveChannel,
+    pub noise: NoiseChannel,
+
+    //NR50
+    pub isVinLeftEnabled: bool, //Bit 7
+    pub leftVolume: u8, //Bits 4-6
+    pub isVinRightEnabled: bool, //Bit 3
+    pub rightVolume: u8, //Bits 0-2
+
+    pub channelPanning: u8, //NR51
+
+    pub frameSequencerStep: u8,
+    pub lastDividerBit: bool, //used to find the falling edge of bit 4 of DIV
+
+    pub sampleCycles: u32, //cycles since the last sample was output
+    pub sampleBuffer: Vec<i16> //interleaved left and right samples at SAMPLE_RATE_HZ
+}
+
+impl APUState {
+    pub fn new() -> APUState {
+        APUState {
+            isEnabled: false,
+
+            square1: SquareChannel::new(),
+            square2: SquareChannel::new(),
+            wave: WaveChannel::new(),
+            noise: NoiseChannel::new(),
+
+            isVinLeftEnabled: false,
+            leftVolume: 0,
+            isVinRightEnabled: false,
+            rightVolume: 0,
+
+            channelPanning: 0,
+
+            frameSequencerStep: 0,
+            lastDividerBit: false,
+
+            sampleCycles: 0,
+            sampleBuffer: vec![]
+        }
+    }
+}
+
+fn squarePeriod(frequency: u16) -> u32 {
+    (2048 - frequency as u32) * 4
+}
+
+fn wavePeriod(frequency: u16) -> u32 {
+    (2048 - frequency as u32) * 2
+}
+
+fn noisePeriod(noise: &NoiseChannel) -> u32 {
+    NOISE_DIVISORS[noise.divisorCode as usize] << noise.clockShift
+}
+
+fn envelopeToU8(envelope: &Envelope) -> u8 {
+    (envelope.initialVolume << 4) |
+        (if envelope.isIncreasing {1 << 3} else {0}) |
+        envelope.period
+}
+
+fn updateEnvelopeFromU8(envelope: &mut Envelope, val: u8) {
+    envelope.initialVolume = val >> 4;
+    envelope.isIncreasing = testBit!(val, 3);
+    envelope.period = val & 7;
+}
+
+fn triggerEnvelope(envelope: &mut Envelope) {
+    envelope.volume = envelope.initialVolume;
+    envelope.timer = envelope.period;
+}
+
+fn clockEnvelope(envelope: &mut Envelope) {
+    //a period of 0 means the envelope is stopped
+    if envelope.period == 0 {
+        return;
+    }
+
+    if envelope.timer > 0 {
+        envelope.timer -= 1;
+    }
+
+    if envelope.timer == 0 {
+        envelope.timer = envelope.period;
+
+        if envelope.isIncreasing && envelope.volume < 15 {
+            envelope.volume += 1;
+        }
+        else if !envelope.isIncreasing && envelope.volume > 0 {
+            envelope.volume -= 1;
+        }
+    }
+}
+
+//disables the channel once its length counter runs out
+fn clockLengthCounter(lengthCounter: &mut u16, isLengthEnabled: bool, isChannelEnabled: &mut bool) {
+    if isLengthEnabled && *lengthCounter > 0 {
+        *lengthCounter -= 1;
+
+        if *lengthCounter == 0 {
+            *isChannelEnabled = false;
+        }
+    }
+}
+
+//calculates the next sweep frequency and disables the channel if it overflows
+fn calculateSweepFrequency(square: &mut SquareChannel) -> u16 {
+    let delta = square.shadowFrequency >> square.sweepShift;
+
+    let newFrequency = if square.isSweepNegated {
+        square.shadowFrequency.wrapping_sub(delta)
+    }
+    else {
+        square.shadowFrequency + delta
+    };
+
+    if newFrequency > MAX_FREQUENCY {
+        square.isEnabled = false;
+    }
+
+    newFrequency
+}
+
+fn clockSweep(square: &mut SquareChannel) {
+    if square.sweepTimer > 0 {
+        square.sweepTimer -= 1;
+    }
+
+    if square.sweepTimer == 0 {
+        //a period of 0 is treated as 8
+        square.sweepTimer = if square.sweepPeriod == 0 {8} else {square.sweepPeriod};
+
+        if square.isSweepEnabled && square.sweepPeriod != 0 {
+            let newFrequency = calculateSweepFrequency(square);
+
+            if newFrequency <= MAX_FREQUENCY && square.sweepShift != 0 {
+                square.frequency = newFrequency;
+                square.shadowFrequency = newFrequency;
+
+                //the new frequency is checked for overflow again, but not written back
+                calculateSweepFrequency(square);
+            }
+        }
+    }
+}
+
+fn triggerSquareChannel(square: &mut SquareChannel) {
+    square.isEnabled = square.isDACEnabled;
+
+    if square.lengthCounter == 0 {
+        square.lengthCounter = SQUARE_LENGTH;
+    }
+
+    square.frequencyTimer = squarePeriod(square.frequency);
+    triggerEnvelope(&mut square.envelope);
+
+    square.shadowFrequency = square.frequency;
+    square.sweepTimer = if square.sweepPeriod == 0 {8} else {square.sweepPeriod};
+    square.isSweepEnabled = square.sweepPeriod != 0 || square.sweepShift != 0;
+
+    if square.sweepShift != 0 {
+        calculateSweepFrequency(square);
+    }
+}
+
+fn triggerWaveChannel(wave: &mut WaveChannel) {
+    wave.isEnabled = wave.isDACEnabled;
+
+    if wave.lengthCounter == 0 {
+        wave.lengthCounter = WAVE_LENGTH;
+    }
+
+    wave.frequencyTimer = wavePeriod(wave.frequency);
+    wave.position = 0;
+}
+
+fn triggerNoiseChannel(noise: &mut NoiseChannel) {
+    noise.isEnabled = noise.isDACEnabled;
+
+    if noise.lengthCounter == 0 {
+        noise.lengthCounter = NOISE_LENGTH;
+    }
+
+    noise.frequencyTimer = noisePeriod(noise);
+    triggerEnvelope(&mut noise.envelope);
+    noise.lfsr = 0x7FFF;
+}
+
+fn stepSquareChannel(square: &mut SquareChannel, cycles: u32) {
+    let mut cyclesLeft = cycles;
+
+    while cyclesLeft >= square.frequencyTimer {
+        cyclesLeft -= square.frequencyTimer;
+        square.frequencyTimer = squarePeriod(square.frequency);
+        square.dutyStep = (square.dutyStep + 1) % 8;
+    }
+
+    square.frequencyTimer -= cyclesLeft;
+}
+
+fn stepWaveChannel(wave: &mut WaveChannel, cycles: u32) {
+    let mut cyclesLeft = cycles;
+
+    while cyclesLeft >= wave.frequencyTimer {
+        cyclesLeft -= wave.frequencyTimer;
+        wave.frequencyTimer = wavePeriod(wave.frequency);
+        wave.position = (wave.position + 1) % WAVE_SAMPLES;
+    }
+
+    wave.frequencyTimer -= cyclesLeft;
+}
+
+fn stepNoiseChannel(noise: &mut NoiseChannel, cycles: u32) {
+    let mut cyclesLeft = cycles;
+
+    while cyclesLeft >= noise.frequencyTimer {
+        cyclesLeft -= noise.frequencyTimer;
+        noise.frequencyTimer = noisePeriod(noise);
+
+        //xor the low 2 bits, shift right and put the result in bit 14 (and bit 6 in 7-bit mode)
+        let xorResult = (noise.lfsr & 1) ^ ((noise.lfsr >> 1) & 1);
+        noise.lfsr = (noise.lfsr >> 1) | (xorResult << 14);
+
+        if noise.isWidth7Bit {
+            noise.lfsr = (noise.lfsr & !(1 << 6)) | (xorResult << 6);
+        }
+    }
+
+    noise.frequencyTimer -= cyclesLeft;
+}
+
+fn squareOutput(square: &SquareChannel) -> u8 {
+    if square.isEnabled {
+        DUTY_PATTERNS[square.duty as usize][square.dutyStep] * square.envelope.volume
+    }
+    else {
+        0
+    }
+}
+
+fn waveOutput(wave: &WaveChannel) -> u8 {
+    if wave.isEnabled {
+        let sampleByte = wave.waveRAM[wave.position / 2];
+
+        //high nibble is played first
+        let sample = if wave.position % 2 == 0 {sampleByte >> 4} else {sampleByte & 0xF};
+
+        match wave.volumeCode {
+            0 => 0, //mute
+            1 => sample, //100%
+            2 => sample >> 1, //50%
+            3 => sample >> 2, //25%
+            _ => panic!("Wave volume code should only be between 0 and 3")
+        }
+    }
+    else {
+        0
+    }
+}
+
+fn noiseOutput(noise: &NoiseChannel) -> u8 {
+    //output is the inverted low bit of the lfsr
+    if noise.isEnabled && (noise.lfsr & 1) == 0 {
+        noise.envelope.volume
+    }
+    else {
+        0
+    }
+}
+
+//converts a channel's 4-bit output to a value between -1 and 1
+fn dacOutput(digitalOutput: u8, isDACEnabled: bool) -> f32 {
+    if isDACEnabled {
+        (digitalOutput as f32 / 7.5) - 1.0
+    }
+    else {
+        0.0
+    }
+}
+
+fn mixChannels(apu: &APUState) -> (i16, i16) {
+    if !apu.isEnabled {
+        return (0, 0);
+    }
+
+    let channelOutputs = [
+        if apu.square1.isEnabled {dacOutput(squareOutput(&apu.square1), apu.square1.isDACEnabled)} else {0.0},
+        if apu.square2.isEnabled {dacOutput(squareOutput(&apu.square2), apu.square2.isDACEnabled)} else {0.0},
+        if apu.wave.isEnabled {dacOutput(waveOutput(&apu.wave), apu.wave.isDACEnabled)} else {0.0},
+        if apu.noise.isEnabled {dacOutput(noiseOutput(&apu.noise), apu.noise.isDACEnabled)} else {0.0}
+    ];
+
+    let mut left = 0f32;
+    let mut right = 0f32;
+
+    //NR51: Bits 4-7 send channels 1-4 to the left, Bits 0-3 send channels 1-4 to the right
+    for (i, output) in channelOutputs.iter().enumerate() {
+        if testBit!(apu.channelPanning, i + 4) {
+            left += *output;
+        }
+        if testBit!(apu.channelPanning, i) {
+            right += *output;
+        }
+    }
+
+    //average the 4 channels then apply the master volume from NR50
+    left = (left / 4.0) * ((apu.leftVolume as f32 + 1.0) / 8.0);
+    right = (right / 4.0) * ((apu.rightVolume as f32 + 1.0) / 8.0);
+
+    ((left * i16::max_value() as f32) as i16, (right * i16::max_value() as f32) as i16)
+}
+
+fn clockFrameSequencer(apu: &mut APUState) {
+    /* Frame Sequencer (512 Hz):
+     *
+     * Step   Length Ctr  Vol Env     Sweep
+     * ---------------------------------------
+     * 0      Clock       -           -
+     * 1      -           -           -
+     * 2      Clock       -           Clock
+     * 3      -           -           -
+     * 4      Clock       -           -
+     * 5      -           -           -
+     * 6      Clock       -           Clock
+     * 7      -           Clock       -
+     */
+    match apu.frameSequencerStep {
+        0 | 2 | 4 | 6 => {
+            clockLengthCounter(&mut apu.square1.lengthCounter, apu.square1.isLengthEnabled, &mut apu.square1.isEnabled);
+            clockLengthCounter(&mut apu.square2.lengthCounter, apu.square2.isLengthEnabled, &mut apu.square2.isEnabled);
+            clockLengthCounter(&mut apu.wave.lengthCounter, apu.wave.isLengthEnabled, &mut apu.wave.isEnabled);
+            clockLengthCounter(&mut apu.noise.lengthCounter, apu.noise.isLengthEnabled, &mut apu.noise.isEnabled);
+
+            if apu.frameSequencerStep == 2 || apu.frameSequencerStep == 6 {
+                clockSweep(&mut apu.square1);
+            }
+        },
+
+        7 => {
+            clockEnvelope(&mut apu.square1.envelope);
+            clockEnvelope(&mut apu.square2.envelope);
+            clockEnvelope(&mut apu.noise.envelope);
+        },
+
+        _ => {}
+    }
+
+    apu.frameSequencerStep = (apu.frameSequencerStep + 1) % 8;
+}
+
+//turning off the APU clears every register except wave RAM
+fn powerOffAPU(apu: &mut APUState) {
+    let waveRAM = apu.wave.waveRAM;
+
+    apu.isEnabled = false;
+
+    apu.square1 = SquareChannel::new();
+    apu.square2 = SquareChannel::new();
+    apu.wave = WaveChannel::new();
+    apu.noise = NoiseChannel::new();
+    apu.wave.waveRAM = waveRAM;
+
+    apu.isVinLeftEnabled = false;
+    apu.leftVolume = 0;
+    apu.isVinRightEnabled = false;
+    apu.rightVolume = 0;
+    apu.channelPanning = 0;
+}
+
+pub fn readAPURegister(apu: &APUState, addr: u16) -> u8 {
+    let square1 = &apu.square1;
+    let square2 = &apu.square2;
+    let wave = &apu.wave;
+    let noise = &apu.noise;
+
+    //NOTE: Unused and write-only bits read back as 1
+    match addr {
+        0xFF10 => { //NR10 - Channel 1 Sweep
+            0x80 | (square1.sweepPeriod << 4) |
+                (if square1.isSweepNegated {1 << 3} else {0}) |
+                square1.sweepShift
+        },
+        0xFF11 => 0x3F | (square1.duty << 6), //NR11 - Channel 1 Duty
+        0xFF12 => envelopeToU8(&square1.envelope), //NR12 - Channel 1 Envelope
+        0xFF14 => 0xBF | if square1.isLengthEnabled {1 << 6} else {0}, //NR14
+
+        0xFF16 => 0x3F | (square2.duty << 6), //NR21 - Channel 2 Duty
+        0xFF17 => envelopeToU8(&square2.envelope), //NR22 - Channel 2 Envelope
+        0xFF19 => 0xBF | if square2.isLengthEnabled {1 << 6} else {0}, //NR24
+
+        0xFF1A => 0x7F | if wave.isDACEnabled {1 << 7} else {0}, //NR30 - Channel 3 DAC
+        0xFF1C => 0x9F | (wave.volumeCode << 5), //NR32 - Channel 3 Volume
+        0xFF1E => 0xBF | if wave.isLengthEnabled {1 << 6} else {0}, //NR34
+
+        0xFF21 => envelopeToU8(&noise.envelope), //NR42 - Channel 4 Envelope
+        0xFF22 => { //NR43 - Channel 4 Polynomial Counter
+            (noise.clockShift << 4) |
+                (if noise.isWidth7Bit {1 << 3} else {0}) |
+                noise.divisorCode
+        },
+        0xFF23 => 0xBF | if noise.isLengthEnabled {1 << 6} else {0}, //NR44
+
+        0xFF24 => { //NR50 - Master Volume
+            (if apu.isVinLeftEnabled {1 << 7} else {0}) |
+                (apu.leftVolume << 4) |
+                (if apu.isVinRightEnabled {1 << 3} else {0}) |
+                apu.rightVolume
+        },
+        0xFF25 => apu.channelPanning, //NR51
+        0xFF26 => { //NR52 - Sound On/Off
+            let mut status = 0x70;
+
+            status |= if apu.isEnabled {1 << 7} else {0};
+            status |= if noise.isEnabled {1 << 3} else {0};
+            status |= if wave.isEnabled {1 << 2} else {0};
+            status |= if square2.isEnabled {1 << 1} else {0};
+            status |= if square1.isEnabled {1} else {0};
+
+            status
+        },
+
+        0xFF30...0xFF3F => wave.waveRAM[(addr - 0xFF30) as usize],
+
+        _ => 0xFF //frequency registers are write-only and the rest are unused
+    }
+}
+
+pub fn writeAPURegister(apu: &mut APUState, byte: u8, addr: u16) {
+
+    //wave RAM and NR52 can be accessed even when the APU is off
+    match addr {
+        0xFF30...0xFF3F => {
+            apu.wave.waveRAM[(addr - 0xFF30) as usize] = byte;
+            return;
+        },
+        0xFF26 => {
+            if !testBit!(byte, 7) && apu.isEnabled {
+                powerOffAPU(apu);
+            }
+            else if testBit!(byte, 7) && !apu.isEnabled {
+                apu.isEnabled = true;
+                apu.frameSequencerStep = 0;
+            }
+            return;
+        },
+        _ => {}
+    }
+
+    if !apu.isEnabled {
+        //NOTE: On the DMG, the length counters can still be written to while the APU is off
+        match addr {
+            0xFF11 => apu.square1.lengthCounter = SQUARE_LENGTH - (byte & 0x3F) as u16,
+            0xFF16 => apu.square2.lengthCounter = SQUARE_LENGTH - (byte & 0x3F) as u16,
+            0xFF1B => apu.wave.lengthCounter = WAVE_LENGTH - byte as u16,
+            0xFF20 => apu.noise.lengthCounter = NOISE_LENGTH - (byte & 0x3F) as u16,
+            _ => {}
+        }
+        return;
+    }
+
+    match addr {
+        0xFF10 => { //NR10
+            apu.square1.sweepPeriod = (byte >> 4) & 7;
+            apu.square1.isSweepNegated = testBit!(byte, 3);
+            apu.square1.sweepShift = byte & 7;
+        },
+        0xFF11 => { //NR11
+            apu.square1.duty = byte >> 6;
+            apu.square1.lengthCounter = SQUARE_LENGTH - (byte & 0x3F) as u16;
+        },
+        0xFF12 => { //NR12
+            updateEnvelopeFromU8(&mut apu.square1.envelope, byte);
+
+            //DAC is off when the upper 5 bits are cleared, which also turns off the channel
+            apu.square1.isDACEnabled = (byte & 0xF8) != 0;
+            if !apu.square1.isDACEnabled {
+                apu.square1.isEnabled = false;
+            }
+        },
+        0xFF13 => apu.square1.frequency = (apu.square1.frequency & 0x700) | byte as u16, //NR13
+        0xFF14 => { //NR14
+            apu.square1.frequency = (apu.square1.frequency & 0xFF) | (((byte & 7) as u16) << 8);
+            apu.square1.isLengthEnabled = testBit!(byte, 6);
+
+            if testBit!(byte, 7) {
+                triggerSquareChannel(&mut apu.square1);
+            }
+        },
+
+        0xFF16 => { //NR21
+            apu.square2.duty = byte >> 6;
+            apu.square2.lengthCounter = SQUARE_LENGTH - (byte & 0x3F) as u16;
+        },
+        0xFF17 => { //NR22
+            updateEnvelopeFromU8(&mut apu.square2.envelope, byte);
+
+            apu.square2.isDACEnabled = (byte & 0xF8) != 0;
+            if !apu.square2.isDACEnabled {
+                apu.square2.isEnabled = false;
+            }
+        },
+        0xFF18 => apu.square2.frequency = (apu.square2.frequency & 0x700) | byte as u16, //NR23
+        0xFF19 => { //NR24
+            apu.square2.frequency = (apu.square2.frequency & 0xFF) | (((byte & 7) as u16) << 8);
+            apu.square2.isLengthEnabled = testBit!(byte, 6);
+
+            if testBit!(byte, 7) {
+                triggerSquareChannel(&mut apu.square2);
+            }
+        },
+
+        0xFF1A => { //NR30
+            apu.wave.isDACEnabled = testBit!(byte, 7);
+            if !apu.wave.isDACEnabled {
+                apu.wave.isEnabled = false;
+            }
+        },
+        0xFF1B => apu.wave.lengthCounter = WAVE_LENGTH - byte as u16, //NR31
+        0xFF1C => apu.wave.volumeCode = (byte >> 5) & 3, //NR32
+        0xFF1D => apu.wave.frequency = (apu.wave.frequency & 0x700) | byte as u16, //NR33
+        0xFF1E => { //NR34
+            apu.wave.frequency = (apu.wave.frequency & 0xFF) | (((byte & 7) as u16) << 8);
+            apu.wave.isLengthEnabled = testBit!(byte, 6);
+
+            if testBit!(byte, 7) {
+                triggerWaveChannel(&mut apu.wave);
+            }
+        },
+
+        0xFF20 => apu.noise.lengthCounter = NOISE_LENGTH - (byte & 0x3F) as u16, //NR41
+        0xFF21 => { //NR42
+            updateEnvelopeFromU8(&mut apu.noise.envelope, byte);
+
+            apu.noise.isDACEnabled = (byte & 0xF8) != 0;
+            if !apu.noise.isDACEnabled {
+                apu.noise.isEnabled = false;
+            }
+        },
+        0xFF22 => { //NR43
+            apu.noise.clockShift = byte >> 4;
+            apu.noise.isWidth7Bit = testBit!(byte, 3);
+            apu.noise.divisorCode = byte & 7;
+        },
+        0xFF23 => { //NR44
+            apu.noise.isLengthEnabled = testBit!(byte, 6);
+
+            if testBit!(byte, 7) {
+                triggerNoiseChannel(&mut apu.noise);
+            }
+        },
+
+        0xFF24 => { //NR50
+            apu.isVinLeftEnabled = testBit!(byte, 7);
+            apu.leftVolume = (byte >> 4) & 7;
+            apu.isVinRightEnabled = testBit!(byte, 3);
+            apu.rightVolume = byte & 7;
+        },
+        0xFF25 => apu.channelPanning = byte, //NR51
+
+        _ => {} //unused registers
+    }
+}
+
+/*
+ * Advances all of the sound channels and outputs samples into apu.sampleBuffer
+ *
+ * Args:
+ *      apu: The sound state of the Game Boy
+ *      divider: The current value of the DIV register, which clocks the frame sequencer
+ *      cyclesTakenOfLastInstruction: How many cycles to advance the channels by
+ *
+ */
+pub fn stepAPU(apu: &mut APUState, divider: u8, cyclesTakenOfLastInstruction: u32) {
+
+    //the frame sequencer is clocked on the falling edge of bit 4 of DIV (512 Hz)
+    let dividerBit = testBit!(divider, 4);
+    if apu.isEnabled && apu.lastDividerBit && !dividerBit {
+        clockFrameSequencer(apu);
+    }
+    apu.lastDividerBit = dividerBit;
+
+    if apu.isEnabled {
+        stepSquareChannel(&mut apu.square1, cyclesTakenOfLastInstruction);
+        stepSquareChannel(&mut apu.square2, cyclesTakenOfLastInstruction);
+        stepWaveChannel(&mut apu.wave, cyclesTakenOfLastInstruction);
+        stepNoiseChannel(&mut apu.noise, cyclesTakenOfLastInstruction);
+    }
+
+    apu.sampleCycles += cyclesTakenOfLastInstruction;
+
+    while apu.sampleCycles >= CYCLES_PER_SAMPLE {
+        apu.sampleCycles -= CYCLES_PER_SAMPLE;
+
+        let (left, right) = mixChannels(apu);
+        apu.sampleBuffer.push(left);
+        apu.sampleBuffer.push(right);
+    }
+}
+
+//Converts interleaved stereo samples from one sample rate to another using linear interpolation.
+//Keeps track of where it left off so samples can be fed in one frame at a time
+pub struct AudioResampler {
+    pub inputRate: u32,
+    pub outputRate: u32,
+
+    position: f64, //position of the next output sample, relative to lastFrame
+    lastFrame: (i16, i16) //last left and right sample of the previous input
+}
+
+impl AudioResampler {
+    pub fn new(inputRate: u32, outputRate: u32) -> AudioResampler {
+        AudioResampler {
+            inputRate: inputRate,
+            outputRate: outputRate,
+
+            position: 0.0,
+            lastFrame: (0, 0)
+        }
+    }
+}
+
+/*
+ * Resamples interleaved stereo samples from resampler.inputRate to resampler.outputRate
+ *
+ * Args:
+ *      resampler: Holds the rates and the leftover position from the last call
+ *      input: Interleaved left and right samples at resampler.inputRate
+ *
+ * Return: Interleaved left and right samples at resampler.outputRate
+ *
+ */
+pub fn resampleAudio(resampler: &mut AudioResampler, input: &[i16]) -> Vec<i16> {
+    let numInputFrames = input.len() / 2;
+    let step = resampler.inputRate as f64 / resampler.outputRate as f64;
+    let mut output = vec![];
+
+    //frame 0 is the last frame of the previous input so we can interpolate across calls
+    let frameAt = |i: usize| -> (f64, f64) {
+        if i == 0 {
+            (resampler.lastFrame.0 as f64, resampler.lastFrame.1 as f64)
+        }
+        else {
+            (input[(i - 1) * 2] as f64, input[(i - 1) * 2 + 1] as f64)
+        }
+    };
+
+    let mut position = resampler.position;
+
+    while position < numInputFrames as f64 {
+        let i = position as usize;
+        let fraction = position - i as f64;
+
+        let (leftStart, rightStart) = frameAt(i);
+        let (leftEnd, rightEnd) = frameAt(i + 1);
+
+        output.push((leftStart + (leftEnd - leftStart) * fraction) as i16);
+        output.push((rightStart + (rightEnd - rightStart) * fraction) as i16);
+
+        position += step;
+    }
+
+    resampler.position = position - numInputFrames as f64;
+
+    if numInputFrames > 0 {
+        resampler.lastFrame = (input[(numInputFrames - 1) * 2], input[(numInputFrames - 1) * 2 + 1]);
+    }
+
+    output
+}
@@ -0,0 +1,384 @@
+/*
+ * Import and export of BESS (Best Effort Save State) files, which several emulators can read.
+ * A BESS file is:
+ *      buffers (WRAM, VRAM, cart RAM, OAM, HRAM) that the CORE block points to
+ *      blocks, each with a 4 character ID and a u32 length: NAME, INFO, CORE, MBC then END
+ *      footer: u32 offset of the first block followed by "BESS"
+ *
+ * All values are little endian.  Only what maps onto this emulator's state is read;
+ * other blocks are skipped
+ */
+use std::fmt;
+
+use gb_gameboy::*;
+use gb_memory::*;
+use gb_lcd::*;
+use gb_util::*;
+use gb_savestate::*;
+
+const BESS_MAGIC: &'static [u8] = b"BESS";
+const FOOTER_SIZE: usize = 8;
+const BLOCK_HEADER_SIZE: usize = 8;
+
+const BESS_MAJOR_VERSION: u16 = 1;
+const BESS_MINOR_VERSION: u16 = 1;
+const CORE_BLOCK_SIZE: usize = 0xD0;
+const INFO_BLOCK_SIZE: usize = 0x12;
+const DMG_MODEL: &'static [u8] = b"GDB ";
+
+//offsets into the CORE block
+const CORE_MODEL: usize = 0x4;
+const CORE_REGISTERS: usize = 0x8;
+const CORE_IME: usize = 0x14;
+const CORE_IE: usize = 0x15;
+const CORE_EXECUTION_STATE: usize = 0x16;
+const CORE_IO_REGISTERS: usize = 0x18;
+const CORE_BUFFERS: usize = 0x98;
+
+const NUM_IO_REGISTERS: usize = 0x80;
+const EXECUTION_STATE_HALTED: u8 = 1;
+
+#[derive(PartialEq, Debug)]
+pub enum BESSError {
+    NoFooter,
+    BlockOutOfBounds(String),
+    BadBlockSize(String),
+    MissingCoreBlock,
+    UnsupportedVersion(u16, u16),
+    UnsupportedModel(String),
+    ROMMismatch,
+    BadMBCRegister(u16)
+}
+
+impl fmt::Display for BESSError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BESSError::NoFooter =>
+                write!(f, "Not a BESS file"),
+            BESSError::BlockOutOfBounds(ref block) =>
+                write!(f, "BESS {} block goes past the end of the file", block),
+            BESSError::BadBlockSize(ref block) =>
+                write!(f, "BESS {} block has the wrong size", block),
+            BESSError::MissingCoreBlock =>
+                write!(f, "BESS file has no CORE block"),
+            BESSError::UnsupportedVersion(major, minor) =>
+                write!(f, "Unsupported BESS version: {}.{}", major, minor),
+            BESSError::UnsupportedModel(ref model) =>
+                write!(f, "BESS state is for an unsupported model: {}", model),
+            BESSError::ROMMismatch =>
+                write!(f, "BESS state is for a different game"),
+            BESSError::BadMBCRegister(addr) =>
+                write!(f, "BESS MBC block writes to a non-MBC address: {:X}", addr)
+        }
+    }
+}
+
+fn writeBlockHeader(w: &mut StateWriter, id: &[u8], size: usize) {
+    w.writeBytes(id);
+    w.writeU32(size as u32);
+}
+
+//title and global checksum, used to check that a state is for the loaded game
+fn romInfo(gb: &GameBoyState) -> Vec<u8> {
+    (0x134..0x144).chain(0x14E..0x150)
+        .map(|addr| gb.mem.cartridge.read_rom(addr))
+        .collect()
+}
+
+/*
+ * Creates a BESS file from the current state
+ *
+ * Args:
+ *      gb: The Game Boy to export
+ *
+ * Return: The contents of the BESS file
+ *
+ */
+pub fn exportBESS(gb: &GameBoyState) -> Vec<u8> {
+    let mem = &gb.mem;
+    let cpu = &gb.cpu;
+    let mut w = StateWriter::new();
+
+    //buffers go first so the CORE block can point at them
+    let cartRAM = mem.cartridge.cart_ram();
+    let buffers: [&[u8]; 5] = [&mem.workingRAM[..], &mem.lcd.videoRAM[..], cartRAM,
+                               &mem.lcd.oam[..], &mem.zeroPageRAM[..]];
+    let mut bufferOffsets = vec![];
+
+    for buffer in buffers.iter() {
+        bufferOffsets.push(w.data.len());
+        w.writeBytes(buffer);
+    }
+
+    let firstBlockOffset = w.data.len();
+
+    let name = format!("gbEmu {}", env!("CARGO_PKG_VERSION"));
+    writeBlockHeader(&mut w, b"NAME", name.len());
+    w.writeBytes(name.as_bytes());
+
+    writeBlockHeader(&mut w, b"INFO", INFO_BLOCK_SIZE);
+    w.writeBytes(&romInfo(gb)[..]);
+
+    writeBlockHeader(&mut w, b"CORE", CORE_BLOCK_SIZE);
+    w.writeU16(BESS_MAJOR_VERSION);
+    w.writeU16(BESS_MINOR_VERSION);
+    w.writeBytes(DMG_MODEL);
+
+    w.writeU16(cpu.PC);
+    w.writeU16(word(cpu.A, cpu.F));
+    w.writeU16(word(cpu.B, cpu.C));
+    w.writeU16(word(cpu.D, cpu.E));
+    w.writeU16(word(cpu.H, cpu.L));
+    w.writeU16(cpu.SP);
+
+    w.writeBool(cpu.enableInterrupts);
+    w.writeU8(mem.enabledInterrupts);
+    w.writeU8(if cpu.isHalted {EXECUTION_STATE_HALTED} else {0});
+    w.writeU8(0); //reserved
+
+    for i in 0..NUM_IO_REGISTERS {
+        w.writeU8(readByteFromMemory(mem, 0xFF00 + i as u16));
+    }
+
+    for (buffer, offset) in buffers.iter().zip(bufferOffsets.iter()) {
+        w.writeU32(buffer.len() as u32);
+        w.writeU32(*offset as u32);
+    }
+
+    //no CGB palettes
+    for _ in 0..4 {
+        w.writeU32(0);
+    }
+
+    let mbcWrites = mem.cartridge.control_register_writes();
+    writeBlockHeader(&mut w, b"MBC ", mbcWrites.len() * 3);
+    for &(addr, byte) in mbcWrites.iter() {
+        w.writeU16(addr);
+        w.writeU8(byte);
+    }
+
+    writeBlockHeader(&mut w, b"END ", 0);
+
+    w.writeU32(firstBlockOffset as u32);
+    w.writeBytes(BESS_MAGIC);
+
+    w.data
+}
+
+/*
+ * Loads a BESS file made by this or another emulator.  The same ROM must already be loaded.
+ * If the file can't be loaded, the machine is left as it was
+ *
+ * Args:
+ *      gb: The Game Boy to load the state into
+ *      data: The contents of the BESS file
+ *
+ * Return: Why the file could not be loaded, if it couldn't
+ *
+ */
+pub fn importBESS(gb: &mut GameBoyState, data: &[u8]) -> Result<(), BESSError> {
+    let backup = gb.save_state();
+
+    match readBESS(gb, data) {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            gb.load_state(&backup[..]).unwrap();
+            Err(err)
+        }
+    }
+}
+
+fn readBESS(gb: &mut GameBoyState, data: &[u8]) -> Result<(), BESSError> {
+    if data.len() < FOOTER_SIZE || &data[data.len() - 4..] != BESS_MAGIC {
+        return Err(BESSError::NoFooter);
+    }
+
+    let footerStart = data.len() - FOOTER_SIZE;
+    let mut blockOffset = readU32(data, footerStart) as usize;
+    let mut hasReadCore = false;
+
+    loop {
+        if blockOffset + BLOCK_HEADER_SIZE > footerStart {
+            return Err(BESSError::BlockOutOfBounds("header".to_string()));
+        }
+
+        let id = String::from_utf8_lossy(&data[blockOffset..blockOffset + 4]).into_owned();
+        let size = readU32(data, blockOffset + 4) as usize;
+        let blockStart = blockOffset + BLOCK_HEADER_SIZE;
+
+        if blockStart + size > footerStart {
+            return Err(BESSError::BlockOutOfBounds(id));
+        }
+
+        let block = &data[blockStart..blockStart + size];
+
+        match &*id {
+            "INFO" => {
+                if size != INFO_BLOCK_SIZE {
+                    return Err(BESSError::BadBlockSize(id));
+                }
+                if block != &romInfo(gb)[..] {
+                    return Err(BESSError::ROMMismatch);
+                }
+            },
+            "CORE" => {
+                try!(readCoreBlock(gb, data, block));
+                hasReadCore = true;
+            },
+            "MBC " => {
+                if size % 3 != 0 {
+                    return Err(BESSError::BadBlockSize(id));
+                }
+
+                for write in block.chunks(3) {
+                    let addr = readU16(write, 0);
+                    match addr {
+                        0...0x7FFF => gb.mem.cartridge.write_control(addr, write[2]),
+                        //e.g. MBC3 RTC registers
+                        0xA000...0xBFFF => writeByteToMemory(&mut gb.mem, write[2], addr),
+                        _ => return Err(BESSError::BadMBCRegister(addr))
+                    }
+                }
+            },
+            "END " => break,
+            _ => {} //NAME and blocks for things we don't emulate
+        }
+
+        blockOffset = blockStart + size;
+    }
+
+    if hasReadCore {
+        Ok(())
+    }
+    else {
+        Err(BESSError::MissingCoreBlock)
+    }
+}
+
+//copies as much of a CORE block buffer as fits into dest
+fn readBuffer(data: &[u8], core: &[u8], index: usize, name: &str, dest: &mut [u8]) -> Result<(), BESSError> {
+    let size = readU32(core, CORE_BUFFERS + (index * 8)) as usize;
+    let offset = readU32(core, CORE_BUFFERS + (index * 8) + 4) as usize;
+
+    if offset + size > data.len() {
+        return Err(BESSError::BlockOutOfBounds(name.to_string()));
+    }
+
+    for (destByte, srcByte) in dest.iter_mut().zip(data[offset..offset + size].iter()) {
+        *destByte = *srcByte;
+    }
+
+    Ok(())
+}
+
+fn readCoreBlock(gb: &mut GameBoyState, data: &[u8], core: &[u8]) -> Result<(), BESSError> {
+    if core.len() < CORE_BLOCK_SIZE {
+        return Err(BESSError::BadBlockSize("CORE".to_string()));
+    }
+
+    let majorVersion = readU16(core, 0);
+    let minorVersion = readU16(core, 2);
+    if majorVersion != BESS_MAJOR_VERSION {
+        return Err(BESSError::UnsupportedVersion(majorVersion, minorVersion));
+    }
+
+    //only DMG and SGB states can be run on a DMG
+    let model = &core[CORE_MODEL..CORE_MODEL + 4];
+    if model[0] != b'G' && model[0] != b'S' {
+        return Err(BESSError::UnsupportedModel(String::from_utf8_lossy(model).into_owned()));
+    }
+
+    {
+        let cpu = &mut gb.cpu;
+        let register = |i: usize| readU16(core, CORE_REGISTERS + (i * 2));
+
+        cpu.PC = register(0);
+        cpu.A = hb(register(1));
+        cpu.F = lb(register(1)) & 0xF0;
+        cpu.B = hb(register(2));
+        cpu.C = lb(register(2));
+        cpu.D = hb(register(3));
+        cpu.E = lb(register(3));
+        cpu.H = hb(register(4));
+        cpu.L = lb(register(4));
+        cpu.SP = register(5);
+
+        cpu.enableInterrupts = core[CORE_IME] != 0;
+        cpu.isHalted = core[CORE_EXECUTION_STATE] == EXECUTION_STATE_HALTED;
+    }
+
+    let mem = &mut gb.mem;
+    mem.enabledInterrupts = core[CORE_IE];
+
+    try!(readBuffer(data, core, 0, "WRAM", &mut mem.workingRAM[..]));
+    try!(readBuffer(data, core, 1, "VRAM", &mut mem.lcd.videoRAM[..]));
+    try!(readBuffer(data, core, 2, "cart RAM", mem.cartridge.cart_ram_mut()));
+    try!(readBuffer(data, core, 3, "OAM", &mut mem.lcd.oam[..]));
+    try!(readBuffer(data, core, 4, "HRAM", &mut mem.zeroPageRAM[..]));
+
+    readIORegisters(mem, &core[CORE_IO_REGISTERS..CORE_IO_REGISTERS + NUM_IO_REGISTERS]);
+
+    Ok(())
+}
+
+//applies 0xFF00-0xFF7F.  Registers whose writes have side effects are set directly
+fn readIORegisters(mem: &mut MemoryMapState, registers: &[u8]) {
+    let register = |addr: u16| registers[(addr - 0xFF00) as usize];
+
+    //NR52 has to be written first so the APU is powered on for the other sound registers
+    writeByteToMemory(mem, register(0xFF26), 0xFF26);
+
+    for addr in 0xFF00..0xFF80u16 {
+        let byte = register(addr);
+
+        match addr {
+            0xFF04 => mem.timer.internalCounter = (byte as u16) << 8,
+            0xFF05 => mem.timer.tima = byte,
+            //writing TAC can clock TIMA, which would change the TIMA that was just restored
+            0xFF07 => {
+                mem.timer.isEnabled = testBit!(byte, 2);
+                mem.timer.clockSelect = byte & 3;
+            },
+            0xFF26 => {},
+            //restore SC without asking the serial device for a byte
+            0xFF02 => {
+                mem.serial.isTransferring = testBit!(byte, 7);
+                mem.serial.isInternalClock = testBit!(byte, 0);
+            },
+            //don't retrigger sound channels
+            0xFF14 | 0xFF19 | 0xFF1E | 0xFF23 => writeByteToMemory(mem, byte & 0x7F, addr),
+            0xFF41 => {
+                writeByteToMemory(mem, byte, addr);
+                mem.lcd.mode = match byte & 0x3 {
+                    0 => LCDMode::HBlank,
+                    1 => LCDMode::VBlank,
+                    2 => LCDMode::ScanOAM,
+                    _ => LCDMode::ScanVRAMAndOAM
+                };
+                mem.lcd.modeClock = 0;
+            },
+            0xFF44 => mem.lcd.currScanLine = byte,
+            //restore the DMA source without starting a transfer
+            0xFF46 => {
+                mem.currentDMAAddress = (byte as u16) << 8;
+                mem.isDMAOccurring = false;
+                mem.currentDMACycles = 0;
+            },
+            0xFF50 => mem.inBios = (byte & 1) == 0,
+            _ => writeByteToMemory(mem, byte, addr)
+        }
+    }
+
+    //channel status bits in NR52 are read only, so turn the channels back on directly
+    let nr52 = register(0xFF26);
+    let apu = &mut mem.apu;
+
+    apu.square1.isEnabled = apu.isEnabled && testBit!(nr52, 0);
+    apu.square2.isEnabled = apu.isEnabled && testBit!(nr52, 1);
+    apu.wave.isEnabled = apu.isEnabled && testBit!(nr52, 2);
+    apu.noise.isEnabled = apu.isEnabled && testBit!(nr52, 3);
+
+    apu.square1.envelope.volume = apu.square1.envelope.initialVolume;
+    apu.square2.envelope.volume = apu.square2.envelope.initialVolume;
+    apu.noise.envelope.volume = apu.noise.envelope.initialVolume;
+}
@@ -0,0 +1,155 @@
+/*
+ * What the CPU sees of the rest of the system.  The CPU does every read and write through a Bus
+ * and tells it how many cycles have passed, so it can run on the full memory map or on something
+ * simpler, like a flat 64KB RAM for testing instructions
+ *
+ */
+use gb_memory::*;
+use gb_lcd::*;
+use gb_apu::*;
+use gb_timer::*;
+use gb_serial::*;
+
+pub trait Bus {
+    fn read(&mut self, addr: u16) -> u8;
+    fn write(&mut self, byte: u8, addr: u16);
+
+    //Advances everything besides the CPU by the number of cycles that have passed
+    fn tick(&mut self, cycles: u32);
+
+    //IE & IF.  Checking for interrupts is not a memory access, so it is kept out of read and write
+    fn pending_interrupts(&self) -> u8;
+    //Clears an interrupt's bit in IF once the CPU starts handling it
+    fn acknowledge_interrupt(&mut self, interrupt: u8);
+}
+
+impl Bus for MemoryMapState {
+    fn read(&mut self, addr: u16) -> u8 {
+        readByteFromMemory(self, addr)
+    }
+
+    fn write(&mut self, byte: u8, addr: u16) {
+        writeByteToMemory(self, byte, addr)
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        stepDMA(self, cycles);
+        stepTimer(&mut self.timer, &mut self.requestedInterrupts, cycles);
+        stepSerial(&mut self.serial, &mut self.requestedInterrupts, cycles);
+        stepLCD(&mut self.lcd, &mut self.requestedInterrupts, cycles);
+        stepAPU(&mut self.apu, dividerRegister(&self.timer), cycles);
+        self.cartridge.step(cycles);
+    }
+
+    fn pending_interrupts(&self) -> u8 {
+        self.enabledInterrupts & self.requestedInterrupts
+    }
+
+    fn acknowledge_interrupt(&mut self, interrupt: u8) {
+        self.requestedInterrupts &= !interrupt;
+    }
+}
+
+//64KB of plain RAM with nothing mapped into it.  Every address can be read and written.
+//IE and IF are just the bytes at 0xFFFF and 0xFF0F
+pub struct FlatBus {
+    pub memory: Vec<u8>
+}
+
+impl FlatBus {
+    pub fn new() -> FlatBus {
+        FlatBus {
+            memory: vec![0; 0x10000]
+        }
+    }
+}
+
+impl Bus for FlatBus {
+    fn read(&mut self, addr: u16) -> u8 {
+        self.memory[addr as usize]
+    }
+
+    fn write(&mut self, byte: u8, addr: u16) {
+        self.memory[addr as usize] = byte;
+    }
+
+    fn tick(&mut self, _cycles: u32) {}
+
+    fn pending_interrupts(&self) -> u8 {
+        self.memory[0xFFFF] & self.memory[0xFF0F]
+    }
+
+    fn acknowledge_interrupt(&mut self, interrupt: u8) {
+        self.memory[0xFF0F] &= !interrupt;
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum BusAccessKind {
+    Read,
+    Write
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct BusAccess {
+    pub cycle: u64, //cycles ticked before the access
+    pub kind: BusAccessKind,
+    pub addr: u16,
+    pub value: u8 //byte read or written
+}
+
+//Wraps another bus and logs every access along with the cycle it happened on
+pub struct RecordingBus<B: Bus> {
+    pub bus: B,
+    pub cycle: u64,
+    pub accesses: Vec<BusAccess>
+}
+
+impl<B: Bus> RecordingBus<B> {
+    pub fn new(bus: B) -> RecordingBus<B> {
+        RecordingBus {
+            bus: bus,
+            cycle: 0,
+            accesses: vec![]
+        }
+    }
+}
+
+impl<B: Bus> Bus for RecordingBus<B> {
+    fn read(&mut self, addr: u16) -> u8 {
+        let value = self.bus.read(addr);
+
+        self.accesses.push(BusAccess {
+            cycle: self.cycle,
+            kind: BusAccessKind::Read,
+            addr: addr,
+            value: value
+        });
+
+        value
+    }
+
+    fn write(&mut self, byte: u8, addr: u16) {
+        self.accesses.push(BusAccess {
+            cycle: self.cycle,
+            kind: BusAccessKind::Write,
+            addr: addr,
+            value: byte
+        });
+
+        self.bus.write(byte, addr);
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        self.cycle += cycles as u64;
+        self.bus.tick(cycles);
+    }
+
+    fn pending_interrupts(&self) -> u8 {
+        self.bus.pending_interrupts()
+    }
+
+    fn acknowledge_interrupt(&mut self, interrupt: u8) {
+        self.bus.acknowledge_interrupt(interrupt);
+    }
+}
@@ -0,0 +1,234 @@
+/*
+ * Parses and validates the cartridge header found at 0x100-0x14F of every ROM
+ *
+ */
+use std::fmt;
+use gb_util::*;
+
+pub const HEADER_END: usize = 0x150;
+
+const LOGO_ADDR: usize = 0x104;
+const TITLE_ADDR: usize = 0x134;
+const TITLE_END: usize = 0x144;
+const MANUFACTURER_CODE_ADDR: usize = 0x13F;
+const CGB_FLAG_ADDR: usize = 0x143;
+const NEW_LICENSEE_CODE_ADDR: usize = 0x144;
+const SGB_FLAG_ADDR: usize = 0x146;
+const CARTRIDGE_TYPE_ADDR: usize = 0x147;
+const ROM_SIZE_ADDR: usize = 0x148;
+const RAM_SIZE_ADDR: usize = 0x149;
+const OLD_LICENSEE_CODE_ADDR: usize = 0x14B;
+const VERSION_ADDR: usize = 0x14C;
+const HEADER_CHECKSUM_ADDR: usize = 0x14D;
+const GLOBAL_CHECKSUM_ADDR: usize = 0x14E;
+
+//old licensee code that means the new licensee code should be used instead
+const USE_NEW_LICENSEE_CODE: u8 = 0x33;
+
+//the boot ROM refuses to run a cartridge that doesn't have this exact logo
+pub const NINTENDO_LOGO: [u8; 48] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E
+];
+
+#[derive(PartialEq, Debug)]
+pub enum CartridgeHeaderError {
+    ROMTooSmall(usize),
+    InvalidROMSize(u8),
+    InvalidLogo,
+    HeaderChecksumMismatch { expected: u8, actual: u8 },
+    GlobalChecksumMismatch { expected: u16, actual: u16 }
+}
+
+impl fmt::Display for CartridgeHeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CartridgeHeaderError::ROMTooSmall(size) =>
+                write!(f, "ROM is too small to have a header: {} bytes", size),
+            CartridgeHeaderError::InvalidROMSize(code) =>
+                write!(f, "Invalid ROM size in header: {:X}", code),
+            CartridgeHeaderError::InvalidLogo =>
+                write!(f, "Nintendo logo in header does not match"),
+            CartridgeHeaderError::HeaderChecksumMismatch { expected, actual } =>
+                write!(f, "Header checksum mismatch. Expected: {:X}, Actual: {:X}", expected, actual),
+            CartridgeHeaderError::GlobalChecksumMismatch { expected, actual } =>
+                write!(f, "Global checksum mismatch. Expected: {:X}, Actual: {:X}", expected, actual)
+        }
+    }
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum CGBSupport {
+    DMGOnly,
+    CGBEnhanced, //works on both DMG and CGB
+    CGBOnly
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum LicenseeCode {
+    Old(u8),
+    New(String)
+}
+
+#[derive(Clone, Debug)]
+pub struct CartridgeHeader {
+    pub title: String,
+    pub manufacturerCode: String, //only on newer cartridges.  Empty otherwise
+    pub cgbSupport: CGBSupport,
+    pub isSGBSupported: bool,
+    pub licenseeCode: LicenseeCode,
+    pub cartridgeType: u8,
+    pub romSize: usize, //in bytes
+    pub ramSize: usize, //in bytes.  Does not include RAM built into the MBC
+    pub version: u8,
+    pub headerChecksum: u8,
+    pub globalChecksum: u16
+}
+
+impl CartridgeHeader {
+    //battery backed carts keep their RAM (and clock) between power cycles
+    pub fn hasBattery(&self) -> bool {
+        match self.cartridgeType {
+            0x3 | 0x6 | 0x9 | 0xD | 0xF | 0x10 | 0x13 | 0x1B | 0x1E | 0x22 | 0xFF => true,
+            _ => false
+        }
+    }
+}
+
+//reads printable ASCII up to the first null byte
+fn headerString(bytes: &[u8]) -> String {
+    bytes.iter()
+         .take_while(|b| **b != 0)
+         .map(|b| if *b >= 0x20 && *b < 0x7F { *b as char } else { '?' })
+         .collect::<String>()
+         .trim_right()
+         .to_string()
+}
+
+fn romSizeFromCode(code: u8) -> Result<usize, CartridgeHeaderError> {
+    match code {
+        0...8 => Ok(0x8000 << code),
+        //unofficial sizes that are listed in some docs
+        0x52 => Ok(72 * 0x4000),
+        0x53 => Ok(80 * 0x4000),
+        0x54 => Ok(96 * 0x4000),
+        _ => Err(CartridgeHeaderError::InvalidROMSize(code))
+    }
+}
+
+//unknown codes are treated as no RAM so the cartridge can still be played
+fn ramSizeFromCode(code: u8) -> usize {
+    match code {
+        0 => 0,
+        1 => 0x800,
+        2 => 0x2000,
+        3 => 0x8000,
+        4 => 0x20000,
+        5 => 0x10000,
+        _ => {
+            println!("Warning: unknown RAM size in header: {:X}. Assuming no cartridge RAM", code);
+            0
+        }
+    }
+}
+
+/*
+ * Parses the header of a ROM.  Does not validate the logo or checksums.
+ * Use validateCartridgeHeader() for that
+ *
+ * Args:
+ *      romData: The entire contents of the ROM
+ *
+ * Return: The parsed header or why it could not be parsed
+ *
+ */
+pub fn parseCartridgeHeader(romData: &[u8]) -> Result<CartridgeHeader, CartridgeHeaderError> {
+    if romData.len() < HEADER_END {
+        return Err(CartridgeHeaderError::ROMTooSmall(romData.len()));
+    }
+
+    let cgbSupport = match romData[CGB_FLAG_ADDR] {
+        0x80 => CGBSupport::CGBEnhanced,
+        0xC0 => CGBSupport::CGBOnly,
+        _ => CGBSupport::DMGOnly
+    };
+
+    //newer cartridges use the end of the title for the manufacturer code and CGB flag
+    let (title, manufacturerCode) = match cgbSupport {
+        CGBSupport::DMGOnly => (headerString(&romData[TITLE_ADDR..TITLE_END]), String::new()),
+        _ => (headerString(&romData[TITLE_ADDR..MANUFACTURER_CODE_ADDR]),
+              headerString(&romData[MANUFACTURER_CODE_ADDR..CGB_FLAG_ADDR]))
+    };
+
+    let licenseeCode = match romData[OLD_LICENSEE_CODE_ADDR] {
+        USE_NEW_LICENSEE_CODE => LicenseeCode::New(headerString(&romData[NEW_LICENSEE_CODE_ADDR..SGB_FLAG_ADDR])),
+        code => LicenseeCode::Old(code)
+    };
+
+    Ok(CartridgeHeader {
+        title: title,
+        manufacturerCode: manufacturerCode,
+        cgbSupport: cgbSupport,
+        isSGBSupported: romData[SGB_FLAG_ADDR] == 0x3,
+        licenseeCode: licenseeCode,
+        cartridgeType: romData[CARTRIDGE_TYPE_ADDR],
+        romSize: try!(romSizeFromCode(romData[ROM_SIZE_ADDR])),
+        ramSize: ramSizeFromCode(romData[RAM_SIZE_ADDR]),
+        version: romData[VERSION_ADDR],
+        headerChecksum: romData[HEADER_CHECKSUM_ADDR],
+        globalChecksum: word(romData[GLOBAL_CHECKSUM_ADDR], romData[GLOBAL_CHECKSUM_ADDR + 1])
+    })
+}
+
+//checksum of 0x134-0x14C that the boot ROM verifies
+pub fn calculateHeaderChecksum(romData: &[u8]) -> u8 {
+    romData[TITLE_ADDR..HEADER_CHECKSUM_ADDR].iter()
+        .fold(0u8, |checksum, byte| checksum.wrapping_sub(*byte).wrapping_sub(1))
+}
+
+//sum of every byte in the ROM except the global checksum itself.  Never checked by hardware
+pub fn calculateGlobalChecksum(romData: &[u8]) -> u16 {
+    romData.iter()
+        .enumerate()
+        .filter(|&(i, _)| i != GLOBAL_CHECKSUM_ADDR && i != GLOBAL_CHECKSUM_ADDR + 1)
+        .fold(0u16, |checksum, (_, byte)| checksum.wrapping_add(*byte as u16))
+}
+
+/*
+ * Checks the logo, header checksum and global checksum of a ROM against its header
+ *
+ * Args:
+ *      header: Header parsed from romData
+ *      romData: The entire contents of the ROM
+ *
+ * Return: The first check that failed, if any
+ *
+ */
+pub fn validateCartridgeHeader(header: &CartridgeHeader, romData: &[u8]) -> Result<(), CartridgeHeaderError> {
+    if romData.len() < HEADER_END {
+        return Err(CartridgeHeaderError::ROMTooSmall(romData.len()));
+    }
+
+    if &romData[LOGO_ADDR..LOGO_ADDR + NINTENDO_LOGO.len()] != &NINTENDO_LOGO[..] {
+        return Err(CartridgeHeaderError::InvalidLogo);
+    }
+
+    let headerChecksum = calculateHeaderChecksum(romData);
+    if headerChecksum != header.headerChecksum {
+        return Err(CartridgeHeaderError::HeaderChecksumMismatch {
+            expected: header.headerChecksum,
+            actual: headerChecksum
+        });
+    }
+
+    let globalChecksum = calculateGlobalChecksum(romData);
+    if globalChecksum != header.globalChecksum {
+        return Err(CartridgeHeaderError::GlobalChecksumMismatch {
+            expected: header.globalChecksum,
+            actual: globalChecksum
+        });
+    }
+
+    Ok(())
+}
@@ -4,7 +4,7 @@
  */
 use std::fmt;
 
-use gb_memory::*;
+use gb_bus::*;
 use gb_util::*;
 use gb_debug::*;
 
@@ -25,9 +25,11 @@ pub struct CPUState {
     pub L: u8,
     pub totalCycles: u64, //total cycles since game has been loaded
     pub instructionCycles: u32, //number of cycles in a given instruction
+    pub lastOpcode: Option<u8>, //opcode executed by the last step.  None if the CPU was halted
 
     pub enableInterrupts: bool,
-    pub isHalted: bool
+    pub isHalted: bool,
+    pub isHaltBugTriggered: bool //PC fails to increment after the next opcode is read
 }
 
 impl CPUState {
@@ -45,9 +47,11 @@ impl CPUState {
             L: 0,
             totalCycles: 0,
             instructionCycles: 0,
+            lastOpcode: None,
 
             enableInterrupts: false,
-            isHalted: false
+            isHalted: false,
+            isHaltBugTriggered: false
         }
     }
 }
@@ -74,37 +78,122 @@ pub enum Flag {
 //addresses of interrupt service routines in order of priority
 static ISRs: [u16;5] = [0x40, 0x48, 0x50, 0x58, 0x60]; 
 
-pub fn stepCPU(cpu: &mut CPUState, mem: &mut MemoryMapState) {
+//2 machine cycles of waiting, 2 to push PC and 1 to jump to the ISR
+const INTERRUPT_DISPATCH_CYCLES: u32 = 20;
+
+const CYCLES_PER_MACHINE_CYCLE: u32 = 4;
+
+/*
+ * Every memory access the CPU makes takes a machine cycle, so this ticks the rest of the system by
+ * 4 cycles after each read and write.  That way whatever the CPU reads mid-instruction (LY, STAT, the timer...)
+ * is as far along as it would be on hardware.
+ *
+ * Instructions that take longer than their memory accesses (e.g. INC BC) tick the rest at the end
+ */
+struct MachineCycleBus<'a, B: Bus + 'a> {
+    bus: &'a mut B,
+    cyclesTicked: u32 //cycles ticked since the start of the step
+}
+
+impl<'a, B: Bus> MachineCycleBus<'a, B> {
+    fn new(bus: &'a mut B) -> MachineCycleBus<'a, B> {
+        MachineCycleBus {
+            bus: bus,
+            cyclesTicked: 0
+        }
+    }
+
+    //ticks whatever is left to reach the given number of cycles since the start of the step
+    fn tickUntil(&mut self, cycles: u32) {
+        if cycles > self.cyclesTicked {
+            let cyclesLeft = cycles - self.cyclesTicked;
+            self.tick(cyclesLeft);
+        }
+    }
+}
+
+impl<'a, B: Bus> Bus for MachineCycleBus<'a, B> {
+    fn read(&mut self, addr: u16) -> u8 {
+        let byte = self.bus.read(addr);
+        self.tick(CYCLES_PER_MACHINE_CYCLE);
+
+        byte
+    }
+
+    fn write(&mut self, byte: u8, addr: u16) {
+        self.bus.write(byte, addr);
+        self.tick(CYCLES_PER_MACHINE_CYCLE);
+    }
+
+    fn tick(&mut self, cycles: u32) {
+        self.cyclesTicked += cycles;
+        self.bus.tick(cycles);
+    }
+
+    fn pending_interrupts(&self) -> u8 {
+        self.bus.pending_interrupts()
+    }
+
+    fn acknowledge_interrupt(&mut self, interrupt: u8) {
+        self.bus.acknowledge_interrupt(interrupt);
+    }
+}
+
+/*
+ * Runs one instruction, or one idle machine cycle if halted.  The bus gets ticked along with every
+ * memory access, so the rest of the system is caught up by the time this returns
+ *
+ * Args:
+ *      cpu: The CPU to step
+ *      mem: Everything the CPU is connected to
+ *
+ */
+pub fn stepCPU<B: Bus>(cpu: &mut CPUState, mem: &mut B) {
+    let mut mem = MachineCycleBus::new(mem);
     let mut isHandlingInterrupt = false;
+    let interruptsToHandle = mem.pending_interrupts();
 
-    if cpu.enableInterrupts {
-        let interruptsToHandle = mem.enabledInterrupts & mem.requestedInterrupts;
+    //a pending interrupt wakes the CPU up even if interrupts are disabled.
+    //It just doesn't get handled, so the CPU carries on after the HALT
+    if interruptsToHandle != 0 {
+        cpu.isHalted = false;
+    }
 
-        if interruptsToHandle != 0 {
-            isHandlingInterrupt = true;
-            cpu.isHalted = false; //no longer halted when servicing interrupt
+    if cpu.enableInterrupts && interruptsToHandle != 0 {
+        isHandlingInterrupt = true;
 
-            pushOnToStack(mem, cpu.PC, &mut cpu.SP); //Save PC
+        //pushOnToStack waits out the second of the 2 waiting machine cycles
+        mem.tick(CYCLES_PER_MACHINE_CYCLE);
+        pushOnToStack(&mut mem, cpu.PC, &mut cpu.SP); //Save PC
 
-            for (i, ISR) in ISRs.iter().enumerate() {
-                if (interruptsToHandle & (1 << i)) != 0 {
-                    cpu.PC = *ISR;
+        for (i, ISR) in ISRs.iter().enumerate() {
+            if (interruptsToHandle & (1 << i)) != 0 {
+                cpu.PC = *ISR;
 
-                    //turn off request bit since we are handling the interrupt
-                    mem.requestedInterrupts &= !(1 << i);
-                    cpu.enableInterrupts = false;
-                    break;
-                }
+                //turn off request bit since we are handling the interrupt
+                mem.acknowledge_interrupt(1 << i);
+                cpu.enableInterrupts = false;
+                break;
             }
         }
+
+        mem.tickUntil(INTERRUPT_DISPATCH_CYCLES);
     }
 
     if !cpu.isHalted {
-        let instructionToExecute = readByteFromMemory(mem, cpu.PC);
+        let instructionToExecute = mem.read(cpu.PC);
+
+        //HALT bug: PC didn't get incremented after reading the opcode, so the opcode's
+        //byte gets read again as the next byte of the instruction (or as the next opcode)
+        if cpu.isHaltBugTriggered {
+            cpu.isHaltBugTriggered = false;
+            cpu.PC = cpu.PC.wrapping_sub(1);
+        }
 
         gbDebugInsertExecutionState(instructionToExecute, cpu);
 
-        let (newPC, cyclesTaken) = executeInstruction(instructionToExecute, cpu, mem); 
+        let (newPC, cyclesTaken) = executeInstruction(instructionToExecute, cpu, &mut mem); 
+        cpu.lastOpcode = Some(instructionToExecute);
 
 
 
@@ -114,34 +203,17 @@ pub fn stepCPU(cpu: &mut CPUState, mem: &mut MemoryMapState) {
 
         cpu.PC = newPC;
 
-        //Handling interrupts takes 20 cycles to just set up handling
-        cpu.instructionCycles = if isHandlingInterrupt {cyclesTaken.wrapping_add(20)} else {cyclesTaken};
-        cpu.totalCycles = cpu.totalCycles.wrapping_add(cyclesTaken as u64);
-        
-        if mem.isDMAOccurring {
-            mem.currentDMACycles += cpu.instructionCycles;
-
-            if mem.currentDMACycles > CYCLES_PER_DMA_BYTE {
-
-                //not finished transferring
-                if (mem.currentDMAAddress & 0xFF) < 0xA0 {
-                    let byteToWrite =  readByteFromMemory(mem, mem.currentDMAAddress);
-                    let destAddr = 0xFE00 + (mem.currentDMAAddress & 0xFF);
-                    //println!("Transferring: {:X} from address {:X}, to address: {:X}", byteToWrite, mem.currentDMAAddress, destAddr); 
-                    writeByteToMemory(mem, byteToWrite, destAddr);
-                    mem.currentDMACycles -= CYCLES_PER_DMA_BYTE;
-                    mem.currentDMAAddress = mem.currentDMAAddress.wrapping_add(1); 
-                }
-                //finished transferring
-                else {
-                    mem.isDMAOccurring = false;
-                    mem.currentDMACycles = 0;
-                    mem.currentDMAAddress = 0;
-                }
-            }
-        }
+        cpu.instructionCycles = if isHandlingInterrupt {cyclesTaken.wrapping_add(INTERRUPT_DISPATCH_CYCLES)} else {cyclesTaken};
     }
+    else {
+        cpu.instructionCycles = CYCLES_PER_MACHINE_CYCLE; //a halted CPU idles one machine cycle at a time
+        cpu.lastOpcode = None;
+    }
+
+    cpu.totalCycles = cpu.totalCycles.wrapping_add(cpu.instructionCycles as u64);
 
+    //internal cycles that come after the last memory access
+    mem.tickUntil(cpu.instructionCycles);
 }
 
 
@@ -157,10 +229,22 @@ pub fn isFlagSet(flag: Flag, F: u8) -> bool {
     flag as u8 & F != 0
 }
 
-//return number of bytes to increment PC by
-fn loadImm16(highDest: &mut u8, lowDest: &mut u8, PC: u16, mem: &MemoryMapState){
-    *highDest = readByteFromMemory(mem, PC.wrapping_add(2));
-    *lowDest = readByteFromMemory(mem, PC.wrapping_add(1));
+//reads a little endian word, low byte first
+fn readWord<B: Bus>(mem: &mut B, addr: u16) -> u16 {
+    let low = mem.read(addr);
+    let high = mem.read(addr.wrapping_add(1));
+
+    word(high, low)
+}
+
+fn writeWord<B: Bus>(mem: &mut B, value: u16, addr: u16) {
+    mem.write(lb(value), addr);
+    mem.write(hb(value), addr.wrapping_add(1));
+}
+
+fn loadImm16<B: Bus>(highDest: &mut u8, lowDest: &mut u8, PC: u16, mem: &mut B){
+    *lowDest = mem.read(PC.wrapping_add(1));
+    *highDest = mem.read(PC.wrapping_add(2));
 }
 
 /*
@@ -172,9 +256,15 @@ fn loadImm16(highDest: &mut u8, lowDest: &mut u8, PC: u16, mem: &MemoryMapState)
  *      SP: the stack pointer
  *
  */
-fn pushOnToStack(mem: &mut MemoryMapState, value: u16, SP: &mut u16) {
-    *SP = SP.wrapping_sub(2);
-    writeWordToMemory(mem, value, *SP);
+fn pushOnToStack<B: Bus>(mem: &mut B, value: u16, SP: &mut u16) {
+    //SP gets decremented during a machine cycle before anything is written
+    mem.tick(CYCLES_PER_MACHINE_CYCLE);
+
+    //high byte goes first
+    *SP = SP.wrapping_sub(1);
+    mem.write(hb(value), *SP);
+    *SP = SP.wrapping_sub(1);
+    mem.write(lb(value), *SP);
 }
 
 /*
@@ -187,8 +277,8 @@ fn pushOnToStack(mem: &mut MemoryMapState, value: u16, SP: &mut u16) {
  * Return: The 16bit value off of the stack
  *
  */
-fn popOffOfStack(mem: &MemoryMapState, SP: &mut u16) -> u16 {
-    let ret = readWordFromMemory(mem, *SP);
+fn popOffOfStack<B: Bus>(mem: &mut B, SP: &mut u16) -> u16 {
+    let ret = readWord(mem, *SP);
     *SP = SP.wrapping_add(2);
 
     ret
@@ -207,7 +297,7 @@ fn disableInterrupts(cpu: &mut CPUState) {
 //NOTE(DanB) the reason I return these values instead of modifying them is because I constantly
 //forget to update the PC and cycles passed.  This way, the compiler will force me to do so.
 //Perhaps I can find a better way
-pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryMapState) -> (u16, u32) {
+pub fn executeInstruction<B: Bus>(instruction: u8, cpu: &mut CPUState, mem: &mut B) -> (u16, u32) {
 
     use self::Flag::*;
 
@@ -487,9 +577,11 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
     macro_rules! jumpRelative {
         ($condition: expr) => ({
 
+            //the offset gets read whether or not the jump is taken
+            let offset = mem.read(cpu.PC.wrapping_add(1)) as i8;
+
             //whether or not to do the actual jump
             if $condition {
-                let offset = readByteFromMemory(&mem, cpu.PC.wrapping_add(1)) as i8;
                 (((cpu.PC as i16).wrapping_add(offset as i16)).wrapping_add(2) as u16, 12)
             }
             else {
@@ -551,10 +643,12 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
      */
     macro_rules! jumpAbsolute {
         ($condition: expr) => ({
-            
+            //the address gets read whether or not the jump is taken
+            let addr = readWord(mem, cpu.PC.wrapping_add(1));
+
             //should we perform the jump?
             if $condition {
-                (readWordFromMemory(&mem, cpu.PC.wrapping_add(1)), 16)
+                (addr, 16)
             }
             else {
                 (cpu.PC.wrapping_add(3), 12)
@@ -563,7 +657,7 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
     }
 
     /*
-     * Performs the RET series of instructions
+     * Performs the conditional RET instructions
      *
      * Args:
      *      condition: whether to return from the procedure or not.
@@ -574,6 +668,9 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
      */
     macro_rules! returnFromProc {
         ($condition: expr) => ({
+            //checking the condition takes a machine cycle
+            mem.tick(CYCLES_PER_MACHINE_CYCLE);
+
             if $condition {
                 //pop return address off stack
                 (popOffOfStack(mem, &mut cpu.SP), 20)
@@ -597,12 +694,15 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
      */
     macro_rules! callProc {
         ($condition: expr) => ({
+            //the address gets read whether or not the call is made
+            let addr = readWord(mem, cpu.PC.wrapping_add(1));
+
             if $condition {
                 //save PC
                 pushOnToStack(mem, cpu.PC.wrapping_add(3), &mut cpu.SP);
 
                 //jump to procedure
-                (readWordFromMemory(mem, cpu.PC.wrapping_add(1)), 24)
+                (addr, 24)
             }
             else {
                 (cpu.PC.wrapping_add(3), 12)
@@ -640,7 +740,7 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
         () => ({
 
             //the "as i8 as i32" propagates the sign bit
-            let addend = readByteFromMemory(mem, cpu.PC.wrapping_add(1)) as i8  as i32;
+            let addend = mem.read(cpu.PC.wrapping_add(1)) as i8  as i32;
             let signedSP = cpu.SP as i32;
             let sum = signedSP.wrapping_add(addend);
 
@@ -816,11 +916,11 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
             (cpu.PC.wrapping_add(1),4)
         }, 
         0x1 => { //LD BC, NN
-            loadImm16(&mut cpu.B, &mut cpu.C, cpu.PC, &mem);
+            loadImm16(&mut cpu.B, &mut cpu.C, cpu.PC, mem);
             (cpu.PC.wrapping_add(3), 12)
         },
         0x2 => { //LD (BC), A
-            writeByteToMemory(mem, cpu.A, word(cpu.B, cpu.C));
+            mem.write(cpu.A, word(cpu.B, cpu.C));
             (cpu.PC.wrapping_add(1), 8)
         },
         0x3 => { //INC BC
@@ -835,7 +935,7 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
         },
 
         0x6 => { //LD B, d8
-            cpu.B = readByteFromMemory(&mem, cpu.PC.wrapping_add(1));
+            cpu.B = mem.read(cpu.PC.wrapping_add(1));
             (cpu.PC.wrapping_add(2), 8)
         },
 
@@ -845,9 +945,9 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
         },
 
         0x8 => { //LD (a16), SP
-            let addr = readWordFromMemory(mem, cpu.PC.wrapping_add(1));
+            let addr = readWord(mem, cpu.PC.wrapping_add(1));
 
-            writeWordToMemory(mem, cpu.SP, addr);
+            writeWord(mem, cpu.SP, addr);
 
             (cpu.PC.wrapping_add(3), 20)
 
@@ -858,7 +958,7 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
         },
 
         0xA => { //LD A, (BC)
-            cpu.A = readByteFromMemory(mem, word(cpu.B, cpu.C));
+            cpu.A = mem.read(word(cpu.B, cpu.C));
             (cpu.PC.wrapping_add(1), 8)
         },
 
@@ -875,7 +975,7 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
         },
         
         0xE => { //LD C, d8
-            cpu.C = readByteFromMemory(&mem, cpu.PC.wrapping_add(1));
+            cpu.C = mem.read(cpu.PC.wrapping_add(1));
             (cpu.PC.wrapping_add(2), 8)
         },
 
@@ -886,18 +986,19 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
 
         0x10 => { //STOP 0
             //TODO: To be implemented
-            gbDebugAssert!(readByteFromMemory(&mem, cpu.PC.wrapping_add(1)) == 0, 
-                           "STOP must be called with 0"); //next byte should be 0
-            (cpu.PC.wrapping_add(2), 4)
+            //the operand is read outside the assert so debug and release builds tick the bus the same
+            let operand = mem.read(cpu.PC.wrapping_add(1));
+            gbDebugAssert!(operand == 0, "STOP must be called with 0"); //next byte should be 0
+            (cpu.PC.wrapping_add(2), 8)
         },
 
         0x11 => { //LD DE, d16
-            loadImm16(&mut cpu.D, &mut cpu.E, cpu.PC, &mem);
+            loadImm16(&mut cpu.D, &mut cpu.E, cpu.PC, mem);
             (cpu.PC.wrapping_add(3), 12) 
         },
 
         0x12 => { //LD (BC), A
-            writeByteToMemory(mem, cpu.A, word(cpu.D, cpu.E));
+            mem.write(cpu.A, word(cpu.D, cpu.E));
             (cpu.PC.wrapping_add(1), 8)
         },
 
@@ -914,7 +1015,7 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
         },
 
         0x16 => { //LD D, d8
-            cpu.D = readByteFromMemory(&mem, cpu.PC.wrapping_add(1));
+            cpu.D = mem.read(cpu.PC.wrapping_add(1));
             (cpu.PC.wrapping_add(2), 8)
         },
 
@@ -932,7 +1033,7 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
         },
 
         0x1A => { //LD A, (DE)
-            cpu.A = readByteFromMemory(mem, word(cpu.D, cpu.E));
+            cpu.A = mem.read(word(cpu.D, cpu.E));
             (cpu.PC.wrapping_add(1), 8)
 
         },
@@ -950,7 +1051,7 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
         },
 
         0x1E => { //LD E, d8
-            cpu.E = readByteFromMemory(&mem, cpu.PC.wrapping_add(1));
+            cpu.E = mem.read(cpu.PC.wrapping_add(1));
             (cpu.PC.wrapping_add(2), 8)
         },
 
@@ -965,12 +1066,12 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
         },
 
         0x21 => { //LD HL, d16
-            loadImm16(&mut cpu.H, &mut cpu.L, cpu.PC, &mem);
+            loadImm16(&mut cpu.H, &mut cpu.L, cpu.PC, mem);
             (cpu.PC.wrapping_add(3), 12) 
         },
         
         0x22 => { //LD (HL+), A
-            writeByteToMemory(mem, cpu.A, word(cpu.H, cpu.L));
+            mem.write(cpu.A, word(cpu.H, cpu.L));
             increment16!(H,L);
             (cpu.PC.wrapping_add(1), 8)
         },
@@ -988,7 +1089,7 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
         },
         
         0x26 => { //LD H, d8
-            cpu.H = readByteFromMemory(&mem, cpu.PC.wrapping_add(1));
+            cpu.H = mem.read(cpu.PC.wrapping_add(1));
             (cpu.PC.wrapping_add(2), 8)
         },
 
@@ -1045,7 +1146,7 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
         },
         
         0x2A => { //LD A, (HL+)
-            cpu.A = readByteFromMemory(mem, word(cpu.H, cpu.L));
+            cpu.A = mem.read(word(cpu.H, cpu.L));
             increment16!(H,L);
             (cpu.PC.wrapping_add(1), 8)
 
@@ -1064,7 +1165,7 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
         },
         
         0x2E => { //LD L, d8
-            cpu.L = readByteFromMemory(&mem, cpu.PC.wrapping_add(1));
+            cpu.L = mem.read(cpu.PC.wrapping_add(1));
             (cpu.PC.wrapping_add(2), 8)
         },
 
@@ -1082,12 +1183,12 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
         },
 
         0x31 => { //LD SP, d16
-            cpu.SP = readWordFromMemory(&mem, cpu.PC.wrapping_add(1));
+            cpu.SP = readWord(mem, cpu.PC.wrapping_add(1));
             (cpu.PC.wrapping_add(3), 12)
         },
         
         0x32 => { //LD (HL-), A
-            writeByteToMemory(mem, cpu.A, word(cpu.H, cpu.L));
+            mem.write(cpu.A, word(cpu.H, cpu.L));
             decrement16!(H,L);
             (cpu.PC.wrapping_add(1), 8)
         },
@@ -1099,7 +1200,7 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
 
         0x34 => { //INC (HL)
 
-            let val = readByteFromMemory(mem, word(cpu.H, cpu.L)).wrapping_add(1); //incremented value
+            let val = mem.read(word(cpu.H, cpu.L)).wrapping_add(1); //incremented value
 
             match val {
                 0 => setFlag(Zero, &mut cpu.F),
@@ -1113,7 +1214,7 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
                 _ => clearFlag(Half, &mut cpu.F)
             };
 
-            writeByteToMemory(mem, val, word(cpu.H, cpu.L));
+            mem.write(val, word(cpu.H, cpu.L));
 
             (cpu.PC.wrapping_add(1), 12)
 
@@ -1121,7 +1222,7 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
         
         0x35 => { //DEC (HL)
 
-            let val = readByteFromMemory(&mem, word(cpu.H, cpu.L)).wrapping_sub(1); //decremented value
+            let val = mem.read(word(cpu.H, cpu.L)).wrapping_sub(1); //decremented value
 
             match val {
                 0 => setFlag(Zero, &mut cpu.F),
@@ -1135,7 +1236,7 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
                 _ => clearFlag(Half, &mut cpu.F)
             };
 
-            writeByteToMemory(mem, val, word(cpu.H, cpu.L));
+            mem.write(val, word(cpu.H, cpu.L));
 
             (cpu.PC.wrapping_add(1), 12)
 
@@ -1143,8 +1244,8 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
 
         0x36 => { //LD (HL), d8
 
-            let val = readByteFromMemory(mem, cpu.PC.wrapping_add(1)); //value from memory
-            writeByteToMemory(mem, val, word(cpu.H, cpu.L));
+            let val = mem.read(cpu.PC.wrapping_add(1)); //value from memory
+            mem.write(val, word(cpu.H, cpu.L));
 
             (cpu.PC.wrapping_add(2), 12)
 
@@ -1168,7 +1269,7 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
         },
         
         0x3A => { //LD A, (HL-)
-            cpu.A = readByteFromMemory(mem, word(cpu.H, cpu.L));
+            cpu.A = mem.read(word(cpu.H, cpu.L));
             decrement16!(H,L);
             (cpu.PC.wrapping_add(1), 8)
 
@@ -1188,7 +1289,7 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
         },
         
         0x3E => { //LD A, d8
-            cpu.A = readByteFromMemory(mem, cpu.PC.wrapping_add(1));
+            cpu.A = mem.read(cpu.PC.wrapping_add(1));
             (cpu.PC.wrapping_add(2), 8)
         },
         
@@ -1215,7 +1316,7 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
                 3 => cpu.E,
                 4 => cpu.H,
                 5 => cpu.L,
-                6 => readByteFromMemory(mem, word(cpu.H, cpu.L)),
+                6 => mem.read(word(cpu.H, cpu.L)),
                 7 => cpu.A,
                 _ => panic!("Unreachable")
             };
@@ -1255,23 +1356,23 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
                 _ => panic!("Unreachable")
             };
 
-            writeByteToMemory(mem, src, word(cpu.H, cpu.L));
+            mem.write(src, word(cpu.H, cpu.L));
 
             (cpu.PC.wrapping_add(1), 8)
 
         },
 
         0x76 => { //HALT
-            if cpu.enableInterrupts {
-                   cpu.isHalted = true;
-                   (cpu.PC.wrapping_add(1), 4)
+            //With interrupts disabled and one already pending, the CPU doesn't halt.
+            //Instead it fails to increment PC after reading the next opcode
+            if !cpu.enableInterrupts && mem.pending_interrupts() != 0 {
+                cpu.isHaltBugTriggered = true;
             }
             else {
-                //does the next instruction without incrementing the PC
-                executeInstruction(readByteFromMemory(mem, cpu.PC.wrapping_add(1)), cpu, mem)
+                cpu.isHalted = true;
             }
-            //TODO(DanB): to be implemented....
 
+            (cpu.PC.wrapping_add(1), 4)
         },
 
         0x80...0xBF | 
@@ -1290,7 +1391,7 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
                             3 => cpu.E,
                             4 => cpu.H,
                             5 => cpu.L,
-                            6 => readByteFromMemory(mem, word(cpu.H, cpu.L)),
+                            6 => mem.read(word(cpu.H, cpu.L)),
                             7 => cpu.A,
                             _ => panic!("Unreachable")
                         };
@@ -1305,7 +1406,7 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
 
                     0xC6 | 0xD6 | 0xE6 | 0xF6 |
                            0xCE | 0xDE | 0xEE | 0xFE => {
-                        src = readByteFromMemory(mem, cpu.PC.wrapping_add(1));
+                        src = mem.read(cpu.PC.wrapping_add(1));
                         ret = (cpu.PC.wrapping_add(2), 8);
                      },
                      _ => panic!("Unreachable")
@@ -1408,7 +1509,7 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
             //pastraiser and marc rawer manuals.  SRA should set Carry and RLCA should set Zero 
 
             //instruction to execute
-            let inst = readByteFromMemory(mem, cpu.PC.wrapping_add(1));
+            let inst = mem.read(cpu.PC.wrapping_add(1));
 
 
             //load
@@ -1419,7 +1520,7 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
                 3 => cpu.E,
                 4 => cpu.H,
                 5 => cpu.L,
-                6 => readByteFromMemory(mem, word(cpu.H, cpu.L)),
+                6 => mem.read(word(cpu.H, cpu.L)),
                 7 => cpu.A,
                 _ => panic!("Unreachable.  Modding 8 should only yield values 0 to 7")
             };
@@ -1504,23 +1605,27 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
                 _ => panic!("Unreachable.  Max value is 0xFF")
             }
 
-            //save
+            //save.  BIT only reads, so nothing gets written back
+            let isBIT = inst >= 0x40 && inst <= 0x7F;
+
             match inst % 8 {
+                _ if isBIT => {},
                 0 => cpu.B = src,
                 1 => cpu.C = src,
                 2 => cpu.D = src,
                 3 => cpu.E = src,
                 4 => cpu.H = src,
                 5 => cpu.L = src,
-                6 => writeByteToMemory(mem, src, word(cpu.H, cpu.L)),
+                6 => mem.write(src, word(cpu.H, cpu.L)),
                 7 => cpu.A = src,
                 _ => panic!("Unreachable.  Modding 8 should only yield values 0 to 7")
             }
 
            
-            //8 cycles normally, but 16 cycles for (HL)
+            //8 cycles normally, but 16 cycles for (HL), or 12 for BIT since it doesn't write back
             match inst % 8 {
                 0...5 | 7 => (cpu.PC.wrapping_add(2), 8),
+                6 if isBIT => (cpu.PC.wrapping_add(2), 12),
                 6 => (cpu.PC.wrapping_add(2), 16),
                 _ => panic!("Unreachable.  Modding 8 should only yield values 0 to 7")
             }
@@ -1542,7 +1647,7 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
         0xD8 => returnFromProc!(isFlagSet!(Carry)), //RET C
         0xD9 => { //RETI
             enableInterrupts(cpu);
-            returnFromProc!(true)
+            (popOffOfStack(mem, &mut cpu.SP), 16)
         }
         0xDA => jumpAbsolute!(isFlagSet!(Carry)), // JP C, a16
         //No DB
@@ -1553,16 +1658,16 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
 
 
         0xE0 => { //LDH (a8), A 
-            //I can use "+" here since readByteFromMemory can't return a value high enough to wrap
-            let addr = readByteFromMemory(mem, cpu.PC.wrapping_add(1)) as u16 + 0xFF00; 
-            writeByteToMemory(mem, cpu.A, addr);
+            //I can use "+" here since a byte can't be high enough to wrap
+            let addr = mem.read(cpu.PC.wrapping_add(1)) as u16 + 0xFF00; 
+            mem.write(cpu.A, addr);
             (cpu.PC.wrapping_add(2), 12)
         }
         0xE1 => pop16!(H,L), //POP HL
         0xE2 => { //LD (C), A 
-            //I can use "+" here since readByteFromMemory can't return a value high enough to wrap
+            //I can use "+" here since a byte can't be high enough to wrap
             let addr = cpu.C as u16 + 0xFF00; 
-            writeByteToMemory(mem, cpu.A, addr);
+            mem.write(cpu.A, addr);
             (cpu.PC.wrapping_add(1), 8)
         }
         //No E3
@@ -1576,8 +1681,8 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
         }
         0xE9 => (word(cpu.H, cpu.L), 4), //JP (HL)
         0xEA => { //LD (a16), A
-            let addr = readWordFromMemory(mem, cpu.PC.wrapping_add(1));
-            writeByteToMemory(mem, cpu.A, addr);
+            let addr = readWord(mem, cpu.PC.wrapping_add(1));
+            mem.write(cpu.A, addr);
             (cpu.PC.wrapping_add(3), 16)
         }
         //No EB
@@ -1587,17 +1692,17 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
         0xEF => restart!(0x28), //RST 28H
 
         0xF0 => { //LDH A, (a8)
-            //I can use "+" here since readByteFromMemory can't return a value high enough to wrap
-            let addr = readByteFromMemory(mem, cpu.PC.wrapping_add(1)) as u16 + 0xFF00; 
-            cpu.A = readByteFromMemory(mem, addr);
+            //I can use "+" here since a byte can't be high enough to wrap
+            let addr = mem.read(cpu.PC.wrapping_add(1)) as u16 + 0xFF00; 
+            cpu.A = mem.read(addr);
             (cpu.PC.wrapping_add(2), 12)
         }
         0xF1 => pop16!(A,F), //POP AF
         0xF2 => { //LDH A, (C)
-            //I can use "+" here since readByteFromMemory can't return a value high enough to wrap
+            //I can use "+" here since a byte can't be high enough to wrap
             let addr = cpu.C as u16 + 0xFF00; 
-            cpu.A = readByteFromMemory(mem, addr);
-            (cpu.PC.wrapping_add(2), 12)
+            cpu.A = mem.read(addr);
+            (cpu.PC.wrapping_add(1), 8)
         }
         0xF3 => { //DI
             disableInterrupts(cpu);
@@ -1619,8 +1724,8 @@ pub fn executeInstruction(instruction: u8, cpu: &mut CPUState, mem: &mut MemoryM
             (cpu.PC.wrapping_add(1), 8)
         },
         0xFA => { //LD (a16), A
-            let addr = readWordFromMemory(mem, cpu.PC.wrapping_add(1));
-            cpu.A = readByteFromMemory(mem, addr);
+            let addr = readWord(mem, cpu.PC.wrapping_add(1));
+            cpu.A = mem.read(addr);
             (cpu.PC.wrapping_add(3), 16)
         },
         0xFB => { //EI
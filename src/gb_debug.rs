@@ -1,27 +1,42 @@
+//The debug overlay draws with SDL2_ttf, so it is only built with the sdl feature.
+//The execution trace and state dump are part of the core
+#[cfg(feature = "sdl")]
 extern crate sdl2;
 
+#[cfg(feature = "sdl")]
 use std::path::Path;
 
+#[cfg(feature = "sdl")]
 use sdl2_ttf;
+#[cfg(feature = "sdl")]
 use sdl2::render::Renderer;
+#[cfg(feature = "sdl")]
 use sdl2_ttf::Font;
 
+#[cfg(feature = "sdl")]
 use gb_util::*;
 use gb_cpu::*;
 use gb_memory::*;
 use gb_gameboy::*;
 use gb_lcd::*;
+#[cfg(feature = "sdl")]
+use gb_timer::*;
 use std::fs::File;
 use std::io::Result;
 use std::io::Write;
+#[cfg(feature = "sdl")]
 use std::io::Read;
+#[cfg(feature = "sdl")]
 use libc::{c_void, c_int, c_float};
+#[cfg(feature = "sdl")]
 use std::ptr;
 
 
+#[cfg(feature = "sdl")]
 static FONT_PATH_STR: &'static str = "res/Gamegirl.ttf";
 
 
+#[cfg(feature = "sdl")]
 extern "C" {
     fn stbtt_InitFont(fontInfo: *mut stbtt_fontinfo, data: *mut c_void, offset: c_int) -> c_int; 
     //used as offset argument in InitFont 
@@ -35,6 +50,7 @@ extern "C" {
 }
 
 
+#[cfg(feature = "sdl")]
 pub struct DebugInfo {
     pub mhz: f32,
     pub fps: f32,
@@ -68,15 +84,18 @@ static mut previousExecutionStates: [(u8, CPUState); 100] = [(0,
             L: 0,
             totalCycles: 0,
             instructionCycles: 0,
+            lastOpcode: None,
 
             enableInterrupts: false,
-            isHalted: false
+            isHalted: false,
+            isHaltBugTriggered: false
         }                                                     
         );100];
 
 static mut nextFreeExecStateSlot: usize = 0; 
 
 
+#[cfg(feature = "sdl")]
 #[repr(C)]
 struct stbtt_fontinfo
 {
@@ -162,6 +181,7 @@ pub fn gbDebugInsertExecutionState(instruction: u8, resultingExecutionState: &CP
 }
 
 
+#[cfg(feature = "sdl")]
 fn initFont() -> Result<()> {
     let mut f = try!(File::open(FONT_PATH_STR));
     gbDebugAssert!(true, "");
@@ -195,6 +215,7 @@ fn initFont() -> Result<()> {
 }
 
 //TODO: figure out font proportions
+#[cfg(feature = "sdl")]
 pub fn initDebug(xpos: i32, ypos: i32, debugWidth: u32, debugHeight: u32) -> DebugInfo {
     //TODO: get C linking to work
     initFont();
@@ -218,6 +239,7 @@ pub fn initDebug(xpos: i32, ypos: i32, debugWidth: u32, debugHeight: u32) -> Deb
     }
 }
 
+#[cfg(feature = "sdl")]
 pub fn drawDebugInfo(dbg: &DebugInfo, gb: &GameBoyState, renderer: &mut Renderer) {
     let toPrint: String;
 
@@ -240,7 +262,7 @@ pub fn drawDebugInfo(dbg: &DebugInfo, gb: &GameBoyState, renderer: &mut Renderer
                       format!("FPS: {}, Paused: {}", dbg.fps, dbg.isPaused),
                       format!("Mouse X: {}, Mouse Y: {}", dbg.mouseX, dbg.mouseY),
                       format!("Color Mouse is on: {}", dbg.colorMouseIsOn),
-                      format!("DIV: {:X}, TIMA: {:X}, TMA: {:X}, Timer On: {}", gb.mem.divider, gb.mem.timerCounter, gb.mem.timerModulo, gb.mem.isTimerEnabled)); 
+                      format!("DIV: {:X}, TIMA: {:X}, TMA: {:X}, Timer On: {}", dividerRegister(&gb.mem.timer), gb.mem.timer.tima, gb.mem.timer.tma, gb.mem.timer.isEnabled)); 
 
 
 
@@ -362,6 +384,7 @@ fn lcdDebugInfo(lcd: &LCDState) -> String {
    toPrint
 }
 
+#[cfg(feature = "sdl")]
 pub fn debugQuit() {
     sdl2_ttf::quit();
 }
@@ -1,11 +1,39 @@
+use std::mem;
+
 use gb_memory::*;
 use gb_cpu::*;
+use gb_lcd::*;
+use gb_savestate::*;
 
 pub const GAMEBOY_SCALE: u32 = 2;
+pub const CYCLES_PER_FRAME: u32 = 70224;
+
+//LD B,B does nothing, so test ROMs (e.g. mooneye's) use it as a software breakpoint
+const LD_B_B_OPCODE: u8 = 0x40;
+
+//what happened while stepping.  Returned by step_instruction(), run_frame() and run_cycles()
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct StepEvents {
+    pub cycles: u32, //cycles that were run
+    pub isVBlankReached: bool, //the LCD entered VBlank, so a new frame is ready to draw
+    pub isBreakpointHit: bool //PC landed on one of the breakpoints or LD B,B ran with isLDBBBreakpointEnabled
+}
+
+impl StepEvents {
+    fn new() -> StepEvents {
+        StepEvents {
+            cycles: 0,
+            isVBlankReached: false,
+            isBreakpointHit: false
+        }
+    }
+}
 
 pub struct GameBoyState {
     pub cpu: CPUState,
-    pub mem: MemoryMapState
+    pub mem: MemoryMapState,
+    pub breakpoints: Vec<u16>, //addresses that stop run_frame() and run_cycles() once PC reaches them
+    pub isLDBBBreakpointEnabled: bool //LD B,B stops run_frame() and run_cycles() too
 }
 
 impl GameBoyState {
@@ -14,7 +42,127 @@ impl GameBoyState {
         GameBoyState {
             cpu: CPUState::new(),
             mem: MemoryMapState::new(),
+            breakpoints: vec![],
+            isLDBBBreakpointEnabled: false
+        }
+    }
+
+    //Starts at the cartridge's entry point instead of running the boot ROM's "Nintendo" logo
+    pub fn skipBootScreen(&mut self) {
+        self.cpu.PC = 0x100;
+        self.mem.inBios = false;
+        self.mem.lcd.mode = LCDMode::VBlank;
+    }
 
+    //Runs one instruction (or one idle machine cycle if halted) and advances every other
+    //component by the cycles it took
+    pub fn step_instruction(&mut self) -> StepEvents {
+        let wasInVBlank = self.mem.lcd.mode == LCDMode::VBlank;
+
+        //the CPU ticks the rest of the memory map itself
+        stepCPU(&mut self.cpu, &mut self.mem);
+
+        let cycles = self.cpu.instructionCycles;
+
+        StepEvents {
+            cycles: cycles,
+            isVBlankReached: !wasInVBlank && self.mem.lcd.mode == LCDMode::VBlank,
+            isBreakpointHit: self.breakpoints.contains(&self.cpu.PC) ||
+                             (self.isLDBBBreakpointEnabled && self.cpu.lastOpcode == Some(LD_B_B_OPCODE))
+        }
+    }
+
+    //Runs until the next VBlank.  Stops early if a breakpoint is hit and runs for one frame's
+    //worth of cycles if the LCD is off
+    pub fn run_frame(&mut self) -> StepEvents {
+        let mut events = StepEvents::new();
+
+        while !events.isVBlankReached && !events.isBreakpointHit && events.cycles < CYCLES_PER_FRAME {
+            let stepEvents = self.step_instruction();
+
+            events.cycles += stepEvents.cycles;
+            events.isVBlankReached = stepEvents.isVBlankReached;
+            events.isBreakpointHit = stepEvents.isBreakpointHit;
         }
+
+        events
+    }
+
+    //Runs at least numCycles cycles, finishing the last instruction.  Stops early if a breakpoint is hit
+    pub fn run_cycles(&mut self, numCycles: u32) -> StepEvents {
+        let mut events = StepEvents::new();
+
+        while !events.isBreakpointHit && events.cycles < numCycles {
+            let stepEvents = self.step_instruction();
+
+            events.cycles += stepEvents.cycles;
+            events.isVBlankReached |= stepEvents.isVBlankReached;
+            events.isBreakpointHit = stepEvents.isBreakpointHit;
+        }
+
+        events
+    }
+
+    //Returns the interleaved stereo samples generated since the last call.
+    //Should be called once per frame
+    pub fn takeAudioSamples(&mut self) -> Vec<i16> {
+        mem::replace(&mut self.mem.apu.sampleBuffer, vec![])
+    }
+
+    //Serializes the whole machine.  See gb_savestate.rs for the format
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut w = StateWriter::new();
+
+        w.writeBytes(SAVE_STATE_MAGIC);
+        w.writeU32(SAVE_STATE_VERSION);
+
+        writeCPUState(&mut w, &self.cpu);
+        writeMemoryMapState(&mut w, &self.mem);
+        writeLCDState(&mut w, &self.mem.lcd);
+        writeJoypadState(&mut w, &self.mem.joypad);
+        writeAPUState(&mut w, &self.mem.apu);
+        writeCartridgeState(&mut w, &*self.mem.cartridge);
+
+        w.data
+    }
+
+    //Loads a state made by save_state(). The same ROM must already be loaded.
+    //If the state can't be loaded, the machine is left as it was
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), SaveStateError> {
+        let backup = self.save_state();
+
+        match self.readState(data) {
+            Ok(_) => Ok(()),
+            Err(err) => {
+                self.readState(&backup[..]).unwrap();
+                Err(err)
+            }
+        }
+    }
+
+    fn readState(&mut self, data: &[u8]) -> Result<(), SaveStateError> {
+        let mut r = StateReader::new(data);
+
+        let mut magic = [0u8;4];
+        try!(r.readBytes(&mut magic[..]));
+        if &magic[..] != SAVE_STATE_MAGIC {
+            return Err(SaveStateError::BadMagic);
+        }
+
+        let version = try!(r.readU32());
+        if version != SAVE_STATE_VERSION {
+            return Err(SaveStateError::UnsupportedVersion(version));
+        }
+
+        try!(readCPUState(&mut r, &mut self.cpu));
+        try!(readMemoryMapState(&mut r, &mut self.mem));
+        try!(readLCDState(&mut r, &mut self.mem.lcd));
+        try!(readJoypadState(&mut r, &mut self.mem.joypad));
+        try!(readAPUState(&mut r, &mut self.mem.apu));
+        try!(readCartridgeState(&mut r, &mut *self.mem.cartridge));
+
+        self.mem.apu.sampleBuffer.clear();
+
+        Ok(())
     }
 }
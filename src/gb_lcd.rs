@@ -1,4 +1,3 @@
-extern crate sdl2;
 
 use std::mem::swap; 
 use self::LCDMode::*;
@@ -20,6 +19,12 @@ pub struct LCDState {
     pub isEnabled: bool,
     pub isOAMEnabled: bool,
 
+    pub windowTileMap: u8, //which window tile map to use (0 or 1)
+    pub isWindowEnabled: bool,
+    pub wx: u8, //window x position + 7
+    pub wy: u8, //window y position
+    pub windowLine: u8, //internal window line counter. Only advances on lines the window was drawn
+
     pub scx: u8, //scroll x
     pub scy: u8, //scroll y
     pub spriteHeight: SpriteHeight, //can be 8 or 16
@@ -45,11 +50,19 @@ pub enum SpriteHeight {
     Tall = 16
 }
 
-pub type PaletteColor = sdl2::pixels::Color;
-pub const WHITE: PaletteColor = sdl2::pixels::Color::RGBA(255, 255, 255, 255);
-pub const LIGHT_GRAY: PaletteColor = sdl2::pixels::Color::RGBA(170,170,170,255);
-pub const DARK_GRAY: PaletteColor = sdl2::pixels::Color::RGBA(85,85,85,255);
-pub const BLACK: PaletteColor = sdl2::pixels::Color::RGBA(0,0,0,255);
+//plain RGBA so the core doesn't depend on SDL.  The frontend converts it to whatever it draws with
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct PaletteColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8
+}
+
+pub const WHITE: PaletteColor = PaletteColor { r: 255, g: 255, b: 255, a: 255 };
+pub const LIGHT_GRAY: PaletteColor = PaletteColor { r: 170, g: 170, b: 170, a: 255 };
+pub const DARK_GRAY: PaletteColor = PaletteColor { r: 85, g: 85, b: 85, a: 255 };
+pub const BLACK: PaletteColor = PaletteColor { r: 0, g: 0, b: 0, a: 255 };
 
 pub const SCREEN_WIDTH: usize = 160;
 pub const SCREEN_HEIGHT: usize = 144;
@@ -66,6 +79,10 @@ const TILE_MAP_WIDTH: usize = 32;
 const TILE_MAP_HEIGHT: usize = 32;
 const MAX_SPRITES_PER_SCANLINE: usize = 10;
 
+//WX holds the window's x position plus 7. Any WX above this puts the window off screen
+const WINDOW_X_OFFSET: usize = 7;
+const MAX_VISIBLE_WX: u8 = 166;
+
 const TALL_SPRITE_HEIGHT: usize = 16;
 const SHORT_SPRITE_HEIGHT: usize = 8;
 
@@ -91,6 +108,11 @@ impl LCDState {
             isEnabled: false,
             isOAMEnabled: false,
 
+            windowTileMap: 0,
+            isWindowEnabled: false,
+            wx: 0,
+            wy: 0,
+            windowLine: 0,
 
             lcdc: 0,
             lyc: 0,
@@ -252,6 +274,46 @@ fn colorNumberForBackgroundTileReferenceAddress(backgroundTileRefAddr: usize, sc
 
 }
 
+/*
+ * Gets the color number of a pixel in the window layer
+ *
+ * Args:
+ *      windowX: x position of the pixel relative to the left edge of the window
+ *      lcd: LCD state.  lcd.windowLine is used as the y position in the window
+ *
+ */
+fn colorNumberForWindow(windowX: usize, lcd: &mut LCDState) -> ColorNumber {
+    let windowY = lcd.windowLine as usize;
+
+    let mut tileRefAddr = match lcd.windowTileMap {
+        0 => 0x1800usize,  //relative to start of vram
+        1 => 0x1C00usize,
+        _ => panic!("Uh oh, the window tile map should only be 0 or 1")
+    };
+
+    //window uses the same tile map layout as the background, it just never scrolls
+    tileRefAddr += (windowY / TILE_HEIGHT) * TILE_MAP_WIDTH;
+    tileRefAddr += windowX / TILE_WIDTH;
+
+    let tileRef = lcd.videoRAM[tileRefAddr];
+
+    //the window shares the background tile set
+    let mut tileAddr = match lcd.backgroundTileSet {
+        0 => (0x1000i16 + ((tileRef as i8 as i16) * BYTES_PER_TILE as i16)) as usize, //signed addition
+        1 => (tileRef as usize) * BYTES_PER_TILE, 
+        _ => panic!("Uh oh, the tile set should only be 0 or 1")
+    };
+
+    tileAddr += (windowY % TILE_HEIGHT) * BYTES_PER_TILE_ROW;
+
+    let xMask = 0x80u8 >> (windowX & 7);
+
+    let highBit = if (lcd.videoRAM[tileAddr + 1] & xMask) != 0 {1u8} else {0};
+    let lowBit = if (lcd.videoRAM[tileAddr] & xMask) != 0 {1u8} else {0};
+
+    ColorNumber::fromU8((highBit * 2) + lowBit)
+}
+
 //TODO: refactor
 fn colorNumberForSprite(sprite: &Sprite, posInScanLine: usize, lcd: &mut LCDState) -> ColorNumber {
 
@@ -385,6 +447,7 @@ pub fn stepLCD(lcd: &mut LCDState, requestedInterrupts: &mut u8, cyclesTakenOfLa
                 //at the last line...
                 if lcd.currScanLine == 144 {
                     changeToNewLCDMode(VBlank, lcd, requestedInterrupts); //engage VBlank
+                    lcd.windowLine = 0; //window starts drawing from its first line next frame
                     swap(&mut lcd.screen, &mut lcd.screenBackBuffer); //commit fully drawn screen
                     *requestedInterrupts |= 1; //request VBlank interrupt
                 }
@@ -468,6 +531,11 @@ pub fn stepLCD(lcd: &mut LCDState, requestedInterrupts: &mut u8, cyclesTakenOfLa
                 //can only draw at most 10 sprites per scanline
                 let spritesToDraw = &spritesSortedByPriority[0..numSpritesToDraw];
 
+                //NOTE: On the DMG, clearing the background enable bit also hides the window
+                let isWindowOnScanLine = lcd.isWindowEnabled && lcd.isBackgroundEnabled &&
+                    lcd.currScanLine >= lcd.wy && lcd.wx <= MAX_VISIBLE_WX;
+                let mut wasWindowDrawn = false;
+
 
                 for posInScanLine in 0..SCREEN_WIDTH {
 
@@ -492,7 +560,14 @@ pub fn stepLCD(lcd: &mut LCDState, requestedInterrupts: &mut u8, cyclesTakenOfLa
                     }
 
 
-                    let backgroundColorNum = if lcd.isBackgroundEnabled {
+                    //the window is drawn over the background starting at WX - 7.
+                    //When WX < 7, the first 7 - WX pixels of the window are cut off
+                    let backgroundColorNum = if isWindowOnScanLine && 
+                        posInScanLine + WINDOW_X_OFFSET >= lcd.wx as usize {
+                        wasWindowDrawn = true;
+                        colorNumberForWindow(posInScanLine + WINDOW_X_OFFSET - lcd.wx as usize, lcd)
+                    }
+                    else if lcd.isBackgroundEnabled {
                         colorNumberForBackgroundTileReferenceAddress(backgroundTileRefAddr, posInScanLine, lcd) 
                     }
                     else {
@@ -535,6 +610,11 @@ pub fn stepLCD(lcd: &mut LCDState, requestedInterrupts: &mut u8, cyclesTakenOfLa
 
                 }
 
+                //the window line counter only moves forward on lines where the window was visible
+                if wasWindowDrawn {
+                    lcd.windowLine = lcd.windowLine.wrapping_add(1);
+                }
+
                 changeToNewLCDMode(HBlank, lcd, requestedInterrupts); 
                 lcd.modeClock = 0;
 
@@ -546,3 +626,33 @@ pub fn stepLCD(lcd: &mut LCDState, requestedInterrupts: &mut u8, cyclesTakenOfLa
 
 }
 
+
+fn grayLevelForColor(color: PaletteColor) -> u8 {
+    match color {
+        WHITE => 255,
+        LIGHT_GRAY => 170,
+        DARK_GRAY => 85,
+        _ => 0
+    }
+}
+
+/*
+ * Encodes a screen as a binary PGM image so it can be saved without SDL
+ *
+ * Args:
+ *      screen: The screen to encode
+ *
+ * Return: The contents of the PGM file
+ *
+ */
+pub fn screenToPGM(screen: &LCDScreen) -> Vec<u8> {
+    let mut pgm = format!("P5\n{} {}\n255\n", SCREEN_WIDTH, SCREEN_HEIGHT).into_bytes();
+
+    for row in screen.iter() {
+        for color in row.iter() {
+            pgm.push(grayLevelForColor(*color));
+        }
+    }
+
+    pgm
+}
@@ -0,0 +1,1001 @@
+/*
+ * All memory bank controllers (cartridge mappers) go into this module
+ *
+ */
+use std::cmp;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use gb_util::*;
+use gb_cpu::CLOCK_SPEED_HZ;
+use gb_cartridge::*;
+use gb_savestate::*;
+
+pub const ROM_BANK_SIZE: usize = 0x4000;
+pub const RAM_BANK_SIZE: usize = 0x2000;
+pub const MBC2_RAM_SIZE: usize = 0x200;
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+#[repr(u8)]
+pub enum MemoryBankControllerType {
+    MBC0 = 0,
+    MBC1 = 1,
+    MBC2 = 2,
+    MBC3 = 3,
+    MBC5 = 5,
+}
+
+/*
+ * Everything the memory map needs from a cartridge.  Each mapper implements this
+ * so that the memory map never has to know which mapper is in use.
+ *
+ * Addresses passed in are the full Game Boy addresses
+ * (0x0000-0x7FFF for ROM and control registers, 0xA000-0xBFFF for RAM)
+ */
+pub trait Cartridge {
+    fn mbc_type(&self) -> MemoryBankControllerType;
+
+    fn read_rom(&self, addr: u16) -> u8;
+    //writes to 0x0000-0x7FFF go to the mapper's control registers
+    fn write_control(&mut self, addr: u16, byte: u8);
+
+    fn read_ram(&self, addr: u16) -> u8;
+    //Returns whether the byte was actually stored.  Writes are dropped while RAM is disabled
+    //or if there is no RAM, so there's nothing new to save
+    fn write_ram(&mut self, addr: u16, byte: u8) -> bool;
+
+    //advances anything on the cartridge that runs off of the clock
+    fn step(&mut self, _cycles: u32) {}
+
+    //save data hooks. Returns/takes what should be kept in between runs
+    fn save_data(&self) -> Vec<u8>;
+    fn load_save_data(&mut self, data: &[u8]);
+
+    //save state hooks.  Covers the mapper registers and cart RAM, but not the ROM
+    fn save_state(&self, w: &mut StateWriter);
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError>;
+
+    //raw access to cart RAM for other emulators' state formats
+    fn cart_ram(&self) -> &[u8];
+    fn cart_ram_mut(&mut self) -> &mut [u8];
+
+    //writes to 0x0000-0x7FFF that put another cartridge of this type into the same state as this one
+    fn control_register_writes(&self) -> Vec<(u16, u8)>;
+
+    fn is_rumble_motor_on(&self) -> bool {
+        false
+    }
+}
+
+//reads from a switchable ROM bank.  Banks past the end of the ROM wrap around
+fn readBankedROM(romData: &[u8], bank: usize, addr: u16) -> u8 {
+    let numBanks = romData.len() / ROM_BANK_SIZE;
+
+    if numBanks == 0 {
+        return 0xFF;
+    }
+
+    romData[((bank % numBanks) * ROM_BANK_SIZE) + (addr as usize % ROM_BANK_SIZE)]
+}
+
+//index into cart RAM for the given bank.  Addresses past the end of RAM wrap around,
+//since the cart doesn't connect the extra address lines.  None if the cart has no RAM
+fn bankedRAMIndex(cartRAM: &[u8], bank: usize, addr: u16) -> Option<usize> {
+    if cartRAM.is_empty() {
+        return None;
+    }
+
+    Some(((RAM_BANK_SIZE * bank) + (addr as usize - 0xA000)) % cartRAM.len())
+}
+
+fn readBankedRAM(cartRAM: &[u8], bank: usize, addr: u16) -> u8 {
+    match bankedRAMIndex(cartRAM, bank, addr) {
+        Some(i) => cartRAM[i],
+        None => 0xFF
+    }
+}
+
+//returns whether there was RAM to write to
+fn writeBankedRAM(cartRAM: &mut Vec<u8>, bank: usize, addr: u16, byte: u8) -> bool {
+    if let Some(i) = bankedRAMIndex(cartRAM, bank, addr) {
+        cartRAM[i] = byte;
+        true
+    }
+    else {
+        false
+    }
+}
+
+fn copySaveData(cartRAM: &mut Vec<u8>, data: &[u8]) {
+    for (dest, src) in cartRAM.iter_mut().zip(data.iter()) {
+        *dest = *src;
+    }
+}
+
+//---------------------------------MBC0---------------------------------------
+//32KB ROM with no banking
+
+pub struct MBC0Cartridge {
+    pub romData: Vec<u8>,
+    pub cartRAM: Vec<u8>
+}
+
+impl MBC0Cartridge {
+    pub fn new(romData: Vec<u8>, ramSize: usize) -> MBC0Cartridge {
+        MBC0Cartridge {
+            romData: romData,
+            cartRAM: vec![0;ramSize]
+        }
+    }
+}
+
+impl Cartridge for MBC0Cartridge {
+    fn mbc_type(&self) -> MemoryBankControllerType {
+        MemoryBankControllerType::MBC0
+    }
+
+    fn read_rom(&self, addr: u16) -> u8 {
+        match self.romData.get(addr as usize) {
+            Some(byte) => *byte,
+            None => 0xFF
+        }
+    }
+
+    fn write_control(&mut self, _addr: u16, _byte: u8) {} //no registers
+
+    fn read_ram(&self, addr: u16) -> u8 {
+        readBankedRAM(&self.cartRAM, 0, addr)
+    }
+
+    fn write_ram(&mut self, addr: u16, byte: u8) -> bool {
+        writeBankedRAM(&mut self.cartRAM, 0, addr, byte)
+    }
+
+    fn save_data(&self) -> Vec<u8> {
+        self.cartRAM.clone()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        copySaveData(&mut self.cartRAM, data);
+    }
+
+    fn cart_ram(&self) -> &[u8] {
+        &self.cartRAM[..]
+    }
+
+    fn cart_ram_mut(&mut self) -> &mut [u8] {
+        &mut self.cartRAM[..]
+    }
+
+    fn control_register_writes(&self) -> Vec<(u16, u8)> {
+        vec![] //no registers
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.writeSizedBytes(&self.cartRAM[..]);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        r.readSizedBytes("cart RAM", &mut self.cartRAM[..])
+    }
+}
+
+//---------------------------------MBC1---------------------------------------
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+#[repr(u8)]
+pub enum BankingMode {
+    Mode0 = 0, //secondary register only selects the upper ROM bits of 0x4000-0x7FFF
+    Mode1 = 1  //secondary register also applies to 0x0000-0x3FFF and cart RAM
+}
+
+//MBC1M multicarts are 1MB and have a copy of the Nintendo logo at the start of each 256KB game
+const MBC1M_ROM_SIZE: usize = 0x100000;
+const MBC1M_GAME_SIZE: usize = 0x40000;
+const LOGO_START: usize = 0x104;
+const LOGO_END: usize = 0x134;
+
+pub struct MBC1Cartridge {
+    pub romData: Vec<u8>,
+    pub cartRAM: Vec<u8>,
+
+    pub bankRegister: u8, //5 bit register at 0x2000-0x3FFF. Never 0
+    pub secondaryBankRegister: u8, //2 bit register at 0x4000-0x5FFF
+    pub isCartRAMEnabled: bool,
+    pub bankingMode: BankingMode,
+
+    //MBC1M wires the secondary register to ROM bits 4-5 instead of 5-6
+    pub isMultiCart: bool
+}
+
+impl MBC1Cartridge {
+    pub fn new(romData: Vec<u8>, ramSize: usize) -> MBC1Cartridge {
+        let isMultiCart = isMBC1MultiCart(&romData);
+
+        MBC1Cartridge {
+            romData: romData,
+            cartRAM: vec![0;ramSize],
+
+            bankRegister: 1,
+            secondaryBankRegister: 0,
+            isCartRAMEnabled: false,
+            bankingMode: BankingMode::Mode0,
+            isMultiCart: isMultiCart
+        }
+    }
+
+    fn bankRegisterBits(&self) -> u8 {
+        if self.isMultiCart { 4 } else { 5 }
+    }
+
+    //bank mapped to 0x0000-0x3FFF
+    pub fn lowerROMBank(&self) -> usize {
+        match self.bankingMode {
+            BankingMode::Mode0 => 0,
+            BankingMode::Mode1 => (self.secondaryBankRegister as usize) << self.bankRegisterBits()
+        }
+    }
+
+    //bank mapped to 0x4000-0x7FFF
+    pub fn upperROMBank(&self) -> usize {
+        let bits = self.bankRegisterBits();
+        let lowBits = self.bankRegister as usize & ((1 << bits) - 1);
+
+        ((self.secondaryBankRegister as usize) << bits) | lowBits
+    }
+
+    pub fn currentRAMBank(&self) -> usize {
+        match self.bankingMode {
+            BankingMode::Mode0 => 0,
+            BankingMode::Mode1 => self.secondaryBankRegister as usize
+        }
+    }
+
+    //bank numbers past the end of the ROM lose their upper bits, just like on the cart
+    fn maskROMBank(&self, bank: usize) -> usize {
+        let numBanks = self.romData.len() / ROM_BANK_SIZE;
+
+        if numBanks == 0 {
+            bank
+        }
+        else {
+            bank & (numBanks.next_power_of_two() - 1)
+        }
+    }
+}
+
+//multicarts can't be told apart from regular MBC1 carts by the header,
+//so look for the logo of the second game
+fn isMBC1MultiCart(romData: &[u8]) -> bool {
+    if romData.len() != MBC1M_ROM_SIZE {
+        return false;
+    }
+
+    let logo = &romData[LOGO_START..LOGO_END];
+    let secondLogo = &romData[MBC1M_GAME_SIZE + LOGO_START..MBC1M_GAME_SIZE + LOGO_END];
+
+    logo == secondLogo
+}
+
+impl Cartridge for MBC1Cartridge {
+    fn mbc_type(&self) -> MemoryBankControllerType {
+        MemoryBankControllerType::MBC1
+    }
+
+    fn read_rom(&self, addr: u16) -> u8 {
+        let bank = match addr {
+            0...0x3FFF => self.lowerROMBank(),
+            _ => self.upperROMBank()
+        };
+
+        readBankedROM(&self.romData, self.maskROMBank(bank), addr)
+    }
+
+    fn write_control(&mut self, addr: u16, byte: u8) {
+        match addr {
+            0...0x1FFF => self.isCartRAMEnabled = (byte & 0xF) == 0xA,
+            0x2000...0x3FFF => {
+                //0 maps to 1, but the check is on all 5 bits even on multicarts
+                self.bankRegister = match byte & 0x1F {
+                    0 => 1,
+                    bank => bank
+                };
+            }
+            0x4000...0x5FFF => self.secondaryBankRegister = byte & 0x3,
+            _ => { //0x6000...0x7FFF
+                self.bankingMode = match byte & 0x1 {
+                    0 => BankingMode::Mode0,
+                    _ => BankingMode::Mode1
+                };
+            }
+        }
+    }
+
+    fn read_ram(&self, addr: u16) -> u8 {
+        if self.isCartRAMEnabled {
+            readBankedRAM(&self.cartRAM, self.currentRAMBank(), addr)
+        }
+        else {
+            0xFF
+        }
+    }
+
+    fn write_ram(&mut self, addr: u16, byte: u8) -> bool {
+        if self.isCartRAMEnabled {
+            let bank = self.currentRAMBank();
+            writeBankedRAM(&mut self.cartRAM, bank, addr, byte)
+        }
+        else {
+            false
+        }
+    }
+
+    fn save_data(&self) -> Vec<u8> {
+        self.cartRAM.clone()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        copySaveData(&mut self.cartRAM, data);
+    }
+
+    fn cart_ram(&self) -> &[u8] {
+        &self.cartRAM[..]
+    }
+
+    fn cart_ram_mut(&mut self) -> &mut [u8] {
+        &mut self.cartRAM[..]
+    }
+
+    fn control_register_writes(&self) -> Vec<(u16, u8)> {
+        vec![(0x0000, if self.isCartRAMEnabled {0xA} else {0}),
+             (0x2000, self.bankRegister),
+             (0x4000, self.secondaryBankRegister),
+             (0x6000, self.bankingMode as u8)]
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.writeU8(self.bankRegister);
+        w.writeU8(self.secondaryBankRegister);
+        w.writeBool(self.isCartRAMEnabled);
+        w.writeU8(self.bankingMode as u8);
+        w.writeSizedBytes(&self.cartRAM[..]);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        self.bankRegister = try!(r.readU8()) & 0x1F;
+        self.secondaryBankRegister = try!(r.readU8()) & 0x3;
+        self.isCartRAMEnabled = try!(r.readBool());
+        self.bankingMode = match try!(r.readU8()) {
+            0 => BankingMode::Mode0,
+            _ => BankingMode::Mode1
+        };
+
+        r.readSizedBytes("cart RAM", &mut self.cartRAM[..])
+    }
+}
+
+//---------------------------------MBC2---------------------------------------
+
+pub struct MBC2Cartridge {
+    pub romData: Vec<u8>,
+    pub cartRAM: Vec<u8>, //512 4-bit values
+
+    pub currentMBCBank: u8,
+    pub isCartRAMEnabled: bool
+}
+
+impl MBC2Cartridge {
+    pub fn new(romData: Vec<u8>) -> MBC2Cartridge {
+        MBC2Cartridge {
+            romData: romData,
+            cartRAM: vec![0;MBC2_RAM_SIZE],
+
+            currentMBCBank: 1,
+            isCartRAMEnabled: false
+        }
+    }
+}
+
+impl Cartridge for MBC2Cartridge {
+    fn mbc_type(&self) -> MemoryBankControllerType {
+        MemoryBankControllerType::MBC2
+    }
+
+    fn read_rom(&self, addr: u16) -> u8 {
+        match addr {
+            0...0x3FFF => readBankedROM(&self.romData, 0, addr),
+            _ => readBankedROM(&self.romData, self.currentMBCBank as usize, addr)
+        }
+    }
+
+    fn write_control(&mut self, addr: u16, byte: u8) {
+        match addr {
+            //MBC2 selects its register with bit 8 of the address instead of the address range
+            0...0x3FFF => {
+                if testBit!(addr, 8) {
+                    //only 16 ROM banks.  Bank 0 maps to bank 1
+                    self.currentMBCBank = byte & 0xF;
+
+                    if self.currentMBCBank == 0 {
+                        self.currentMBCBank = 1;
+                    }
+                }
+                else {
+                    self.isCartRAMEnabled = (byte & 0xF) == 0xA;
+                }
+            }
+            _ => {} //no registers here
+        }
+    }
+
+    //MBC2 RAM is 512 4-bit values that repeat through 0xA000-0xBFFF.
+    //The upper 4 bits are not connected so they read as 1s
+    fn read_ram(&self, addr: u16) -> u8 {
+        if self.isCartRAMEnabled {
+            0xF0 | self.cartRAM[(addr as usize - 0xA000) % MBC2_RAM_SIZE]
+        }
+        else {
+            0xFF
+        }
+    }
+
+    fn write_ram(&mut self, addr: u16, byte: u8) -> bool {
+        if self.isCartRAMEnabled {
+            self.cartRAM[(addr as usize - 0xA000) % MBC2_RAM_SIZE] = byte & 0xF;
+        }
+
+        self.isCartRAMEnabled
+    }
+
+    fn save_data(&self) -> Vec<u8> {
+        self.cartRAM.clone()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        copySaveData(&mut self.cartRAM, data);
+    }
+
+    fn cart_ram(&self) -> &[u8] {
+        &self.cartRAM[..]
+    }
+
+    fn cart_ram_mut(&mut self) -> &mut [u8] {
+        &mut self.cartRAM[..]
+    }
+
+    fn control_register_writes(&self) -> Vec<(u16, u8)> {
+        vec![(0x0000, if self.isCartRAMEnabled {0xA} else {0}),
+             (0x0100, self.currentMBCBank)]
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.writeU8(self.currentMBCBank);
+        w.writeBool(self.isCartRAMEnabled);
+        w.writeSizedBytes(&self.cartRAM[..]);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        self.currentMBCBank = try!(r.readU8()) & 0xF;
+        self.isCartRAMEnabled = try!(r.readBool());
+
+        r.readSizedBytes("cart RAM", &mut self.cartRAM[..])
+    }
+}
+
+//---------------------------------MBC3---------------------------------------
+
+//MBC3 real time clock.  Registers are selected by writing 0x08-0x0C to 0x4000-0x5FFF
+const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+pub const RTC_FOOTER_SIZE: usize = 48;
+
+pub struct RealTimeClock {
+    pub seconds: u8, //0x08
+    pub minutes: u8, //0x09
+    pub hours: u8, //0x0A
+    pub days: u16, //0x0B holds the low 8 bits, Bit 0 of 0x0C holds bit 8
+    pub isHalted: bool, //Bit 6 of 0x0C
+    pub isDayCarrySet: bool, //Bit 7 of 0x0C
+
+    pub latchedRegisters: [u8;5], //what the game reads.  Updated when the clock is latched
+    pub lastLatchWrite: u8, //clock is latched when 0 then 1 is written to 0x6000-0x7FFF
+    pub cyclesSinceLastSecond: u32
+}
+
+impl RealTimeClock {
+    pub fn new() -> RealTimeClock {
+        RealTimeClock {
+            seconds: 0,
+            minutes: 0,
+            hours: 0,
+            days: 0,
+            isHalted: false,
+            isDayCarrySet: false,
+
+            latchedRegisters: [0;5],
+            lastLatchWrite: 0xFF,
+            cyclesSinceLastSecond: 0
+        }
+    }
+}
+
+//copies the current time into the registers the game can read
+fn latchRTC(rtc: &mut RealTimeClock) {
+    rtc.latchedRegisters[0] = rtc.seconds;
+    rtc.latchedRegisters[1] = rtc.minutes;
+    rtc.latchedRegisters[2] = rtc.hours;
+    rtc.latchedRegisters[3] = lb(rtc.days);
+    rtc.latchedRegisters[4] = rtcDayHighRegister(rtc);
+}
+
+fn rtcDayHighRegister(rtc: &RealTimeClock) -> u8 {
+    let mut dayHigh = (rtc.days >> 8) as u8 & 1;
+
+    dayHigh |= if rtc.isHalted {1 << 6} else {0};
+    dayHigh |= if rtc.isDayCarrySet {1 << 7} else {0};
+
+    dayHigh
+}
+
+fn writeRTCRegister(rtc: &mut RealTimeClock, register: u8, byte: u8) {
+    match register {
+        0x8 => {
+            rtc.seconds = byte & 0x3F;
+            //writing to the seconds register resets the sub-second counter
+            rtc.cyclesSinceLastSecond = 0;
+        },
+        0x9 => rtc.minutes = byte & 0x3F,
+        0xA => rtc.hours = byte & 0x1F,
+        0xB => rtc.days = (rtc.days & 0x100) | byte as u16,
+        0xC => {
+            rtc.days = (rtc.days & 0xFF) | (((byte & 1) as u16) << 8);
+            rtc.isHalted = testBit!(byte, 6);
+            rtc.isDayCarrySet = testBit!(byte, 7);
+        },
+        _ => panic!("RTC registers are only 0x8 to 0xC")
+    }
+
+    //written values can be read back right away
+    rtc.latchedRegisters[(register - 0x8) as usize] = match register {
+        0xC => rtcDayHighRegister(rtc),
+        _ => byte
+    };
+}
+
+//advances the clock by one second.
+//NOTE: Out of range values keep counting until their bits overflow without carrying
+fn tickRTC(rtc: &mut RealTimeClock) {
+    rtc.seconds = (rtc.seconds + 1) & 0x3F;
+    if rtc.seconds != 60 {
+        return;
+    }
+    rtc.seconds = 0;
+
+    rtc.minutes = (rtc.minutes + 1) & 0x3F;
+    if rtc.minutes != 60 {
+        return;
+    }
+    rtc.minutes = 0;
+
+    rtc.hours = (rtc.hours + 1) & 0x1F;
+    if rtc.hours != 24 {
+        return;
+    }
+    rtc.hours = 0;
+
+    rtc.days += 1;
+    if rtc.days > 0x1FF {
+        rtc.days = 0;
+        rtc.isDayCarrySet = true;
+    }
+}
+
+//advances the clock by a number of real world seconds, such as the time the emulator was closed
+pub fn advanceRTC(rtc: &mut RealTimeClock, seconds: u64) {
+    if rtc.isHalted {
+        return;
+    }
+
+    for _ in 0..(seconds % SECONDS_PER_DAY) {
+        tickRTC(rtc);
+    }
+
+    //whole days can be added directly instead of ticking through them
+    let days = rtc.days as u64 + (seconds / SECONDS_PER_DAY);
+    if days > 0x1FF {
+        rtc.isDayCarrySet = true;
+    }
+    rtc.days = (days % 0x200) as u16;
+}
+
+fn currentUnixTime() -> u64 {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs(),
+        Err(_) => 0
+    }
+}
+
+fn readLittleEndian(data: &[u8]) -> u64 {
+    data.iter().rev().fold(0u64, |value, byte| (value << 8) | *byte as u64)
+}
+
+/*
+ * Appends the clock to the end of a save file. Uses the same 48 byte footer as
+ * VBA-M and BGB so saves can be moved between emulators:
+ *      5 u32s of the current time (seconds, minutes, hours, day low, day high)
+ *      5 u32s of the latched time
+ *      u64 UNIX timestamp of when the save was written
+ * All little endian
+ */
+fn appendRTCFooter(rtc: &RealTimeClock, data: &mut Vec<u8>) {
+    pushU32(data, rtc.seconds as u32);
+    pushU32(data, rtc.minutes as u32);
+    pushU32(data, rtc.hours as u32);
+    pushU32(data, lb(rtc.days) as u32);
+    pushU32(data, rtcDayHighRegister(rtc) as u32);
+
+    for register in rtc.latchedRegisters.iter() {
+        pushU32(data, *register as u32);
+    }
+
+    let timestamp = currentUnixTime();
+    pushU32(data, timestamp as u32);
+    pushU32(data, (timestamp >> 32) as u32);
+}
+
+//loads the clock from a save file footer and catches it up to the current time.
+//Older saves may have a 44 byte footer with a 32 bit timestamp
+fn loadRTCFooter(rtc: &mut RealTimeClock, footer: &[u8]) {
+    if footer.len() < RTC_FOOTER_SIZE - 4 {
+        return;
+    }
+
+    let register = |i: usize| footer[i * 4];
+
+    rtc.seconds = register(0) & 0x3F;
+    rtc.minutes = register(1) & 0x3F;
+    rtc.hours = register(2) & 0x1F;
+    rtc.days = ((register(4) as u16 & 1) << 8) | register(3) as u16;
+    rtc.isHalted = testBit!(register(4), 6);
+    rtc.isDayCarrySet = testBit!(register(4), 7);
+
+    for i in 0..rtc.latchedRegisters.len() {
+        rtc.latchedRegisters[i] = register(i + 5);
+    }
+
+    let timestamp = readLittleEndian(&footer[40..cmp::min(footer.len(), RTC_FOOTER_SIZE)]);
+    let now = currentUnixTime();
+
+    if now > timestamp {
+        advanceRTC(rtc, now - timestamp);
+    }
+}
+
+fn writeRTCState(w: &mut StateWriter, rtc: &RealTimeClock) {
+    w.writeU8(rtc.seconds);
+    w.writeU8(rtc.minutes);
+    w.writeU8(rtc.hours);
+    w.writeU16(rtc.days);
+    w.writeBool(rtc.isHalted);
+    w.writeBool(rtc.isDayCarrySet);
+    w.writeBytes(&rtc.latchedRegisters[..]);
+    w.writeU8(rtc.lastLatchWrite);
+    w.writeU32(rtc.cyclesSinceLastSecond);
+}
+
+fn readRTCState(r: &mut StateReader, rtc: &mut RealTimeClock) -> Result<(), SaveStateError> {
+    rtc.seconds = try!(r.readU8()) & 0x3F;
+    rtc.minutes = try!(r.readU8()) & 0x3F;
+    rtc.hours = try!(r.readU8()) & 0x1F;
+    rtc.days = try!(r.readU16()) & 0x1FF;
+    rtc.isHalted = try!(r.readBool());
+    rtc.isDayCarrySet = try!(r.readBool());
+    try!(r.readBytes(&mut rtc.latchedRegisters[..]));
+    rtc.lastLatchWrite = try!(r.readU8());
+    rtc.cyclesSinceLastSecond = try!(r.readU32());
+
+    Ok(())
+}
+
+//advances the real time clock by the number of emulated cycles that passed
+pub fn stepRTC(rtc: &mut RealTimeClock, cyclesTakenOfLastInstruction: u32) {
+    if rtc.isHalted {
+        return;
+    }
+
+    rtc.cyclesSinceLastSecond += cyclesTakenOfLastInstruction;
+
+    while rtc.cyclesSinceLastSecond >= CLOCK_SPEED_HZ as u32 {
+        rtc.cyclesSinceLastSecond -= CLOCK_SPEED_HZ as u32;
+        tickRTC(rtc);
+    }
+}
+
+pub struct MBC3Cartridge {
+    pub romData: Vec<u8>,
+    pub cartRAM: Vec<u8>,
+
+    pub currentMBCBank: u8,
+    pub currentRAMBank: u8, //0-3 select RAM, 0x8-0xC select an RTC register
+    pub isCartRAMEnabled: bool, //also enables the RTC
+    pub rtc: RealTimeClock
+}
+
+impl MBC3Cartridge {
+    pub fn new(romData: Vec<u8>, ramSize: usize) -> MBC3Cartridge {
+        MBC3Cartridge {
+            romData: romData,
+            cartRAM: vec![0;ramSize],
+
+            currentMBCBank: 1,
+            currentRAMBank: 0,
+            isCartRAMEnabled: false,
+            rtc: RealTimeClock::new()
+        }
+    }
+}
+
+impl Cartridge for MBC3Cartridge {
+    fn mbc_type(&self) -> MemoryBankControllerType {
+        MemoryBankControllerType::MBC3
+    }
+
+    fn read_rom(&self, addr: u16) -> u8 {
+        match addr {
+            0...0x3FFF => readBankedROM(&self.romData, 0, addr),
+            _ => readBankedROM(&self.romData, self.currentMBCBank as usize, addr)
+        }
+    }
+
+    fn write_control(&mut self, addr: u16, byte: u8) {
+        match addr {
+            0...0x1FFF => self.isCartRAMEnabled = (byte & 0xF) == 0xA,
+            0x2000...0x3FFF => {
+                //7 bit ROM bank.  Bank 0 maps to bank 1
+                self.currentMBCBank = byte & 0x7F;
+
+                if self.currentMBCBank == 0 {
+                    self.currentMBCBank = 1;
+                }
+            },
+            0x4000...0x5FFF => self.currentRAMBank = byte,
+            _ => { //0x6000...0x7FFF
+                if self.rtc.lastLatchWrite == 0 && byte == 1 {
+                    latchRTC(&mut self.rtc);
+                }
+                self.rtc.lastLatchWrite = byte;
+            }
+        }
+    }
+
+    fn read_ram(&self, addr: u16) -> u8 {
+        if !self.isCartRAMEnabled {
+            return 0xFF;
+        }
+
+        match self.currentRAMBank {
+            0...3 => readBankedRAM(&self.cartRAM, self.currentRAMBank as usize, addr),
+            0x8...0xC => self.rtc.latchedRegisters[(self.currentRAMBank - 0x8) as usize],
+            _ => 0xFF
+        }
+    }
+
+    fn write_ram(&mut self, addr: u16, byte: u8) -> bool {
+        if !self.isCartRAMEnabled {
+            return false;
+        }
+
+        match self.currentRAMBank {
+            0...3 => writeBankedRAM(&mut self.cartRAM, self.currentRAMBank as usize, addr, byte),
+            //the RTC gets saved along with RAM
+            0x8...0xC => {
+                writeRTCRegister(&mut self.rtc, self.currentRAMBank, byte);
+                true
+            },
+            _ => false
+        }
+    }
+
+    fn step(&mut self, cycles: u32) {
+        stepRTC(&mut self.rtc, cycles);
+    }
+
+    //cart RAM followed by the clock
+    fn save_data(&self) -> Vec<u8> {
+        let mut data = self.cartRAM.clone();
+        appendRTCFooter(&self.rtc, &mut data);
+
+        data
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        copySaveData(&mut self.cartRAM, data);
+
+        if data.len() > self.cartRAM.len() {
+            loadRTCFooter(&mut self.rtc, &data[self.cartRAM.len()..]);
+        }
+    }
+
+    fn cart_ram(&self) -> &[u8] {
+        &self.cartRAM[..]
+    }
+
+    fn cart_ram_mut(&mut self) -> &mut [u8] {
+        &mut self.cartRAM[..]
+    }
+
+    fn control_register_writes(&self) -> Vec<(u16, u8)> {
+        vec![(0x0000, if self.isCartRAMEnabled {0xA} else {0}),
+             (0x2000, self.currentMBCBank),
+             (0x4000, self.currentRAMBank)]
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.writeU8(self.currentMBCBank);
+        w.writeU8(self.currentRAMBank);
+        w.writeBool(self.isCartRAMEnabled);
+        w.writeSizedBytes(&self.cartRAM[..]);
+        writeRTCState(w, &self.rtc);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        self.currentMBCBank = try!(r.readU8()) & 0x7F;
+        self.currentRAMBank = try!(r.readU8());
+        self.isCartRAMEnabled = try!(r.readBool());
+        try!(r.readSizedBytes("cart RAM", &mut self.cartRAM[..]));
+
+        readRTCState(r, &mut self.rtc)
+    }
+}
+
+//---------------------------------MBC5---------------------------------------
+
+pub struct MBC5Cartridge {
+    pub romData: Vec<u8>,
+    pub cartRAM: Vec<u8>,
+
+    pub currentMBCBank: u16, //9 bits
+    pub currentRAMBank: u8,
+    pub isCartRAMEnabled: bool,
+
+    pub hasRumble: bool, //rumble carts use bit 3 of the RAM bank register for the motor
+    pub isRumbleMotorOn: bool
+}
+
+impl MBC5Cartridge {
+    pub fn new(romData: Vec<u8>, ramSize: usize, hasRumble: bool) -> MBC5Cartridge {
+        MBC5Cartridge {
+            romData: romData,
+            cartRAM: vec![0;ramSize],
+
+            currentMBCBank: 1,
+            currentRAMBank: 0,
+            isCartRAMEnabled: false,
+
+            hasRumble: hasRumble,
+            isRumbleMotorOn: false
+        }
+    }
+}
+
+impl Cartridge for MBC5Cartridge {
+    fn mbc_type(&self) -> MemoryBankControllerType {
+        MemoryBankControllerType::MBC5
+    }
+
+    fn read_rom(&self, addr: u16) -> u8 {
+        match addr {
+            0...0x3FFF => readBankedROM(&self.romData, 0, addr),
+            _ => readBankedROM(&self.romData, self.currentMBCBank as usize, addr)
+        }
+    }
+
+    fn write_control(&mut self, addr: u16, byte: u8) {
+        match addr {
+            0...0x1FFF => self.isCartRAMEnabled = (byte & 0xF) == 0xA,
+            //9 bit ROM bank.  Unlike other MBCs, bank 0 can be mapped to 0x4000-0x7FFF
+            0x2000...0x2FFF => self.currentMBCBank = (self.currentMBCBank & 0x100) | byte as u16,
+            0x3000...0x3FFF => self.currentMBCBank = (self.currentMBCBank & 0xFF) | (((byte & 1) as u16) << 8),
+            0x4000...0x5FFF => {
+                if self.hasRumble {
+                    self.isRumbleMotorOn = testBit!(byte, 3);
+                    self.currentRAMBank = byte & 0x7;
+                }
+                else {
+                    self.currentRAMBank = byte & 0xF;
+                }
+            },
+            _ => {} //0x6000...0x7FFF has no register
+        }
+    }
+
+    fn read_ram(&self, addr: u16) -> u8 {
+        if self.isCartRAMEnabled {
+            readBankedRAM(&self.cartRAM, self.currentRAMBank as usize, addr)
+        }
+        else {
+            0xFF
+        }
+    }
+
+    fn write_ram(&mut self, addr: u16, byte: u8) -> bool {
+        if self.isCartRAMEnabled {
+            writeBankedRAM(&mut self.cartRAM, self.currentRAMBank as usize, addr, byte)
+        }
+        else {
+            false
+        }
+    }
+
+    fn save_data(&self) -> Vec<u8> {
+        self.cartRAM.clone()
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        copySaveData(&mut self.cartRAM, data);
+    }
+
+    fn cart_ram(&self) -> &[u8] {
+        &self.cartRAM[..]
+    }
+
+    fn cart_ram_mut(&mut self) -> &mut [u8] {
+        &mut self.cartRAM[..]
+    }
+
+    fn control_register_writes(&self) -> Vec<(u16, u8)> {
+        let rumbleBit = if self.isRumbleMotorOn {1 << 3} else {0};
+
+        vec![(0x0000, if self.isCartRAMEnabled {0xA} else {0}),
+             (0x2000, lb(self.currentMBCBank)),
+             (0x3000, hb(self.currentMBCBank)),
+             (0x4000, self.currentRAMBank | rumbleBit)]
+    }
+
+    fn save_state(&self, w: &mut StateWriter) {
+        w.writeU16(self.currentMBCBank);
+        w.writeU8(self.currentRAMBank);
+        w.writeBool(self.isCartRAMEnabled);
+        w.writeBool(self.isRumbleMotorOn);
+        w.writeSizedBytes(&self.cartRAM[..]);
+    }
+
+    fn load_state(&mut self, r: &mut StateReader) -> Result<(), SaveStateError> {
+        self.currentMBCBank = try!(r.readU16()) & 0x1FF;
+        self.currentRAMBank = try!(r.readU8()) & 0xF;
+        self.isCartRAMEnabled = try!(r.readBool());
+        self.isRumbleMotorOn = try!(r.readBool());
+
+        r.readSizedBytes("cart RAM", &mut self.cartRAM[..])
+    }
+
+    fn is_rumble_motor_on(&self) -> bool {
+        self.isRumbleMotorOn
+    }
+}
+
+/*
+ * Creates the right cartridge for the mapper given in the ROM header (0x147)
+ *
+ * Args:
+ *      romData: The entire contents of the ROM
+ *
+ * Return: The cartridge or a message saying why it could not be created
+ *
+ */
+pub fn cartridgeFromROM(romData: Vec<u8>) -> Result<Box<Cartridge>, String> {
+    let header = match parseCartridgeHeader(&romData) {
+        Ok(header) => header,
+        Err(err) => return Err(err.to_string())
+    };
+
+    let ramSize = header.ramSize;
+
+    match header.cartridgeType {
+        0 | 8 | 9 => Ok(Box::new(MBC0Cartridge::new(romData, ramSize))),
+        1...3 => Ok(Box::new(MBC1Cartridge::new(romData, ramSize))),
+        5 | 6 => Ok(Box::new(MBC2Cartridge::new(romData))), //RAM is built into the MBC
+        0xF...0x13 => Ok(Box::new(MBC3Cartridge::new(romData, ramSize))),
+        0x19...0x1B => Ok(Box::new(MBC5Cartridge::new(romData, ramSize, false))),
+        0x1C...0x1E => Ok(Box::new(MBC5Cartridge::new(romData, ramSize, true))), //MBC5 + RUMBLE
+        _ => Err(format!("MBC not yet supported: {}", header.cartridgeType))
+    }
+}
@@ -1,41 +1,19 @@
 use std::fs;
 use std::io;
 use std::io::Read;
+use std::io::Write;
 
-use gb_debug::*;
 use gb_util::*;
 use gb_lcd::*;
 use gb_joypad::*;
-use gb_cpu::CLOCK_SPEED_HZ;
+use gb_apu::*;
+use gb_mbc::*;
+use gb_timer::*;
+use gb_serial::*;
 
-pub const CYCLES_PER_DIVIDER_INCREMENT: u32 = 256;
 pub const CYCLES_PER_DMA_BYTE: u32 = 4;
 
 
-//tells how fast to increment the timer
-#[derive(Copy, Clone)]
-#[repr(u32)]
-pub enum TimerMode  {
-    Mode0 =  CLOCK_SPEED_HZ as u32 / 4096,
-    Mode1 =  CLOCK_SPEED_HZ as u32 / 262144,
-    Mode2 =  CLOCK_SPEED_HZ as u32 / 65536,
-    Mode3 =  CLOCK_SPEED_HZ as u32 / 16384
-}
-
-#[derive(PartialEq)]
-#[repr(u8)]
-pub enum MemoryBankControllerType {
-    MBC0 = 0,
-    MBC1 = 1,
-}
-
-#[repr(u8)]
-pub enum BankingMode {
-    Mode0 = 0, //8kbyte ram; 2MB rom
-    Mode1 = 1  //32kbyte ram; 512kb ROM
-}
-
-
 //NOTE(DanB):anything accessed by MMU goes in here including LCD related function
 pub struct MemoryMapState {
     pub workingRAM: [u8;0x2000],
@@ -47,13 +25,10 @@ pub struct MemoryMapState {
 
     pub lcd: LCDState,
     pub joypad: JoypadState,
+    pub apu: APUState,
 
-    //timer registers
-    pub divider: u8, //DIV
-    pub timerCounter: u8, //TIMA
-    pub timerModulo: u8, //TMA
-    pub timerMode: TimerMode, 
-    pub isTimerEnabled: bool, 
+    pub timer: TimerState,
+    pub serial: SerialState, //attach a SerialDevice to serial.device to plug something into the link port
 
     pub isDMAOccurring: bool,
     pub currentDMAAddress: u16,
@@ -61,14 +36,8 @@ pub struct MemoryMapState {
 
 
     //Cart data
-    pub romData: Vec<u8>,
-    pub mbcType: MemoryBankControllerType,
-    pub cartRAM: Vec<u8>,
-
-    pub currentMBCBank: u8,
-    pub currentRAMBank: u8,
-    pub isCartRAMEnabled: bool,
-    pub bankingMode: BankingMode
+    pub cartridge: Box<Cartridge>,
+    pub isCartRAMDirty: bool //set when cart RAM is written to. Lets the frontend know when to save
 }
 
 impl MemoryMapState {
@@ -84,31 +53,27 @@ impl MemoryMapState {
 
             lcd: LCDState::new(),
             joypad: JoypadState::new(),
+            apu: APUState::new(),
 
-            divider: 0,
-            timerCounter: 0,
-            timerModulo: 0,
-            timerMode: TimerMode::Mode0, 
-            isTimerEnabled: false,
+            timer: TimerState::new(),
+            serial: SerialState::new(),
 
             isDMAOccurring: false,
             currentDMAAddress: 0,
             currentDMACycles: 0,
 
-            romData: vec![],
-            mbcType: MemoryBankControllerType::MBC0,
-            cartRAM: vec![],
-            currentMBCBank: 1,
-            currentRAMBank: 0,
-            isCartRAMEnabled: false,
-            bankingMode: BankingMode::Mode0
+            cartridge: Box::new(MBC0Cartridge::new(vec![], 0)),
+            isCartRAMDirty: false
         }
     }
 
-
+    //lets frontends know when to rumble for MBC5 rumble carts
+    pub fn isRumbleMotorOn(&self) -> bool {
+        self.cartridge.is_rumble_motor_on()
+    }
 }
 
-static BIOS: [u8; 0x100] = [
+pub static BIOS: [u8; 0x100] = [
     0x31, 0xFE, 0xFF, 0xAF, 0x21, 0xFF, 0x9F, 0x32,
     0xCB, 0x7C, 0x20, 0xFB, 0x21, 0x26, 0xFF, 0x0E,
     0x11, 0x3E, 0x80, 0x32, 0xE2, 0x0C, 0x3E, 0xF3,
@@ -186,20 +151,8 @@ pub fn readByteFromMemory(memory: &MemoryMapState, addr: u16) -> u8 {
     let i = addr as usize;
     match addr {
         0...0xFF if memory.inBios => BIOS[i],  
-        0...0xFF if !memory.inBios => memory.romData[i], 
-        0x100...0x3FFF => memory.romData[i],
-        0x4000...0x7FFF =>{
-            match memory.mbcType {
-                MemoryBankControllerType::MBC0 => memory.romData[i],
-                MemoryBankControllerType::MBC1 => {
-                    let addrMultiplier = memory.currentMBCBank as usize - 1;
-                    if i + (0x4000* addrMultiplier) >= memory.romData.len() {
-                        println!("Bad MBC: {:X}", memory.currentMBCBank);
-                    }
-                    memory.romData[i + (0x4000 * addrMultiplier)]
-                }
-            }
-        }
+        0...0xFF if !memory.inBios => memory.cartridge.read_rom(addr), 
+        0x100...0x7FFF => memory.cartridge.read_rom(addr),
         0x8000...0x9FFF => {
             //vram can only be properly accessed when not being drawn from
             if lcd.mode != ScanVRAMAndOAM {
@@ -209,22 +162,7 @@ pub fn readByteFromMemory(memory: &MemoryMapState, addr: u16) -> u8 {
                 0xFF
             }
         }
-        0xA000...0xBFFF => {
-
-            if memory.isCartRAMEnabled {
-                match memory.mbcType {
-                    MemoryBankControllerType::MBC0 => memory.cartRAM[i - 0xA000],
-                    MemoryBankControllerType::MBC1 => {
-                        let addrMultiplier = memory.currentRAMBank as usize;
-                        memory.cartRAM[(i - 0xA000) + (0x2000 * addrMultiplier)]
-                    },
-                }
-            }
-            else {
-                0
-            }
-
-        }
+        0xA000...0xBFFF => memory.cartridge.read_ram(addr),
         0xC000...0xDFFF => memory.workingRAM[i - 0xC000],
         0xE000...0xFDFF => memory.workingRAM[i - 0xE000], //echo of internal RAM 
         0xFE00...0xFE9F => {
@@ -262,33 +200,20 @@ pub fn readByteFromMemory(memory: &MemoryMapState, addr: u16) -> u8 {
 
             joypReg
         },
-        0xFF04 => memory.divider,
-        0xFF05 => memory.timerCounter,
-        0xFF06 => memory.timerModulo,
-        0xFF07 => { //TAC
-            let mut tacReg = 
-                match memory.timerMode {
-                    TimerMode::Mode0 => 0,
-                    TimerMode::Mode1 => 1,
-                    TimerMode::Mode2 => 2,
-                    TimerMode::Mode3 => 3
-                };
-
-            if memory.isTimerEnabled {
-                tacReg |= 1 << 2;
-            }
-            
-            tacReg
-
-        },
+        0xFF01...0xFF02 => readSerialRegister(&memory.serial, addr),
+        0xFF04...0xFF07 => readTimerRegister(&memory.timer, addr),
 
         0xFF0F => memory.requestedInterrupts,
+        0xFF10...0xFF3F => readAPURegister(&memory.apu, addr),
         0xFF40 => { //LCD Control
             let mut control = 0u8;
 
             //Bit 7 - LCD Enabled
             control = if lcd.isEnabled { control | (1 << 7)} else {control};
-            //TODO: Tile stuff goes here
+            //Bit 6 - Window Tile Map Select
+            control |= lcd.windowTileMap << 6;
+            //Bit 5 - Window Enabled
+            control |= if lcd.isWindowEnabled {1 << 5} else {0};
 
             //Bit 4 - Background Tile Set Select
             control |= lcd.backgroundTileSet << 4;
@@ -326,6 +251,8 @@ pub fn readByteFromMemory(memory: &MemoryMapState, addr: u16) -> u8 {
         0xFF47 => u8ForColorPalette(&lcd.palette),
         0xFF48 => u8ForColorPalette(&lcd.spritePalette0),
         0xFF49 => u8ForColorPalette(&lcd.spritePalette1),
+        0xFF4A => lcd.wy,
+        0xFF4B => lcd.wx,
         0xFF50 => if memory.inBios {0} else {1},
         0xFF80...0xFFFE => memory.zeroPageRAM[i - 0xFF80],
         0xFFFF => memory.enabledInterrupts,
@@ -342,65 +269,14 @@ pub fn writeByteToMemory(memory: &mut MemoryMapState, byte: u8, addr: u16) {
 
     let i = addr as usize;
     match addr {
-        0...0x1FFF => {
-            if byte == 0xA {
-                memory.isCartRAMEnabled = true;
-            }
-            else {
-                memory.isCartRAMEnabled = false;
-            }
-        }
-        0x2000...0x3FFF => {
-            //only set low 5 bits
-            memory.currentMBCBank &= 0x60;
-            memory.currentMBCBank |= byte & 0x1F;
-
-            match memory.currentMBCBank {
-                0|0x20|0x40|0x60 => memory.currentMBCBank += 1,
-                _ => {}
-                
-            }
-        }
-        0x4000...0x5FFF => {
-            println!("Setting upper bits to  {:X}", byte);
-            gbDebugPanic!("goddamn");
-            match memory.bankingMode {
-                BankingMode::Mode0 => {
-                    //only set upper 2 bits
-                    memory.currentMBCBank &= 0x1F;
-                    memory.currentMBCBank |= (byte & 0x3) << 5;
-                },
-                BankingMode::Mode1 => memory.currentRAMBank = byte & 0x3,
-            }
-            match memory.currentMBCBank {
-                0|0x20|0x40|0x60 => memory.currentMBCBank += 1,
-                _ => {}
-                
-            }
-        }
-        0x6000...0x7FFF => {
-            println!("changing mode to: {:X}. MBC: {:X}", byte, memory.currentMBCBank);
-            memory.bankingMode = match byte & 0x1 {
-                0 => BankingMode::Mode0,
-                1 => BankingMode::Mode1,
-                _ => panic!("Impossible")
-            };
-        }
+        0...0x7FFF => memory.cartridge.write_control(addr, byte),
         //vram can only be properly accessed when not being drawn from
         0x8000...0x9FFF if lcd.mode != ScanVRAMAndOAM => lcd.videoRAM[i - 0x8000] = byte,
         0xA000...0xBFFF => {
-
-            if memory.isCartRAMEnabled {
-                match memory.mbcType {
-                    MemoryBankControllerType::MBC0 => memory.cartRAM[i - 0xA000] = byte,
-                    MemoryBankControllerType::MBC1 => {
-                        let addrMultiplier = memory.currentRAMBank as usize;
-                        memory.cartRAM[(i - 0xA000) + (addrMultiplier * 0x2000)] = byte;
-                    },
-                }
+            if memory.cartridge.write_ram(addr, byte) {
+                memory.isCartRAMDirty = true;
             }
-
-        }
+        },
         0xC000...0xDFFF => memory.workingRAM[i - 0xC000] = byte,
         0xE000...0xFDFF => memory.workingRAM[i - 0xE000] = byte,
         //TODO: Shouldn't be able to write to OAM memory during these modes.
@@ -417,31 +293,19 @@ pub fn writeByteToMemory(memory: &mut MemoryMapState, byte: u8, addr: u16) {
                 }
 
         },
-        0xFF04 => memory.divider = 0, //reset divider
-        0xFF05 => memory.timerCounter = memory.timerModulo,
-        0xFF06 => memory.timerModulo = byte,
-        0xFF07 =>  { //TAC
-            memory.timerMode = match byte & 3 {
-                0 => TimerMode::Mode0,
-                1 => TimerMode::Mode1,
-                2 => TimerMode::Mode2,
-                3 => TimerMode::Mode3,
-                _ => panic!("This is impossible...")
-            };
-
-            memory.isTimerEnabled = 
-                if byte & (1 << 2) != 0 { 
-                    true
-                }
-                else {
-                    false
-                };
-        },
+        0xFF01...0xFF02 => writeSerialRegister(&mut memory.serial, byte, addr),
+        0xFF04...0xFF07 => writeTimerRegister(&mut memory.timer, byte, addr),
         0xFF0F => memory.requestedInterrupts = byte,
+        0xFF10...0xFF3F => writeAPURegister(&mut memory.apu, byte, addr),
         0xFF40 => { //LCD Control
 
             //Bit 7 - LCD Enabled
             lcd.isEnabled = if (byte & 0x80) != 0 {true} else {false};
+
+            //Bit 6 - Window Tile Map Select
+            lcd.windowTileMap = if testBit!(byte, 6) {1} else {0};
+            //Bit 5 - Window Enabled
+            lcd.isWindowEnabled = testBit!(byte, 5);
             
             //Bit 4 - Background Tile Set Select
             lcd.backgroundTileSet = if testBit!(byte, 4) {1} else {0};
@@ -480,6 +344,8 @@ pub fn writeByteToMemory(memory: &mut MemoryMapState, byte: u8, addr: u16) {
         0xFF47 => updateColorPaletteFromU8(&mut lcd.palette, byte),
         0xFF48 => updateColorPaletteFromU8(&mut lcd.spritePalette0, byte),
         0xFF49 => updateColorPaletteFromU8(&mut lcd.spritePalette1, byte),
+        0xFF4A => lcd.wy = byte,
+        0xFF4B => lcd.wx = byte,
         //TODO: Implement writing to LCD status
         0xFF50 => memory.inBios = if byte != 0 {false} else {true},
         0xFF80...0xFFFE => memory.zeroPageRAM[i - 0xFF80] = byte, 
@@ -502,6 +368,40 @@ pub fn writeWordToMemory(memory: &mut MemoryMapState, word: u16, addr: u16 ) {
     writeByteToMemory(memory, lb(word), addr);
     writeByteToMemory(memory, hb(word), addr+1);
 }
+/*
+ * Advances an OAM DMA transfer started by writing to 0xFF46, one byte per machine cycle
+ *
+ * Args:
+ *      memory: The memory map doing the transfer
+ *      cyclesTakenOfLastInstruction: Cycles to advance by
+ *
+ */
+pub fn stepDMA(memory: &mut MemoryMapState, cyclesTakenOfLastInstruction: u32) {
+    if !memory.isDMAOccurring {
+        return;
+    }
+
+    memory.currentDMACycles += cyclesTakenOfLastInstruction;
+
+    while memory.isDMAOccurring && memory.currentDMACycles >= CYCLES_PER_DMA_BYTE {
+
+        //not finished transferring
+        if (memory.currentDMAAddress & 0xFF) < 0xA0 {
+            let byteToWrite = readByteFromMemory(memory, memory.currentDMAAddress);
+            let destAddr = 0xFE00 + (memory.currentDMAAddress & 0xFF);
+            writeByteToMemory(memory, byteToWrite, destAddr);
+            memory.currentDMACycles -= CYCLES_PER_DMA_BYTE;
+            memory.currentDMAAddress = memory.currentDMAAddress.wrapping_add(1);
+        }
+        //finished transferring
+        else {
+            memory.isDMAOccurring = false;
+            memory.currentDMACycles = 0;
+            memory.currentDMAAddress = 0;
+        }
+    }
+}
+
 pub fn openROM(fileName: &str) -> io::Result<Vec<u8>> {
 
     let mut data: Vec<u8> = vec![];
@@ -511,6 +411,31 @@ pub fn openROM(fileName: &str) -> io::Result<Vec<u8>> {
     Ok(data)
 }
 
+//loads battery backed cart RAM from a save file
+pub fn loadSaveFile(memory: &mut MemoryMapState, fileName: &str) -> io::Result<()> {
+    let mut data: Vec<u8> = vec![];
+    let mut f = try!(fs::File::open(fileName));
+    try!(f.read_to_end(&mut data));
+
+    memory.cartridge.load_save_data(&data[..]);
+    memory.isCartRAMDirty = false;
 
+    Ok(())
+}
 
+//writes battery backed cart RAM to a save file.
+//Writes to a temp file first so a crash mid-write doesn't destroy the old save
+pub fn writeSaveFile(memory: &mut MemoryMapState, fileName: &str) -> io::Result<()> {
+    let tempFileName = format!("{}.tmp", fileName);
 
+    {
+        let mut f = try!(fs::File::create(&tempFileName));
+        try!(f.write_all(&memory.cartridge.save_data()[..]));
+        try!(f.sync_all());
+    }
+
+    try!(fs::rename(&tempFileName, fileName));
+    memory.isCartRAMDirty = false;
+
+    Ok(())
+}
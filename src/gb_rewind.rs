@@ -0,0 +1,147 @@
+/*
+ * Rewind support.  Keeps a ring of save states taken every few frames.
+ *
+ * Only the newest state is kept whole.  Every older state is stored as a delta that turns
+ * the state after it back into it, so memory use stays small since most of the machine
+ * doesn't change between snapshots
+ */
+use std::collections::VecDeque;
+
+use gb_gameboy::*;
+use gb_util::*;
+
+pub struct RewindBuffer {
+    pub deltas: VecDeque<Vec<u8>>, //oldest first
+    pub latestState: Option<Vec<u8>>,
+    pub capacity: usize, //max number of deltas kept
+    pub framesPerSnapshot: u32,
+    pub framesSinceSnapshot: u32
+}
+
+impl RewindBuffer {
+    pub fn new(capacity: usize, framesPerSnapshot: u32) -> RewindBuffer {
+        RewindBuffer {
+            deltas: VecDeque::new(),
+            latestState: None,
+            capacity: capacity,
+            framesPerSnapshot: framesPerSnapshot,
+            framesSinceSnapshot: 0
+        }
+    }
+}
+
+/*
+ * Delta format is a list of runs, each of which is:
+ *      u32 number of unchanged bytes to skip
+ *      u32 number of changed bytes
+ *      the changed bytes XORed with the old bytes
+ * All little endian
+ */
+
+//makes a delta that turns "to" into "from" and vice versa.  Both must be the same length
+pub fn encodeDelta(from: &[u8], to: &[u8]) -> Vec<u8> {
+    debug_assert!(from.len() == to.len());
+
+    let mut delta = vec![];
+    let mut i = 0;
+
+    while i < from.len() {
+        let skipStart = i;
+        while i < from.len() && from[i] == to[i] {
+            i += 1;
+        }
+
+        let changedStart = i;
+        while i < from.len() && from[i] != to[i] {
+            i += 1;
+        }
+
+        pushU32(&mut delta, (changedStart - skipStart) as u32);
+        pushU32(&mut delta, (i - changedStart) as u32);
+        for j in changedStart..i {
+            delta.push(from[j] ^ to[j]);
+        }
+    }
+
+    delta
+}
+
+pub fn applyDelta(state: &mut [u8], delta: &[u8]) {
+    let mut stateIndex = 0;
+    let mut deltaIndex = 0;
+
+    while deltaIndex < delta.len() {
+        stateIndex += readU32(delta, deltaIndex) as usize;
+        let numChanged = readU32(delta, deltaIndex + 4) as usize;
+        deltaIndex += 8;
+
+        for _ in 0..numChanged {
+            state[stateIndex] ^= delta[deltaIndex];
+            stateIndex += 1;
+            deltaIndex += 1;
+        }
+    }
+}
+
+/*
+ * Should be called once per emulated frame.  Takes a snapshot every rewind.framesPerSnapshot frames
+ *
+ * Args:
+ *      rewind: The rewind buffer to record into
+ *      gb: The Game Boy to take snapshots of
+ *
+ */
+pub fn recordRewindFrame(rewind: &mut RewindBuffer, gb: &GameBoyState) {
+    rewind.framesSinceSnapshot += 1;
+
+    if rewind.framesSinceSnapshot < rewind.framesPerSnapshot {
+        return;
+    }
+    rewind.framesSinceSnapshot = 0;
+
+    let state = gb.save_state();
+
+    match rewind.latestState {
+        Some(ref latestState) if latestState.len() == state.len() => {
+            rewind.deltas.push_back(encodeDelta(latestState, &state[..]));
+        },
+        //states are only different sizes if a different game was loaded
+        _ => rewind.deltas.clear()
+    }
+
+    while rewind.deltas.len() > rewind.capacity {
+        rewind.deltas.pop_front();
+    }
+
+    rewind.latestState = Some(state);
+}
+
+/*
+ * Loads the newest snapshot and drops it so the next call goes further back.
+ * The oldest snapshot is never dropped, so holding rewind stops there
+ *
+ * Args:
+ *      rewind: The rewind buffer to step back through
+ *      gb: The Game Boy to load the snapshot into
+ *
+ * Return: false if there is nothing to rewind to
+ *
+ */
+pub fn stepRewindBack(rewind: &mut RewindBuffer, gb: &mut GameBoyState) -> bool {
+    let latestState = match rewind.latestState {
+        Some(ref mut state) => state,
+        None => return false
+    };
+
+    if gb.load_state(&latestState[..]).is_err() {
+        return false;
+    }
+
+    if let Some(delta) = rewind.deltas.pop_back() {
+        applyDelta(&mut latestState[..], &delta[..]);
+    }
+
+    rewind.framesSinceSnapshot = 0;
+
+    true
+}
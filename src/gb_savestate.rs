@@ -0,0 +1,611 @@
+/*
+ * Binary save state format.  A state is:
+ *      magic "GBSS"
+ *      u32 version
+ *      CPU, memory map, LCD, joypad, APU, then cartridge sections in that order
+ *
+ * All values are little endian.  Bump SAVE_STATE_VERSION whenever the layout changes
+ */
+use std::fmt;
+
+use gb_cpu::*;
+use gb_memory::*;
+use gb_lcd::*;
+use gb_joypad::*;
+use gb_apu::*;
+use gb_timer::*;
+use gb_serial::*;
+use gb_mbc::*;
+
+pub const SAVE_STATE_MAGIC: &'static [u8] = b"GBSS";
+pub const SAVE_STATE_VERSION: u32 = 4;
+
+#[derive(PartialEq, Debug)]
+pub enum SaveStateError {
+    BadMagic,
+    UnsupportedVersion(u32),
+    UnexpectedEnd,
+    MBCMismatch { expected: u8, actual: u8 },
+    SizeMismatch { section: &'static str, expected: usize, actual: usize },
+    InvalidValue { field: &'static str, value: u8 }
+}
+
+impl fmt::Display for SaveStateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SaveStateError::BadMagic =>
+                write!(f, "Not a save state"),
+            SaveStateError::UnsupportedVersion(version) =>
+                write!(f, "Unsupported save state version: {}", version),
+            SaveStateError::UnexpectedEnd =>
+                write!(f, "Save state ended early"),
+            SaveStateError::MBCMismatch { expected, actual } =>
+                write!(f, "Save state is for MBC{} but cartridge is MBC{}", actual, expected),
+            SaveStateError::SizeMismatch { section, expected, actual } =>
+                write!(f, "Save state {} is {} bytes. Expected {} bytes", section, actual, expected),
+            SaveStateError::InvalidValue { field, value } =>
+                write!(f, "Invalid value for {} in save state: {:X}", field, value)
+        }
+    }
+}
+
+pub struct StateWriter {
+    pub data: Vec<u8>
+}
+
+impl StateWriter {
+    pub fn new() -> StateWriter {
+        StateWriter {
+            data: vec![]
+        }
+    }
+
+    pub fn writeU8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    pub fn writeBool(&mut self, value: bool) {
+        self.data.push(if value {1} else {0});
+    }
+
+    pub fn writeU16(&mut self, value: u16) {
+        self.data.push(value as u8);
+        self.data.push((value >> 8) as u8);
+    }
+
+    pub fn writeU32(&mut self, value: u32) {
+        self.writeU16(value as u16);
+        self.writeU16((value >> 16) as u16);
+    }
+
+    pub fn writeU64(&mut self, value: u64) {
+        self.writeU32(value as u32);
+        self.writeU32((value >> 32) as u32);
+    }
+
+    pub fn writeBytes(&mut self, bytes: &[u8]) {
+        self.data.extend(bytes.iter().cloned());
+    }
+
+    //for buffers whose size isn't fixed, like cart RAM
+    pub fn writeSizedBytes(&mut self, bytes: &[u8]) {
+        self.writeU32(bytes.len() as u32);
+        self.writeBytes(bytes);
+    }
+}
+
+pub struct StateReader<'a> {
+    pub data: &'a [u8],
+    pub position: usize
+}
+
+impl<'a> StateReader<'a> {
+    pub fn new(data: &'a [u8]) -> StateReader<'a> {
+        StateReader {
+            data: data,
+            position: 0
+        }
+    }
+
+    pub fn readU8(&mut self) -> Result<u8, SaveStateError> {
+        match self.data.get(self.position) {
+            Some(byte) => {
+                self.position += 1;
+                Ok(*byte)
+            },
+            None => Err(SaveStateError::UnexpectedEnd)
+        }
+    }
+
+    pub fn readBool(&mut self) -> Result<bool, SaveStateError> {
+        Ok(try!(self.readU8()) != 0)
+    }
+
+    pub fn readU16(&mut self) -> Result<u16, SaveStateError> {
+        let low = try!(self.readU8()) as u16;
+        let high = try!(self.readU8()) as u16;
+
+        Ok((high << 8) | low)
+    }
+
+    pub fn readU32(&mut self) -> Result<u32, SaveStateError> {
+        let low = try!(self.readU16()) as u32;
+        let high = try!(self.readU16()) as u32;
+
+        Ok((high << 16) | low)
+    }
+
+    pub fn readU64(&mut self) -> Result<u64, SaveStateError> {
+        let low = try!(self.readU32()) as u64;
+        let high = try!(self.readU32()) as u64;
+
+        Ok((high << 32) | low)
+    }
+
+    pub fn readBytes(&mut self, dest: &mut [u8]) -> Result<(), SaveStateError> {
+        if self.position + dest.len() > self.data.len() {
+            return Err(SaveStateError::UnexpectedEnd);
+        }
+
+        for (i, byte) in dest.iter_mut().enumerate() {
+            *byte = self.data[self.position + i];
+        }
+        self.position += dest.len();
+
+        Ok(())
+    }
+
+    //reads bytes written by writeSizedBytes().  The size must match dest
+    pub fn readSizedBytes(&mut self, section: &'static str, dest: &mut [u8]) -> Result<(), SaveStateError> {
+        let size = try!(self.readU32()) as usize;
+
+        if size != dest.len() {
+            return Err(SaveStateError::SizeMismatch {
+                section: section,
+                expected: dest.len(),
+                actual: size
+            });
+        }
+
+        self.readBytes(dest)
+    }
+}
+
+//---------------------------------CPU---------------------------------------
+
+pub fn writeCPUState(w: &mut StateWriter, cpu: &CPUState) {
+    w.writeU16(cpu.PC);
+    w.writeU16(cpu.SP);
+    w.writeBytes(&[cpu.A, cpu.B, cpu.C, cpu.D, cpu.E, cpu.F, cpu.H, cpu.L]);
+    w.writeU64(cpu.totalCycles);
+    w.writeU32(cpu.instructionCycles);
+    w.writeBool(cpu.enableInterrupts);
+    w.writeBool(cpu.isHalted);
+    w.writeBool(cpu.isHaltBugTriggered);
+}
+
+pub fn readCPUState(r: &mut StateReader, cpu: &mut CPUState) -> Result<(), SaveStateError> {
+    cpu.PC = try!(r.readU16());
+    cpu.SP = try!(r.readU16());
+    cpu.A = try!(r.readU8());
+    cpu.B = try!(r.readU8());
+    cpu.C = try!(r.readU8());
+    cpu.D = try!(r.readU8());
+    cpu.E = try!(r.readU8());
+    cpu.F = try!(r.readU8());
+    cpu.H = try!(r.readU8());
+    cpu.L = try!(r.readU8());
+    cpu.totalCycles = try!(r.readU64());
+    cpu.instructionCycles = try!(r.readU32());
+    cpu.enableInterrupts = try!(r.readBool());
+    cpu.isHalted = try!(r.readBool());
+    cpu.isHaltBugTriggered = try!(r.readBool());
+
+    Ok(())
+}
+
+//---------------------------------Memory Map--------------------------------
+
+//everything in the memory map except the LCD, joypad, APU and cartridge, which get their own sections
+pub fn writeMemoryMapState(w: &mut StateWriter, mem: &MemoryMapState) {
+    w.writeBytes(&mem.workingRAM[..]);
+    w.writeBytes(&mem.zeroPageRAM[..]);
+    w.writeBool(mem.inBios);
+
+    w.writeU8(mem.requestedInterrupts);
+    w.writeU8(mem.enabledInterrupts);
+
+    writeTimerState(w, &mem.timer);
+    writeSerialState(w, &mem.serial);
+
+    w.writeBool(mem.isDMAOccurring);
+    w.writeU16(mem.currentDMAAddress);
+    w.writeU32(mem.currentDMACycles);
+}
+
+pub fn readMemoryMapState(r: &mut StateReader, mem: &mut MemoryMapState) -> Result<(), SaveStateError> {
+    try!(r.readBytes(&mut mem.workingRAM[..]));
+    try!(r.readBytes(&mut mem.zeroPageRAM[..]));
+    mem.inBios = try!(r.readBool());
+
+    mem.requestedInterrupts = try!(r.readU8());
+    mem.enabledInterrupts = try!(r.readU8());
+
+    try!(readTimerState(r, &mut mem.timer));
+    try!(readSerialState(r, &mut mem.serial));
+
+    mem.isDMAOccurring = try!(r.readBool());
+    mem.currentDMAAddress = try!(r.readU16());
+    mem.currentDMACycles = try!(r.readU32());
+
+    Ok(())
+}
+
+//---------------------------------Timer-------------------------------------
+
+fn writeTimerState(w: &mut StateWriter, timer: &TimerState) {
+    w.writeU16(timer.internalCounter);
+    w.writeU8(timer.tima);
+    w.writeU8(timer.tma);
+    w.writeBool(timer.isEnabled);
+    w.writeU8(timer.clockSelect);
+    w.writeBool(timer.isOverflowPending);
+    w.writeBool(timer.isReloading);
+    w.writeU32(timer.leftoverCycles);
+}
+
+fn readTimerState(r: &mut StateReader, timer: &mut TimerState) -> Result<(), SaveStateError> {
+    timer.internalCounter = try!(r.readU16());
+    timer.tima = try!(r.readU8());
+    timer.tma = try!(r.readU8());
+    timer.isEnabled = try!(r.readBool());
+    timer.clockSelect = try!(r.readU8()) & 0x3;
+    timer.isOverflowPending = try!(r.readBool());
+    timer.isReloading = try!(r.readBool());
+    timer.leftoverCycles = try!(r.readU32());
+
+    Ok(())
+}
+
+//---------------------------------Serial------------------------------------
+
+//the attached device isn't part of the state
+fn writeSerialState(w: &mut StateWriter, serial: &SerialState) {
+    w.writeU8(serial.sb);
+    w.writeBool(serial.isTransferring);
+    w.writeBool(serial.isInternalClock);
+    w.writeU8(serial.incomingByte);
+    w.writeU8(serial.bitsTransferred);
+    w.writeU32(serial.cycleCounter);
+}
+
+fn readSerialState(r: &mut StateReader, serial: &mut SerialState) -> Result<(), SaveStateError> {
+    serial.sb = try!(r.readU8());
+    serial.isTransferring = try!(r.readBool());
+    serial.isInternalClock = try!(r.readBool());
+    serial.incomingByte = try!(r.readU8());
+
+    serial.bitsTransferred = try!(r.readU8());
+    if serial.bitsTransferred >= 8 {
+        return Err(SaveStateError::InvalidValue { field: "serial bits transferred", value: serial.bitsTransferred });
+    }
+
+    serial.cycleCounter = try!(r.readU32());
+
+    Ok(())
+}
+
+//---------------------------------LCD---------------------------------------
+
+//colors are stored as their color number (0 is white, 3 is black)
+fn colorToU8(color: PaletteColor) -> u8 {
+    match color {
+        WHITE => 0,
+        LIGHT_GRAY => 1,
+        DARK_GRAY => 2,
+        _ => 3
+    }
+}
+
+fn colorFromU8(value: u8) -> Result<PaletteColor, SaveStateError> {
+    match value {
+        0 => Ok(WHITE),
+        1 => Ok(LIGHT_GRAY),
+        2 => Ok(DARK_GRAY),
+        3 => Ok(BLACK),
+        _ => Err(SaveStateError::InvalidValue { field: "color", value: value })
+    }
+}
+
+fn writeColors(w: &mut StateWriter, colors: &[PaletteColor]) {
+    for color in colors.iter() {
+        w.writeU8(colorToU8(*color));
+    }
+}
+
+fn readColors(r: &mut StateReader, colors: &mut [PaletteColor]) -> Result<(), SaveStateError> {
+    for color in colors.iter_mut() {
+        *color = try!(colorFromU8(try!(r.readU8())));
+    }
+
+    Ok(())
+}
+
+pub fn writeLCDState(w: &mut StateWriter, lcd: &LCDState) {
+    writeColors(w, &lcd.palette[..]);
+    writeColors(w, &lcd.spritePalette0[..]);
+    writeColors(w, &lcd.spritePalette1[..]);
+    w.writeBytes(&lcd.videoRAM[..]);
+    w.writeBytes(&lcd.oam[..]);
+
+    w.writeU8(lcd.mode as u8);
+    w.writeU32(lcd.modeClock);
+    w.writeU8(lcd.currScanLine);
+    w.writeU8(lcd.backgroundTileMap);
+    w.writeU8(lcd.backgroundTileSet);
+    w.writeBool(lcd.isBackgroundEnabled);
+    w.writeBool(lcd.isEnabled);
+    w.writeBool(lcd.isOAMEnabled);
+
+    w.writeU8(lcd.windowTileMap);
+    w.writeBool(lcd.isWindowEnabled);
+    w.writeU8(lcd.wx);
+    w.writeU8(lcd.wy);
+    w.writeU8(lcd.windowLine);
+
+    w.writeU8(lcd.scx);
+    w.writeU8(lcd.scy);
+    w.writeU8(lcd.spriteHeight as u8);
+
+    w.writeU8(lcd.lcdc);
+    w.writeU8(lcd.lyc);
+
+    //screens are kept so a loaded state shows the right picture before the next frame is drawn
+    for row in lcd.screen.iter().chain(lcd.screenBackBuffer.iter()) {
+        writeColors(w, &row[..]);
+    }
+}
+
+pub fn readLCDState(r: &mut StateReader, lcd: &mut LCDState) -> Result<(), SaveStateError> {
+    try!(readColors(r, &mut lcd.palette[..]));
+    try!(readColors(r, &mut lcd.spritePalette0[..]));
+    try!(readColors(r, &mut lcd.spritePalette1[..]));
+    try!(r.readBytes(&mut lcd.videoRAM[..]));
+    try!(r.readBytes(&mut lcd.oam[..]));
+
+    lcd.mode = match try!(r.readU8()) {
+        0 => LCDMode::HBlank,
+        1 => LCDMode::VBlank,
+        2 => LCDMode::ScanOAM,
+        3 => LCDMode::ScanVRAMAndOAM,
+        value => return Err(SaveStateError::InvalidValue { field: "LCD mode", value: value })
+    };
+    lcd.modeClock = try!(r.readU32());
+    lcd.currScanLine = try!(r.readU8());
+    lcd.backgroundTileMap = try!(r.readU8());
+    lcd.backgroundTileSet = try!(r.readU8());
+    lcd.isBackgroundEnabled = try!(r.readBool());
+    lcd.isEnabled = try!(r.readBool());
+    lcd.isOAMEnabled = try!(r.readBool());
+
+    lcd.windowTileMap = try!(r.readU8());
+    lcd.isWindowEnabled = try!(r.readBool());
+    lcd.wx = try!(r.readU8());
+    lcd.wy = try!(r.readU8());
+    lcd.windowLine = try!(r.readU8());
+
+    lcd.scx = try!(r.readU8());
+    lcd.scy = try!(r.readU8());
+    lcd.spriteHeight = match try!(r.readU8()) {
+        8 => SpriteHeight::Short,
+        16 => SpriteHeight::Tall,
+        value => return Err(SaveStateError::InvalidValue { field: "sprite height", value: value })
+    };
+
+    lcd.lcdc = try!(r.readU8());
+    lcd.lyc = try!(r.readU8());
+
+    for row in lcd.screen.iter_mut() {
+        try!(readColors(r, &mut row[..]));
+    }
+    for row in lcd.screenBackBuffer.iter_mut() {
+        try!(readColors(r, &mut row[..]));
+    }
+
+    Ok(())
+}
+
+//---------------------------------Joypad------------------------------------
+
+pub fn writeJoypadState(w: &mut StateWriter, joypad: &JoypadState) {
+    for button in [joypad.a, joypad.b, joypad.select, joypad.start,
+                   joypad.right, joypad.left, joypad.up, joypad.down].iter() {
+        w.writeU8(*button as u8);
+    }
+
+    w.writeU8(match joypad.selectedButtonGroup {
+        ButtonGroup::FaceButtons => 0,
+        ButtonGroup::DPad => 1,
+        ButtonGroup::Nothing => 2
+    });
+}
+
+pub fn readJoypadState(r: &mut StateReader, joypad: &mut JoypadState) -> Result<(), SaveStateError> {
+    {
+        let mut buttons = [&mut joypad.a, &mut joypad.b, &mut joypad.select, &mut joypad.start,
+                       &mut joypad.right, &mut joypad.left, &mut joypad.up, &mut joypad.down];
+
+        for button in buttons.iter_mut() {
+            **button = match try!(r.readU8()) {
+                0 => ButtonState::Down,
+                _ => ButtonState::Up
+            };
+        }
+    }
+
+    joypad.selectedButtonGroup = match try!(r.readU8()) {
+        0 => ButtonGroup::FaceButtons,
+        1 => ButtonGroup::DPad,
+        2 => ButtonGroup::Nothing,
+        value => return Err(SaveStateError::InvalidValue { field: "button group", value: value })
+    };
+
+    Ok(())
+}
+
+//---------------------------------APU---------------------------------------
+
+fn writeEnvelope(w: &mut StateWriter, envelope: &Envelope) {
+    w.writeU8(envelope.initialVolume);
+    w.writeBool(envelope.isIncreasing);
+    w.writeU8(envelope.period);
+    w.writeU8(envelope.volume);
+    w.writeU8(envelope.timer);
+}
+
+fn readEnvelope(r: &mut StateReader, envelope: &mut Envelope) -> Result<(), SaveStateError> {
+    envelope.initialVolume = try!(r.readU8());
+    envelope.isIncreasing = try!(r.readBool());
+    envelope.period = try!(r.readU8());
+    envelope.volume = try!(r.readU8());
+    envelope.timer = try!(r.readU8());
+
+    Ok(())
+}
+
+fn writeSquareChannel(w: &mut StateWriter, square: &SquareChannel) {
+    w.writeBool(square.isEnabled);
+    w.writeBool(square.isDACEnabled);
+    w.writeU8(square.duty);
+    w.writeU8(square.dutyStep as u8);
+    w.writeU16(square.lengthCounter);
+    w.writeBool(square.isLengthEnabled);
+    writeEnvelope(w, &square.envelope);
+    w.writeU16(square.frequency);
+    w.writeU32(square.frequencyTimer);
+    w.writeU8(square.sweepPeriod);
+    w.writeBool(square.isSweepNegated);
+    w.writeU8(square.sweepShift);
+    w.writeU8(square.sweepTimer);
+    w.writeU16(square.shadowFrequency);
+    w.writeBool(square.isSweepEnabled);
+}
+
+fn readSquareChannel(r: &mut StateReader, square: &mut SquareChannel) -> Result<(), SaveStateError> {
+    square.isEnabled = try!(r.readBool());
+    square.isDACEnabled = try!(r.readBool());
+    square.duty = try!(r.readU8()) & 0x3;
+    square.dutyStep = (try!(r.readU8()) & 0x7) as usize;
+    square.lengthCounter = try!(r.readU16());
+    square.isLengthEnabled = try!(r.readBool());
+    try!(readEnvelope(r, &mut square.envelope));
+    square.frequency = try!(r.readU16());
+    square.frequencyTimer = try!(r.readU32());
+    square.sweepPeriod = try!(r.readU8());
+    square.isSweepNegated = try!(r.readBool());
+    square.sweepShift = try!(r.readU8());
+    square.sweepTimer = try!(r.readU8());
+    square.shadowFrequency = try!(r.readU16());
+    square.isSweepEnabled = try!(r.readBool());
+
+    Ok(())
+}
+
+pub fn writeAPUState(w: &mut StateWriter, apu: &APUState) {
+    w.writeBool(apu.isEnabled);
+
+    writeSquareChannel(w, &apu.square1);
+    writeSquareChannel(w, &apu.square2);
+
+    w.writeBool(apu.wave.isEnabled);
+    w.writeBool(apu.wave.isDACEnabled);
+    w.writeU16(apu.wave.lengthCounter);
+    w.writeBool(apu.wave.isLengthEnabled);
+    w.writeU8(apu.wave.volumeCode);
+    w.writeU16(apu.wave.frequency);
+    w.writeU32(apu.wave.frequencyTimer);
+    w.writeU8(apu.wave.position as u8);
+    w.writeBytes(&apu.wave.waveRAM[..]);
+
+    w.writeBool(apu.noise.isEnabled);
+    w.writeBool(apu.noise.isDACEnabled);
+    w.writeU16(apu.noise.lengthCounter);
+    w.writeBool(apu.noise.isLengthEnabled);
+    writeEnvelope(w, &apu.noise.envelope);
+    w.writeU8(apu.noise.clockShift);
+    w.writeBool(apu.noise.isWidth7Bit);
+    w.writeU8(apu.noise.divisorCode);
+    w.writeU32(apu.noise.frequencyTimer);
+    w.writeU16(apu.noise.lfsr);
+
+    w.writeBool(apu.isVinLeftEnabled);
+    w.writeU8(apu.leftVolume);
+    w.writeBool(apu.isVinRightEnabled);
+    w.writeU8(apu.rightVolume);
+    w.writeU8(apu.channelPanning);
+
+    w.writeU8(apu.frameSequencerStep);
+    w.writeBool(apu.lastDividerBit);
+    w.writeU32(apu.sampleCycles);
+}
+
+pub fn readAPUState(r: &mut StateReader, apu: &mut APUState) -> Result<(), SaveStateError> {
+    apu.isEnabled = try!(r.readBool());
+
+    try!(readSquareChannel(r, &mut apu.square1));
+    try!(readSquareChannel(r, &mut apu.square2));
+
+    apu.wave.isEnabled = try!(r.readBool());
+    apu.wave.isDACEnabled = try!(r.readBool());
+    apu.wave.lengthCounter = try!(r.readU16());
+    apu.wave.isLengthEnabled = try!(r.readBool());
+    apu.wave.volumeCode = try!(r.readU8()) & 0x3;
+    apu.wave.frequency = try!(r.readU16());
+    apu.wave.frequencyTimer = try!(r.readU32());
+    apu.wave.position = (try!(r.readU8()) & 0x1F) as usize;
+    try!(r.readBytes(&mut apu.wave.waveRAM[..]));
+
+    apu.noise.isEnabled = try!(r.readBool());
+    apu.noise.isDACEnabled = try!(r.readBool());
+    apu.noise.lengthCounter = try!(r.readU16());
+    apu.noise.isLengthEnabled = try!(r.readBool());
+    try!(readEnvelope(r, &mut apu.noise.envelope));
+    apu.noise.clockShift = try!(r.readU8());
+    apu.noise.isWidth7Bit = try!(r.readBool());
+    apu.noise.divisorCode = try!(r.readU8()) & 0x7;
+    apu.noise.frequencyTimer = try!(r.readU32());
+    apu.noise.lfsr = try!(r.readU16());
+
+    apu.isVinLeftEnabled = try!(r.readBool());
+    apu.leftVolume = try!(r.readU8());
+    apu.isVinRightEnabled = try!(r.readBool());
+    apu.rightVolume = try!(r.readU8());
+    apu.channelPanning = try!(r.readU8());
+
+    apu.frameSequencerStep = try!(r.readU8()) & 0x7;
+    apu.lastDividerBit = try!(r.readBool());
+    apu.sampleCycles = try!(r.readU32());
+
+    Ok(())
+}
+
+//---------------------------------Cartridge---------------------------------
+
+//the MBC type goes first so a state can't be loaded into the wrong kind of cartridge
+pub fn writeCartridgeState(w: &mut StateWriter, cartridge: &Cartridge) {
+    w.writeU8(cartridge.mbc_type() as u8);
+    cartridge.save_state(w);
+}
+
+pub fn readCartridgeState(r: &mut StateReader, cartridge: &mut Cartridge) -> Result<(), SaveStateError> {
+    let mbcType = try!(r.readU8());
+
+    if mbcType != cartridge.mbc_type() as u8 {
+        return Err(SaveStateError::MBCMismatch {
+            expected: cartridge.mbc_type() as u8,
+            actual: mbcType
+        });
+    }
+
+    cartridge.load_state(r)
+}
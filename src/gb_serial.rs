@@ -0,0 +1,165 @@
+/*
+ * Serial port (SB at 0xFF01 and SC at 0xFF02).
+ *
+ * A transfer shifts SB out one bit at a time, most significant bit first, while the other side's
+ * bits get shifted in.  With the internal clock, the Game Boy shifts a bit every 512 cycles (8192 Hz).
+ * With the external clock, nothing happens until whatever is plugged in clocks the transfer,
+ * so a transfer with nothing plugged in never finishes, just like on hardware.
+ *
+ * Whatever is on the other end of the cable is a SerialDevice
+ */
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub const SERIAL_INTERRUPT: u8 = 1 << 3;
+
+const CYCLES_PER_SERIAL_BIT: u32 = 512;
+const BITS_PER_TRANSFER: u8 = 8;
+
+//what a disconnected cable reads as
+const DISCONNECTED_BYTE: u8 = 0xFF;
+
+pub trait SerialDevice {
+    //Called when the Game Boy starts a transfer with its internal clock.
+    //Receives the byte being sent and returns the byte the device sends back
+    fn transfer_byte(&mut self, byte: u8) -> u8;
+
+    //Polled while the Game Boy waits on an external clock.  Receives the byte waiting to be sent.
+    //Returns the byte the device sends back once it clocks the transfer, or None to keep waiting
+    fn external_clock_transfer(&mut self, _byte: u8) -> Option<u8> {
+        None
+    }
+}
+
+//nothing plugged in
+pub struct DisconnectedSerialDevice;
+
+impl SerialDevice for DisconnectedSerialDevice {
+    fn transfer_byte(&mut self, _byte: u8) -> u8 {
+        DISCONNECTED_BYTE
+    }
+}
+
+//Records every byte sent.  Test ROMs print their results this way.
+//Clone output before attaching the device to read the bytes afterwards
+pub struct SerialCapture {
+    pub output: Rc<RefCell<Vec<u8>>>
+}
+
+impl SerialCapture {
+    pub fn new() -> SerialCapture {
+        SerialCapture {
+            output: Rc::new(RefCell::new(vec![]))
+        }
+    }
+}
+
+impl SerialDevice for SerialCapture {
+    fn transfer_byte(&mut self, byte: u8) -> u8 {
+        self.output.borrow_mut().push(byte);
+        DISCONNECTED_BYTE
+    }
+}
+
+pub struct SerialState {
+    pub sb: u8, //SB
+
+    //SC
+    pub isTransferring: bool, //Bit 7
+    pub isInternalClock: bool, //Bit 0
+
+    pub incomingByte: u8, //byte being shifted into SB
+    pub bitsTransferred: u8,
+    pub cycleCounter: u32, //cycles since the last bit was shifted
+
+    pub device: Box<SerialDevice>
+}
+
+impl SerialState {
+    pub fn new() -> SerialState {
+        SerialState {
+            sb: 0,
+
+            isTransferring: false,
+            isInternalClock: false,
+
+            incomingByte: DISCONNECTED_BYTE,
+            bitsTransferred: 0,
+            cycleCounter: 0,
+
+            device: Box::new(DisconnectedSerialDevice)
+        }
+    }
+}
+
+fn finishTransfer(serial: &mut SerialState, requestedInterrupts: &mut u8) {
+    serial.isTransferring = false;
+    serial.bitsTransferred = 0;
+    serial.cycleCounter = 0;
+    *requestedInterrupts |= SERIAL_INTERRUPT;
+}
+
+/*
+ * Advances a serial transfer by the number of cycles that have passed
+ *
+ * Args:
+ *      serial: The serial port to step
+ *      requestedInterrupts: IF register.  The serial interrupt gets requested when a transfer finishes
+ *      cyclesTakenOfLastInstruction: Cycles to advance by
+ *
+ */
+pub fn stepSerial(serial: &mut SerialState, requestedInterrupts: &mut u8, cyclesTakenOfLastInstruction: u32) {
+    if !serial.isTransferring {
+        return;
+    }
+
+    if !serial.isInternalClock {
+        //the other side's clock shifts the whole byte at once as far as we can tell
+        if let Some(incomingByte) = serial.device.external_clock_transfer(serial.sb) {
+            serial.sb = incomingByte;
+            finishTransfer(serial, requestedInterrupts);
+        }
+
+        return;
+    }
+
+    serial.cycleCounter += cyclesTakenOfLastInstruction;
+
+    while serial.isTransferring && serial.cycleCounter >= CYCLES_PER_SERIAL_BIT {
+        serial.cycleCounter -= CYCLES_PER_SERIAL_BIT;
+
+        let incomingBit = (serial.incomingByte >> (BITS_PER_TRANSFER - 1 - serial.bitsTransferred)) & 1;
+        serial.sb = (serial.sb << 1) | incomingBit;
+        serial.bitsTransferred += 1;
+
+        if serial.bitsTransferred == BITS_PER_TRANSFER {
+            finishTransfer(serial, requestedInterrupts);
+        }
+    }
+}
+
+pub fn readSerialRegister(serial: &SerialState, addr: u16) -> u8 {
+    match addr {
+        0xFF01 => serial.sb,
+        //unused bits read as 1
+        0xFF02 => 0x7E | (if serial.isTransferring {1 << 7} else {0}) | (if serial.isInternalClock {1} else {0}),
+        _ => panic!("Serial registers are only 0xFF01 and 0xFF02")
+    }
+}
+
+pub fn writeSerialRegister(serial: &mut SerialState, byte: u8, addr: u16) {
+    match addr {
+        0xFF01 => serial.sb = byte,
+        0xFF02 => {
+            serial.isTransferring = testBit!(byte, 7);
+            serial.isInternalClock = testBit!(byte, 0);
+            serial.bitsTransferred = 0;
+            serial.cycleCounter = 0;
+
+            if serial.isTransferring && serial.isInternalClock {
+                serial.incomingByte = serial.device.transfer_byte(serial.sb);
+            }
+        },
+        _ => panic!("Serial registers are only 0xFF01 and 0xFF02")
+    }
+}
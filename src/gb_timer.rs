@@ -0,0 +1,162 @@
+/*
+ * DIV and TIMA timer emulation.
+ *
+ * Both registers are driven by one 16-bit counter that goes up every cycle.  DIV is its upper 8 bits
+ * and TIMA goes up whenever the counter bit selected by TAC falls from 1 to 0.  Since increments
+ * come from falling edges, writes that clear the selected bit (resetting DIV or changing TAC)
+ * can increment TIMA too, just like on hardware
+ *
+ */
+
+pub const TIMER_INTERRUPT: u8 = 1 << 2;
+
+const CYCLES_PER_TICK: u32 = 4; //the timer is clocked once per machine cycle
+
+pub struct TimerState {
+    pub internalCounter: u16, //DIV is the upper 8 bits
+    pub tima: u8, //TIMA
+    pub tma: u8, //TMA
+
+    //TAC
+    pub isEnabled: bool, //Bit 2
+    pub clockSelect: u8, //Bits 0-1
+
+    //TIMA reads 0 for one machine cycle after overflowing, then gets reloaded from TMA
+    pub isOverflowPending: bool,
+    //true during the machine cycle TIMA is reloaded.  Writes to TIMA are ignored and
+    //writes to TMA also go to TIMA during this cycle
+    pub isReloading: bool,
+
+    pub leftoverCycles: u32 //cycles that didn't add up to a full machine cycle
+}
+
+impl TimerState {
+    pub fn new() -> TimerState {
+        TimerState {
+            internalCounter: 0,
+            tima: 0,
+            tma: 0,
+
+            isEnabled: false,
+            clockSelect: 0,
+
+            isOverflowPending: false,
+            isReloading: false,
+
+            leftoverCycles: 0
+        }
+    }
+}
+
+//which bit of the internal counter clocks TIMA for each TAC clock select
+fn selectedCounterBit(clockSelect: u8) -> u16 {
+    match clockSelect & 3 {
+        0 => 9, //4096 Hz
+        1 => 3, //262144 Hz
+        2 => 5, //65536 Hz
+        _ => 7  //16384 Hz
+    }
+}
+
+//the signal that TIMA increments on the falling edge of
+fn timerInput(timer: &TimerState) -> bool {
+    timer.isEnabled && testBit!(timer.internalCounter, selectedCounterBit(timer.clockSelect))
+}
+
+fn incrementTIMA(timer: &mut TimerState) {
+    timer.tima = timer.tima.wrapping_add(1);
+
+    if timer.tima == 0 {
+        timer.isOverflowPending = true;
+    }
+}
+
+fn tickTimer(timer: &mut TimerState, requestedInterrupts: &mut u8) {
+    timer.isReloading = false;
+
+    if timer.isOverflowPending {
+        timer.isOverflowPending = false;
+        timer.isReloading = true;
+        timer.tima = timer.tma;
+        *requestedInterrupts |= TIMER_INTERRUPT;
+    }
+
+    let oldInput = timerInput(timer);
+    timer.internalCounter = timer.internalCounter.wrapping_add(CYCLES_PER_TICK as u16);
+
+    if oldInput && !timerInput(timer) {
+        incrementTIMA(timer);
+    }
+}
+
+/*
+ * Advances the timer by the number of cycles that have passed
+ *
+ * Args:
+ *      timer: The timer to step
+ *      requestedInterrupts: IF register.  The timer interrupt gets requested when TIMA is reloaded
+ *      cyclesTakenOfLastInstruction: Cycles to advance by
+ *
+ */
+pub fn stepTimer(timer: &mut TimerState, requestedInterrupts: &mut u8, cyclesTakenOfLastInstruction: u32) {
+    timer.leftoverCycles += cyclesTakenOfLastInstruction;
+
+    while timer.leftoverCycles >= CYCLES_PER_TICK {
+        timer.leftoverCycles -= CYCLES_PER_TICK;
+        tickTimer(timer, requestedInterrupts);
+    }
+}
+
+//value of the DIV register
+pub fn dividerRegister(timer: &TimerState) -> u8 {
+    (timer.internalCounter >> 8) as u8
+}
+
+pub fn readTimerRegister(timer: &TimerState, addr: u16) -> u8 {
+    match addr {
+        0xFF04 => dividerRegister(timer),
+        0xFF05 => timer.tima,
+        0xFF06 => timer.tma,
+        0xFF07 => 0xF8 | (if timer.isEnabled {1 << 2} else {0}) | timer.clockSelect, //unused bits read as 1
+        _ => panic!("Timer registers are only 0xFF04 to 0xFF07")
+    }
+}
+
+pub fn writeTimerRegister(timer: &mut TimerState, byte: u8, addr: u16) {
+    match addr {
+        0xFF04 => {
+            //resetting the counter is a falling edge if the selected bit was set
+            let oldInput = timerInput(timer);
+            timer.internalCounter = 0;
+
+            if oldInput {
+                incrementTIMA(timer);
+            }
+        },
+        0xFF05 => {
+            if !timer.isReloading {
+                timer.tima = byte;
+                //writing during the cycle after an overflow cancels the reload and interrupt
+                timer.isOverflowPending = false;
+            }
+        },
+        0xFF06 => {
+            timer.tma = byte;
+
+            if timer.isReloading {
+                timer.tima = byte;
+            }
+        },
+        0xFF07 => {
+            //on the DMG, disabling the timer or switching to a cleared bit is a falling edge too
+            let oldInput = timerInput(timer);
+            timer.isEnabled = testBit!(byte, 2);
+            timer.clockSelect = byte & 3;
+
+            if oldInput && !timerInput(timer) {
+                incrementTIMA(timer);
+            }
+        },
+        _ => panic!("Timer registers are only 0xFF04 to 0xFF07")
+    }
+}
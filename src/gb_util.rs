@@ -18,6 +18,23 @@ pub fn lb(word: u16) -> u8 {
     word as u8
 }
 
+//reads a little endian word starting at data[i]
+pub fn readU16(data: &[u8], i: usize) -> u16 {
+    word(data[i + 1], data[i])
+}
+
+//reads a little endian 32 bit value starting at data[i]
+pub fn readU32(data: &[u8], i: usize) -> u32 {
+    ((readU16(data, i + 2) as u32) << 16) | readU16(data, i) as u32
+}
+
+//appends a 32 bit value to data in little endian
+pub fn pushU32(data: &mut Vec<u8>, value: u32) {
+    for i in 0..4 {
+        data.push((value >> (i * 8)) as u8);
+    }
+}
+
 macro_rules! testBit {
     ($num: expr, $bit: expr) => ({
         ($num & (1 << $bit)) != 0
@@ -3,11 +3,15 @@
 #![allow(non_upper_case_globals)]
 
 
+#[cfg(feature = "sdl")]
 extern crate sdl2_sys;
+#[cfg(feature = "sdl")]
 extern crate sdl2;
 extern crate libc;
 extern crate errno;
 
+//only the debug overlay needs SDL.  Build with --no-default-features to leave it out
+#[cfg(feature = "sdl")]
 mod sdl2_ttf;
 
 #[macro_use]
@@ -16,10 +20,20 @@ pub mod gb_util;
 pub mod gb_debug;
 pub mod gb_gameboy;
 pub mod gb_memory;
+pub mod gb_bus;
 pub mod gb_cpu;
 pub mod gb_lcd;
 pub mod gb_joypad;
-
+pub mod gb_apu;
+pub mod gb_timer;
+pub mod gb_serial;
+pub mod gb_mbc;
+pub mod gb_cartridge;
+pub mod gb_savestate;
+pub mod gb_rewind;
+pub mod gb_bess;
+
+#[cfg(feature = "sdl")]
 #[macro_use]
 extern crate bitflags;
 
@@ -11,7 +11,10 @@ extern crate gbEmu;
 
 
 use std::env;
-use std::str;
+use std::path::Path;
+use std::io;
+use std::io::{Read, Write};
+use std::fs::File;
 
 use libc::usleep;
 use libc::EINTR;
@@ -23,11 +26,17 @@ use gbEmu::gb_memory::*;
 use gbEmu::gb_cpu::*;
 use gbEmu::gb_lcd::*;
 use gbEmu::gb_joypad::*;
+use gbEmu::gb_apu::*;
 use gbEmu::gb_debug::*;
-use gbEmu::gb_memory::MemoryBankControllerType::*;
+use gbEmu::gb_mbc::*;
+use gbEmu::gb_cartridge::*;
+use gbEmu::gb_rewind::*;
+use gbEmu::gb_bess::*;
 
+use sdl2::audio::{AudioQueue, AudioSpecDesired};
 use sdl2::event::*;
-use sdl2::keyboard::Keycode;
+use sdl2::keyboard::{Keycode, LSHIFTMOD, RSHIFTMOD};
+use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 use sdl2::*;
 
@@ -42,13 +51,25 @@ const DEBUG_POS_Y: i32 = WINDOW_HEIGHT as i32;
 
 
 const SECONDS_PER_FRAME: f32 = 1f32/60f32;
-const CYCLES_PER_SLEEP: u32 = 60000;
+
+const AUDIO_DEVICE_SAMPLE_RATE: i32 = 48000;
+const AUDIO_DEVICE_BUFFER_SAMPLES: u16 = 1024;
+//how many frames worth of audio to keep queued before waiting for the audio device to catch up
+const AUDIO_FRAMES_TO_QUEUE: u32 = 3;
+
+//how often dirty cart RAM gets written to the save file. About 5 seconds
+const FRAMES_PER_SAVE_FLUSH: u32 = 300;
+
+//rewind keeps a snapshot every 2 frames for the last minute of play
+const REWIND_FRAMES_PER_SNAPSHOT: u32 = 2;
+const REWIND_SNAPSHOT_CAPACITY: usize = 30 * 60;
 
 
 struct ProgramState {
     shouldDisplayDebug: bool,
     isPaused: bool,
     isRunning: bool,
+    isRewinding: bool, //true while the rewind key is held
 
     shouldSkipBootScreen: bool,
     romFileName: String,
@@ -62,6 +83,7 @@ impl ProgramState {
             shouldDisplayDebug: false,
             isPaused: false,
             isRunning: true,
+            isRewinding: false,
 
             shouldSkipBootScreen: shouldSkipBootScreen,
             romFileName: romFileName,
@@ -123,58 +145,65 @@ fn main() {
     };
 
 
-    //load ROM data
-    gb.mem.romData = romData;
-    gb.mem.mbcType = match gb.mem.romData[0x147]  {
-        0 => MBC0,
-        1 => MBC1,
-        _ => panic!("MBC not yet supported: {}", gb.mem.romData[0x147])
-    };
-
-    gb.mem.cartRAM = match gb.mem.mbcType {
-       MBC0 => vec![0;0x2000],
-       MBC1 => vec![0;0x8000]
+    let header = match parseCartridgeHeader(&romData) {
+        Ok(header) => header,
+        Err(err) => panic!("{}", err)
     };
 
+    //the real boot ROM would lock up on a bad logo or header checksum, but most of these still run fine
+    if let Err(err) = validateCartridgeHeader(&header, &romData) {
+        println!("Warning: {}", err);
+    }
 
-
-    let mut titleBytes = [0u8;14]; 
-
-    //TODO use clone_from_slice in rust 1.6
-    for (i, byteRef) in gb.mem.romData[0x134..0x142].iter().enumerate() {
-        titleBytes[i] = *byteRef;
+    let gameName = if header.title.is_empty() {
+        "GB Emu".to_string()
     }
+    else {
+        header.title.clone()
+    };
 
-    let utfResult = str::from_utf8(&titleBytes[..]);
+    //load ROM data into a cartridge based on its MBC
+    gb.mem.cartridge = match cartridgeFromROM(romData) {
+        Ok(cartridge) => cartridge,
+        Err(err) => panic!("{}", err)
+    };
 
-    let gameName = match utfResult {
-        Ok(string) => string,
-        Err(_) => "GB Emu"
+    //battery backed carts keep their save next to the ROM. e.g. game.gb -> game.sav
+    let saveFileName = if header.hasBattery() {
+        Path::new(&prg.romFileName).with_extension("sav").to_str().map(|name| name.to_string())
+    }
+    else {
+        None
     };
 
+    if let Some(ref fileName) = saveFileName {
+        match loadSaveFile(&mut gb.mem, fileName) {
+            Ok(_) => println!("Loaded save file {}", fileName),
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => {},
+            Err(err) => println!("Could not load save file {}. Error: {}", fileName, err)
+        }
+    }
+
     
     
     //skip "Nintendo" logo if specified
     if prg.shouldSkipBootScreen {
-        gb.cpu.PC = 0x100;
-        gb.mem.inBios = false;
-        gb.mem.lcd.mode = LCDMode::VBlank;
+        gb.skipBootScreen();
     }
 
 
-    let mut cyclesPerDividerIncrement = 0u32;
-    let mut cyclesPerTimerIncrement = 0u32;
 
     //init SDL 
     let sdlContext = sdl2::init().unwrap();
 
     let videoSubsystem = sdlContext.video().unwrap();
     let timer = sdlContext.timer().unwrap();
+    let audioSubsystem = sdlContext.audio().unwrap();
     let mut eventPump = sdlContext.event_pump().unwrap();
 
     let mut mainWindowHeight = WINDOW_HEIGHT;
     let mainWindowWidth = WINDOW_WIDTH;
-    let mainWindow = videoSubsystem.window(gameName, mainWindowWidth, mainWindowHeight).position_centered().build().unwrap();
+    let mainWindow = videoSubsystem.window(&gameName, mainWindowWidth, mainWindowHeight).position_centered().build().unwrap();
     let mainWindowID = mainWindow.id();
     let mut renderer = mainWindow.renderer().build().unwrap();
 
@@ -182,6 +211,38 @@ fn main() {
     //init debug screen
     let mut dbg = initDebug(0, WINDOW_HEIGHT as i32, WINDOW_WIDTH, WINDOW_HEIGHT / 2);
 
+    //init audio.  If no audio device can be opened, emulation is paced by sleeping instead
+    let desiredAudioSpec = AudioSpecDesired {
+        freq: Some(AUDIO_DEVICE_SAMPLE_RATE),
+        channels: Some(2),
+        samples: Some(AUDIO_DEVICE_BUFFER_SAMPLES)
+    };
+
+    let audioQueue: Option<AudioQueue<i16>> = match audioSubsystem.open_queue(None, &desiredAudioSpec) {
+        Ok(queue) => {
+            queue.resume();
+            Some(queue)
+        },
+        Err(err) => {
+            println!("Could not open audio device. Sound is disabled. Reason: {}", err);
+            None
+        }
+    };
+
+    let deviceSampleRate = match audioQueue {
+        Some(ref queue) => queue.spec().freq as u32,
+        None => AUDIO_DEVICE_SAMPLE_RATE as u32
+    };
+
+    let mut resampler = AudioResampler::new(SAMPLE_RATE_HZ, deviceSampleRate);
+
+    //2 channels of 2 byte samples
+    let maxQueuedAudioBytes = (deviceSampleRate / 60) * AUDIO_FRAMES_TO_QUEUE * 2 * 2;
+
+    let mut framesSinceSaveFlush = 0u32;
+    let mut rewind = RewindBuffer::new(REWIND_SNAPSHOT_CAPACITY, REWIND_FRAMES_PER_SNAPSHOT);
+    let mut framesSinceRewindStep = 0u32;
+
     //main loop
     while prg.isRunning {
         //get the start time to calculate time
@@ -220,6 +281,8 @@ fn main() {
                                 Keycode::Z => gb.mem.joypad.b = ButtonState::Up,
                                 Keycode::Return => gb.mem.joypad.start = ButtonState::Up,
                                 Keycode::RShift => gb.mem.joypad.select = ButtonState::Up,
+
+                                Keycode::Backspace => prg.isRewinding = false,
                                 _ => {}
                             }
                         }
@@ -230,7 +293,7 @@ fn main() {
                 }
 
 
-                Event::KeyDown{keycode: keyOpt, repeat: isRepeat, ..} => {
+                Event::KeyDown{keycode: keyOpt, keymod: keyMod, repeat: isRepeat, ..} => {
                     match keyOpt {
                         Some(key) => {
                             match key {
@@ -254,6 +317,55 @@ fn main() {
                                     }
                                 },
 
+                                //F1-F4 save to a slot.  Shift+F1-F4 loads from it
+                                Keycode::F1 | Keycode::F2 | Keycode::F3 | Keycode::F4 => {
+                                    if !isRepeat {
+                                        let slot = match key {
+                                            Keycode::F1 => 1,
+                                            Keycode::F2 => 2,
+                                            Keycode::F3 => 3,
+                                            _ => 4
+                                        };
+
+                                        let fileName = saveStateFileName(&prg.romFileName, slot);
+
+                                        if keyMod.intersects(LSHIFTMOD | RSHIFTMOD) {
+                                            match loadStateFromFile(gb, &fileName) {
+                                                Ok(_) => println!("Loaded state from slot {}", slot),
+                                                Err(err) => println!("Could not load state from slot {}. Error: {}", slot, err)
+                                            }
+                                        }
+                                        else {
+                                            match saveStateToFile(gb, &fileName) {
+                                                Ok(_) => println!("Saved state to slot {}", slot),
+                                                Err(err) => println!("Could not save state to slot {}. Error: {}", slot, err)
+                                            }
+                                        }
+                                    }
+                                },
+
+                                //F5 exports a BESS state for other emulators.  Shift+F5 imports one
+                                Keycode::F5 => {
+                                    if !isRepeat {
+                                        let fileName = Path::new(&prg.romFileName).with_extension("bess").to_string_lossy().into_owned();
+
+                                        if keyMod.intersects(LSHIFTMOD | RSHIFTMOD) {
+                                            match importBESSFromFile(gb, &fileName) {
+                                                Ok(_) => println!("Imported BESS state from {}", fileName),
+                                                Err(err) => println!("Could not import BESS state from {}. Error: {}", fileName, err)
+                                            }
+                                        }
+                                        else {
+                                            match File::create(&fileName).and_then(|mut f| f.write_all(&exportBESS(gb)[..])) {
+                                                Ok(_) => println!("Exported BESS state to {}", fileName),
+                                                Err(err) => println!("Could not export BESS state to {}. Error: {}", fileName, err)
+                                            }
+                                        }
+                                    }
+                                },
+
+                                Keycode::Backspace => prg.isRewinding = true,
+
                                 Keycode::U => {
                                     if !isRepeat {
                                         match dumpGameBoyState(gb, "dump.txt") {
@@ -342,40 +454,42 @@ fn main() {
         let mut batchCycles = 0u32;
 
         //------------------------step emulator-------------------------------
-        if !prg.isPaused {
-            //run several thousand game boy cycles or so 
-            while batchCycles < CYCLES_PER_SLEEP {
+        if !prg.isPaused && prg.isRewinding {
+            //step back at the same rate snapshots were taken so rewinding plays back in real time
+            framesSinceRewindStep += 1;
 
-                    stepCPU(&mut gb.cpu, &mut gb.mem);
+            if framesSinceRewindStep >= REWIND_FRAMES_PER_SNAPSHOT {
+                framesSinceRewindStep = 0;
+                stepRewindBack(&mut rewind, gb);
+            }
 
-                    stepLCD(&mut gb.mem.lcd, &mut gb.mem.requestedInterrupts, gb.cpu.instructionCycles);
-                    batchCycles += gb.cpu.instructionCycles;
+            //no sound is made while rewinding, so pace the frame off of the clock instead
+            batchCycles = CYCLES_PER_FRAME;
+        }
+        else if !prg.isPaused {
+            let events = gb.run_frame();
+            batchCycles = events.cycles;
 
-                    cyclesPerDividerIncrement += gb.cpu.instructionCycles;
+            let samples = gb.takeAudioSamples();
 
-                    if cyclesPerDividerIncrement >= CYCLES_PER_DIVIDER_INCREMENT {
-                        gb.mem.divider = gb.mem.divider.wrapping_add(1);
-                        cyclesPerDividerIncrement -= CYCLES_PER_DIVIDER_INCREMENT;
-                    }
+            recordRewindFrame(&mut rewind, gb);
 
-                    //if timer is enabled...
-                    //TODO: Timer may need to be more accurate in the case
-                    //      when the timer is slowed down or sped up.  Cycles may need to be reset.
-                    if gb.mem.isTimerEnabled {
-                        cyclesPerTimerIncrement += gb.cpu.instructionCycles;
+            framesSinceSaveFlush += 1;
 
+            if framesSinceSaveFlush >= FRAMES_PER_SAVE_FLUSH {
+                framesSinceSaveFlush = 0;
 
-                        if cyclesPerTimerIncrement >= (gb.mem.timerMode as u32) {
-                            gb.mem.timerCounter = gb.mem.timerCounter.wrapping_add(1);
+                if gb.mem.isCartRAMDirty {
+                    flushSaveFile(&mut gb.mem, &saveFileName);
+                }
+            }
 
-                            if gb.mem.timerCounter == 0 {
-                                gb.mem.timerCounter = gb.mem.timerModulo;
-                                gb.mem.requestedInterrupts |= 1 << 2;
-                                cyclesPerTimerIncrement -= gb.mem.timerMode as u32; 
-                            }
-                        }
-                    }
-                } 
+            match audioQueue {
+                Some(ref queue) => {
+                    queue.queue(&resampleAudio(&mut resampler, &samples[..])[..]);
+                },
+                None => {}
+            }
         }
         //--------------------------------------------------------------------
 
@@ -383,7 +497,7 @@ fn main() {
 
         //--------------------draw GB screen-----------------------------------
         renderer.clear();
-        renderer.set_draw_color(WHITE);
+        renderer.set_draw_color(sdlColor(WHITE));
         renderer.fill_rect(Rect::new_unwrap(0,0, mainWindowWidth, mainWindowHeight));
 
         //draw clear screen if lcd is disabled
@@ -396,7 +510,7 @@ fn main() {
             for row in &gb.mem.lcd.screen[..] {
                 for color in &row[..] {
 
-                    renderer.set_draw_color(*color);
+                    renderer.set_draw_color(sdlColor(*color));
                     renderer.fill_rect(Rect::new_unwrap(x as i32 ,y as i32, GAMEBOY_SCALE, GAMEBOY_SCALE));
 
                     x = (x + GAMEBOY_SCALE) % (row.len() as u32 * GAMEBOY_SCALE);
@@ -420,12 +534,24 @@ fn main() {
 
         renderer.present();
 
-        let secsElapsed = secondsForCountRange(start, timer.performance_counter(), &timer);
-        let targetSecs =  batchCycles as f32 / CLOCK_SPEED_HZ; 
+        match audioQueue {
+            //NOTE: Pacing off of the audio device keeps playback continuous and keeps the
+            //      emulator from drifting away from the audio device's clock
+            Some(ref queue) if !prg.isPaused && !prg.isRewinding => {
+                while queue.size() > maxQueuedAudioBytes {
+                    timer.delay(1);
+                }
+            },
+
+            _ => {
+                let secsElapsed = secondsForCountRange(start, timer.performance_counter(), &timer);
+                let targetSecs =  batchCycles as f32 / CLOCK_SPEED_HZ; 
 
-        if secsElapsed < targetSecs {
-            let secsToSleep = targetSecs - secsElapsed;
-            sleep(secsToSleep, &timer).unwrap();
+                if secsElapsed < targetSecs {
+                    let secsToSleep = targetSecs - secsElapsed;
+                    sleep(secsToSleep, &timer).unwrap();
+                }
+            }
         }
 
         //TODO: clock speed and dbg.fps lag one frame behind
@@ -438,6 +564,7 @@ fn main() {
 
     }
 
+    flushSaveFile(&mut gb.mem, &saveFileName);
     debugQuit();
 
 }
@@ -473,6 +600,45 @@ fn disassemble(cpu: &CPUState, mem: &MemoryMapState) -> String {
 
 }
 
+//save states live next to the ROM. e.g. game.gb -> game.ss1
+fn saveStateFileName(romFileName: &str, slot: u32) -> String {
+    Path::new(romFileName).with_extension(format!("ss{}", slot)).to_string_lossy().into_owned()
+}
+
+fn saveStateToFile(gb: &GameBoyState, fileName: &str) -> Result<(), String> {
+    let mut f = try!(File::create(fileName).map_err(|err| err.to_string()));
+    f.write_all(&gb.save_state()[..]).map_err(|err| err.to_string())
+}
+
+fn loadStateFromFile(gb: &mut GameBoyState, fileName: &str) -> Result<(), String> {
+    let mut data: Vec<u8> = vec![];
+    let mut f = try!(File::open(fileName).map_err(|err| err.to_string()));
+    try!(f.read_to_end(&mut data).map_err(|err| err.to_string()));
+
+    gb.load_state(&data[..]).map_err(|err| err.to_string())
+}
+
+fn importBESSFromFile(gb: &mut GameBoyState, fileName: &str) -> Result<(), String> {
+    let mut data: Vec<u8> = vec![];
+    let mut f = try!(File::open(fileName).map_err(|err| err.to_string()));
+    try!(f.read_to_end(&mut data).map_err(|err| err.to_string()));
+
+    importBESS(gb, &data[..]).map_err(|err| err.to_string())
+}
+
+//writes cart RAM to the save file if the cart has a battery
+fn flushSaveFile(mem: &mut MemoryMapState, saveFileName: &Option<String>) {
+    if let Some(ref fileName) = *saveFileName {
+        if let Err(err) = writeSaveFile(mem, fileName) {
+            println!("Could not write save file {}. Error: {}", fileName, err);
+        }
+    }
+}
+
+fn sdlColor(color: PaletteColor) -> Color {
+    Color::RGBA(color.r, color.g, color.b, color.a)
+}
+
 //Returns number of seconds for a given performance count range
 fn secondsForCountRange(start: u64, end: u64, timer: &TimerSubsystem) -> f32 {
     ((end as f64 - start as f64) / timer.performance_frequency() as f64) as f32
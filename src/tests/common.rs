@@ -0,0 +1,18 @@
+//fixtures shared by the unit tests
+use gb_gameboy::*;
+use gb_mbc::*;
+
+//Game Boy with a blank 64KB MBC1 cart with 8KB of RAM that has the given title in its header.
+//Starts out of the BIOS
+pub fn gameBoyWithMBC1(title: &str) -> GameBoyState {
+    let mut romData = vec![0; 0x10000];
+    for (i, byte) in title.bytes().enumerate() {
+        romData[0x134 + i] = byte;
+    }
+
+    let mut gb = GameBoyState::new();
+    gb.mem.cartridge = Box::new(MBC1Cartridge::new(romData, 0x2000));
+    gb.mem.inBios = false;
+
+    gb
+}
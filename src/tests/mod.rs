@@ -1,3 +1,14 @@
+mod common;
+
 mod test_gb_cpu;
 mod test_gb_memory;
 mod test_gb_lcd;
+mod test_gb_apu;
+mod test_gb_mbc;
+mod test_gb_cartridge;
+mod test_gb_gameboy;
+mod test_gb_rewind;
+mod test_gb_bess;
+mod test_gb_timer;
+mod test_gb_serial;
+mod test_gb_bus;
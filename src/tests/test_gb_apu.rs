@@ -0,0 +1,113 @@
+use gb_memory::*;
+use gb_apu::*;
+
+fn poweredOnMemoryMapState() -> MemoryMapState {
+    let mut mem = MemoryMapState::new();
+    writeByteToMemory(&mut mem, 0x80, 0xFF26); //NR52: turn on sound
+
+    mem
+}
+
+#[test]
+fn testAPURegisterReadMasks() {
+    let mut mem = poweredOnMemoryMapState();
+
+    writeByteToMemory(&mut mem, 0x00, 0xFF10); 
+    assert_eq!(readByteFromMemory(&mem, 0xFF10), 0x80); //bit 7 is unused
+
+    writeByteToMemory(&mut mem, 0x85, 0xFF11); 
+    assert_eq!(readByteFromMemory(&mem, 0xFF11), 0xBF); //only duty is readable
+
+    writeByteToMemory(&mut mem, 0xAB, 0xFF13); 
+    assert_eq!(readByteFromMemory(&mem, 0xFF13), 0xFF); //frequency is write only
+
+    writeByteToMemory(&mut mem, 0xF3, 0xFF12);
+    assert_eq!(readByteFromMemory(&mem, 0xFF12), 0xF3);
+
+    writeByteToMemory(&mut mem, 0x77, 0xFF24);
+    assert_eq!(readByteFromMemory(&mem, 0xFF24), 0x77);
+
+    assert_eq!(readByteFromMemory(&mem, 0xFF15), 0xFF); //unused register
+    assert_eq!(readByteFromMemory(&mem, 0xFF26), 0xF0); //on with no channels playing
+}
+
+#[test]
+fn testAPUTrigger() {
+    let mut mem = poweredOnMemoryMapState();
+
+    writeByteToMemory(&mut mem, 0xF0, 0xFF17); //NR22: volume 15, DAC on
+    writeByteToMemory(&mut mem, 0x80, 0xFF19); //NR24: trigger
+
+    assert_eq!(mem.apu.square2.isEnabled, true);
+    assert_eq!(mem.apu.square2.envelope.volume, 15);
+    assert_eq!(readByteFromMemory(&mem, 0xFF26), 0xF2);
+
+    //turning off the DAC turns off the channel
+    writeByteToMemory(&mut mem, 0x00, 0xFF17); 
+    assert_eq!(mem.apu.square2.isEnabled, false);
+    assert_eq!(readByteFromMemory(&mem, 0xFF26), 0xF0);
+}
+
+#[test]
+fn testAPULengthCounter() {
+    let mut mem = poweredOnMemoryMapState();
+
+    writeByteToMemory(&mut mem, 0xF0, 0xFF21); //NR42: volume 15, DAC on
+    writeByteToMemory(&mut mem, 0x3E, 0xFF20); //NR41: length of 2
+    writeByteToMemory(&mut mem, 0xC0, 0xFF23); //NR44: trigger with length enabled
+
+    assert_eq!(mem.apu.noise.isEnabled, true);
+
+    //each falling edge of DIV bit 4 clocks the frame sequencer
+    //length is clocked on steps 0 and 2
+    for _ in 0..3 {
+        stepAPU(&mut mem.apu, 0x10, 4);
+        stepAPU(&mut mem.apu, 0x00, 4);
+    }
+
+    assert_eq!(mem.apu.noise.isEnabled, false);
+}
+
+#[test]
+fn testAPUPowerOff() {
+    let mut mem = poweredOnMemoryMapState();
+
+    writeByteToMemory(&mut mem, 0xAB, 0xFF30); //wave RAM
+    writeByteToMemory(&mut mem, 0xFF, 0xFF25); //NR51
+    writeByteToMemory(&mut mem, 0x00, 0xFF26); //turn off sound
+
+    assert_eq!(readByteFromMemory(&mem, 0xFF25), 0);
+    assert_eq!(readByteFromMemory(&mem, 0xFF30), 0xAB); //wave RAM is kept
+
+    //registers can't be written while off
+    writeByteToMemory(&mut mem, 0xFF, 0xFF25); 
+    assert_eq!(readByteFromMemory(&mem, 0xFF25), 0);
+    assert_eq!(readByteFromMemory(&mem, 0xFF26), 0x70);
+}
+
+#[test]
+fn testAPUSampleBuffer() {
+    let mut mem = poweredOnMemoryMapState();
+
+    stepAPU(&mut mem.apu, 0, CYCLES_PER_SAMPLE * 10);
+
+    //one left and one right sample every CYCLES_PER_SAMPLE
+    assert_eq!(mem.apu.sampleBuffer.len(), 20);
+}
+
+#[test]
+fn testResampleAudio() {
+    let mut resampler = AudioResampler::new(2, 1);
+
+    let resampled = resampleAudio(&mut resampler, &[10, -10, 20, -20, 30, -30, 40, -40]);
+    assert_eq!(resampled, vec![0, 0, 20, -20]);
+
+    //position carries over into the next call
+    let mut resampler = AudioResampler::new(1, 2);
+
+    let resampled = resampleAudio(&mut resampler, &[10, -10]);
+    assert_eq!(resampled, vec![0, 0, 5, -5]);
+
+    let resampled = resampleAudio(&mut resampler, &[20, -20]);
+    assert_eq!(resampled, vec![10, -10, 15, -15]);
+}
@@ -0,0 +1,141 @@
+use gb_bess::*;
+use gb_memory::*;
+use gb_util::*;
+use tests::common::*;
+
+#[test]
+fn testExportBESS() {
+    let gb = gameBoyWithMBC1("TEST");
+    let data = exportBESS(&gb);
+
+    assert_eq!(&data[data.len() - 4..], b"BESS");
+
+    //walk the blocks
+    let mut offset = readU32(&data[..], data.len() - 8) as usize;
+    let mut ids = vec![];
+
+    loop {
+        let id = String::from_utf8_lossy(&data[offset..offset + 4]).into_owned();
+        let size = readU32(&data[..], offset + 4) as usize;
+
+        if id == "CORE" {
+            assert_eq!(size, 0xD0);
+        }
+
+        offset += 8 + size;
+        ids.push(id.clone());
+
+        if id == "END " {
+            break;
+        }
+    }
+
+    assert_eq!(ids, vec!["NAME", "INFO", "CORE", "MBC ", "END "]);
+    assert_eq!(offset, data.len() - 8);
+}
+
+#[test]
+fn testImportBESS() {
+    let mut gb = gameBoyWithMBC1("TEST");
+
+    gb.cpu.PC = 0x150;
+    gb.cpu.A = 0x12;
+    gb.cpu.F = 0xB0;
+    gb.cpu.SP = 0xFFFE;
+    gb.cpu.isHalted = true;
+    gb.mem.enabledInterrupts = 0x5;
+    gb.mem.timer.internalCounter = 0x3400;
+    writeByteToMemory(&mut gb.mem, 0xAB, 0xC123); 
+    writeByteToMemory(&mut gb.mem, 0xCD, 0xFF90); 
+    writeByteToMemory(&mut gb.mem, 0xE4, 0xFF47); 
+    writeByteToMemory(&mut gb.mem, 0x91, 0xFF40); 
+    writeByteToMemory(&mut gb.mem, 0xA, 0x0000); //enable cart RAM
+    writeByteToMemory(&mut gb.mem, 0x3, 0x2000); //ROM bank 3
+    writeByteToMemory(&mut gb.mem, 0xEF, 0xA010); 
+    gb.mem.lcd.videoRAM[0x10] = 0x55;
+    gb.mem.lcd.currScanLine = 0x42;
+
+    let data = exportBESS(&gb);
+
+    let mut loadedGB = gameBoyWithMBC1("TEST");
+    importBESS(&mut loadedGB, &data[..]).unwrap();
+
+    assert_eq!(loadedGB.cpu.PC, 0x150);
+    assert_eq!(loadedGB.cpu.A, 0x12);
+    assert_eq!(loadedGB.cpu.F, 0xB0);
+    assert_eq!(loadedGB.cpu.SP, 0xFFFE);
+    assert!(loadedGB.cpu.isHalted);
+    assert_eq!(loadedGB.mem.enabledInterrupts, 0x5);
+    assert_eq!(readByteFromMemory(&loadedGB.mem, 0xFF04), 0x34);
+    assert_eq!(readByteFromMemory(&loadedGB.mem, 0xC123), 0xAB);
+    assert_eq!(readByteFromMemory(&loadedGB.mem, 0xFF90), 0xCD);
+    assert_eq!(readByteFromMemory(&loadedGB.mem, 0xFF47), 0xE4);
+    assert_eq!(readByteFromMemory(&loadedGB.mem, 0xFF40), 0x91);
+    assert_eq!(readByteFromMemory(&loadedGB.mem, 0xA010), 0xEF);
+    assert_eq!(loadedGB.mem.cartridge.control_register_writes(), gb.mem.cartridge.control_register_writes());
+    assert_eq!(loadedGB.mem.lcd.videoRAM[0x10], 0x55);
+    assert_eq!(loadedGB.mem.lcd.currScanLine, 0x42);
+}
+
+#[test]
+fn testImportBESSKeepsTIMA() {
+    let mut gb = gameBoyWithMBC1("TEST");
+
+    gb.mem.timer.internalCounter = 0x0200; //bit 9 clocks TIMA with clock select 0
+    writeByteToMemory(&mut gb.mem, 0x10, 0xFF05); //TIMA
+    writeByteToMemory(&mut gb.mem, 0x00, 0xFF07); //timer off
+
+    let data = exportBESS(&gb);
+
+    //turning the timer off with the selected bit set would be a falling edge
+    let mut loadedGB = gameBoyWithMBC1("TEST");
+    writeByteToMemory(&mut loadedGB.mem, 0x04, 0xFF07);
+    importBESS(&mut loadedGB, &data[..]).unwrap();
+
+    assert_eq!(readByteFromMemory(&loadedGB.mem, 0xFF05), 0x10);
+    assert_eq!(readByteFromMemory(&loadedGB.mem, 0xFF07), 0xF8);
+}
+
+#[test]
+fn testBadBESS() {
+    let mut gb = gameBoyWithMBC1("TEST");
+    gb.cpu.PC = 0x150;
+
+    assert_eq!(importBESS(&mut gb, &[0; 16]), Err(BESSError::NoFooter));
+
+    let data = exportBESS(&gameBoyWithMBC1("OTHER GAME"));
+    assert_eq!(importBESS(&mut gb, &data[..]), Err(BESSError::ROMMismatch));
+    assert_eq!(gb.cpu.PC, 0x150);
+}
+
+//finds where the entries of the MBC block start
+fn mbcBlockOffset(data: &[u8]) -> usize {
+    let mut offset = readU32(data, data.len() - 8) as usize;
+
+    while &data[offset..offset + 4] != b"MBC " {
+        offset += 8 + readU32(data, offset + 4) as usize;
+    }
+
+    offset + 8
+}
+
+#[test]
+fn testBESSMBCWrites() {
+    let mut gb = gameBoyWithMBC1("TEST");
+    writeByteToMemory(&mut gb.mem, 0xA, 0x0000); //enable cart RAM
+    let mut data = exportBESS(&gb);
+
+    //the last MBC1 register write becomes a write to cart RAM
+    let lastWrite = mbcBlockOffset(&data[..]) + 3 * (gb.mem.cartridge.control_register_writes().len() - 1);
+    data[lastWrite] = 0x05;
+    data[lastWrite + 1] = 0xA0;
+    data[lastWrite + 2] = 0x77;
+
+    let mut loadedGB = gameBoyWithMBC1("TEST");
+    importBESS(&mut loadedGB, &data[..]).unwrap();
+    assert_eq!(readByteFromMemory(&loadedGB.mem, 0xA005), 0x77);
+
+    //work RAM isn't part of the MBC
+    data[lastWrite + 1] = 0xC0;
+    assert_eq!(importBESS(&mut loadedGB, &data[..]), Err(BESSError::BadMBCRegister(0xC005)));
+}
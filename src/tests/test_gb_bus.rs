@@ -0,0 +1,171 @@
+use gb_bus::*;
+use gb_cpu::*;
+use gb_memory::*;
+use gb_timer::*;
+
+#[test]
+fn testRecordingBus() {
+    let mut cpu = CPUState::new();
+    let mut mem = RecordingBus::new(FlatBus::new());
+
+    cpu.PC = 0xC000;
+    cpu.SP = 0xFFFE;
+    cpu.B = 0x12;
+    cpu.C = 0x34;
+    mem.bus.memory[0xC000] = 0xC5; //PUSH BC
+    mem.bus.memory[0xC001] = 0x00; //NOP
+
+    stepCPU(&mut cpu, &mut mem);
+    stepCPU(&mut cpu, &mut mem);
+
+    //every access takes a machine cycle, and PUSH waits one machine cycle before writing
+    assert_eq!(mem.cycle, 16 + 4);
+    assert_eq!(mem.accesses, vec![
+        BusAccess { cycle: 0, kind: BusAccessKind::Read, addr: 0xC000, value: 0xC5 },
+        BusAccess { cycle: 8, kind: BusAccessKind::Write, addr: 0xFFFD, value: 0x12 },
+        BusAccess { cycle: 12, kind: BusAccessKind::Write, addr: 0xFFFC, value: 0x34 },
+        BusAccess { cycle: 16, kind: BusAccessKind::Read, addr: 0xC001, value: 0x00 }
+    ]);
+}
+
+#[test]
+fn testInterruptDispatchThroughBus() {
+    let mut cpu = CPUState::new();
+    let mut mem = FlatBus::new();
+
+    cpu.PC = 0xC000;
+    cpu.SP = 0xFFFE;
+    cpu.enableInterrupts = true;
+    mem.memory[0xFFFF] = 0x05; //IE: VBlank and timer
+    mem.memory[0xFF0F] = 0x04; //IF: timer
+
+    stepCPU(&mut cpu, &mut mem);
+
+    //the timer ISR at 0x50 is all NOPs, so the first one ran
+    assert_eq!(cpu.PC, 0x51);
+    assert_eq!(mem.memory[0xFF0F], 0);
+    assert!(!cpu.enableInterrupts);
+    assert_eq!(mem.memory[0xFFFC], 0x00); //return address
+    assert_eq!(mem.memory[0xFFFD], 0xC0);
+}
+
+#[test]
+fn testTotalCyclesCountsHaltAndDispatch() {
+    let mut cpu = CPUState::new();
+    let mut mem = RecordingBus::new(FlatBus::new());
+
+    cpu.PC = 0xC000;
+    cpu.SP = 0xFFFE;
+    cpu.enableInterrupts = true;
+    mem.bus.memory[0xC000] = 0x76; //HALT
+    mem.bus.memory[0xFFFF] = 0x04; //IE: timer
+
+    stepCPU(&mut cpu, &mut mem); //HALT
+    stepCPU(&mut cpu, &mut mem); //idle while halted
+    assert!(cpu.isHalted);
+
+    mem.bus.memory[0xFF0F] = 0x04; //IF: timer
+    stepCPU(&mut cpu, &mut mem); //dispatch and the NOP at the ISR
+
+    assert_eq!(cpu.PC, 0x51);
+    assert_eq!(cpu.totalCycles, 4 + 4 + 24);
+    assert_eq!(cpu.totalCycles, mem.cycle);
+}
+
+#[test]
+fn testCallTiming() {
+    let mut cpu = CPUState::new();
+    let mut mem = RecordingBus::new(FlatBus::new());
+
+    cpu.PC = 0xC000;
+    cpu.SP = 0xFFFE;
+    mem.bus.memory[0xC000] = 0xCD; //CALL 0xD000
+    mem.bus.memory[0xC001] = 0x00;
+    mem.bus.memory[0xC002] = 0xD0;
+
+    stepCPU(&mut cpu, &mut mem);
+
+    assert_eq!(cpu.PC, 0xD000);
+    assert_eq!(mem.cycle, 24);
+
+    //the internal machine cycle comes between reading the address and pushing PC
+    let cycles: Vec<u64> = mem.accesses.iter().map(|access| access.cycle).collect();
+    assert_eq!(cycles, vec![0, 4, 8, 16, 20]);
+}
+
+#[test]
+fn testLDHFromCTiming() {
+    let mut cpu = CPUState::new();
+    let mut mem = RecordingBus::new(FlatBus::new());
+
+    cpu.PC = 0xC000;
+    cpu.C = 0x80;
+    mem.bus.memory[0xC000] = 0xF2; //LDH A, (C)
+    mem.bus.memory[0xFF80] = 0x42;
+
+    stepCPU(&mut cpu, &mut mem);
+
+    assert_eq!(cpu.A, 0x42);
+    assert_eq!(cpu.PC, 0xC001);
+    assert_eq!(mem.cycle, 8);
+    assert_eq!(mem.accesses, vec![
+        BusAccess { cycle: 0, kind: BusAccessKind::Read, addr: 0xC000, value: 0xF2 },
+        BusAccess { cycle: 4, kind: BusAccessKind::Read, addr: 0xFF80, value: 0x42 }
+    ]);
+}
+
+#[test]
+fn testSTOPReadsOperandOnce() {
+    let mut cpu = CPUState::new();
+    let mut mem = RecordingBus::new(FlatBus::new());
+
+    cpu.PC = 0xC000;
+    mem.bus.memory[0xC000] = 0x10; //STOP 0
+
+    stepCPU(&mut cpu, &mut mem);
+
+    assert_eq!(cpu.PC, 0xC002);
+    assert_eq!(cpu.instructionCycles, 8);
+    assert_eq!(mem.cycle, 8);
+    assert_eq!(mem.accesses, vec![
+        BusAccess { cycle: 0, kind: BusAccessKind::Read, addr: 0xC000, value: 0x10 },
+        BusAccess { cycle: 4, kind: BusAccessKind::Read, addr: 0xC001, value: 0 }
+    ]);
+}
+
+#[test]
+fn testBITAtHLOnlyReads() {
+    let mut cpu = CPUState::new();
+    let mut mem = RecordingBus::new(FlatBus::new());
+
+    cpu.PC = 0xC000;
+    cpu.H = 0xFF;
+    cpu.L = 0x04;
+    mem.bus.memory[0xC000] = 0xCB; //BIT 7, (HL)
+    mem.bus.memory[0xC001] = 0x7E;
+
+    stepCPU(&mut cpu, &mut mem);
+
+    assert_eq!(cpu.PC, 0xC002);
+    assert_eq!(mem.cycle, 12);
+    assert!(mem.accesses.iter().all(|access| access.kind == BusAccessKind::Read));
+}
+
+#[test]
+fn testReadMidInstruction() {
+    let mut cpu = CPUState::new();
+    let mut mem = MemoryMapState::new();
+
+    cpu.PC = 0xC000;
+    writeByteToMemory(&mut mem, 0xF0, 0xC000); //LDH A,(0x04)
+    writeByteToMemory(&mut mem, 0x04, 0xC001);
+
+    //DIV ticks over 8 cycles in, which is when DIV gets read
+    mem.timer.internalCounter = 0xF8;
+
+    stepCPU(&mut cpu, &mut mem);
+
+    assert_eq!(cpu.A, 1);
+    assert_eq!(cpu.instructionCycles, 12);
+    assert_eq!(dividerRegister(&mem.timer), 1);
+}
@@ -0,0 +1,116 @@
+use gb_cartridge::*;
+
+//makes a 32KB ROM with a valid header
+fn romWithHeader() -> Vec<u8> {
+    let mut romData = vec![0u8; 0x8000];
+
+    for (i, byte) in NINTENDO_LOGO.iter().enumerate() {
+        romData[0x104 + i] = *byte;
+    }
+
+    for (i, byte) in "TESTGAME".bytes().enumerate() {
+        romData[0x134 + i] = byte;
+    }
+
+    romData[0x147] = 0x3; //MBC1+RAM+BATTERY
+    romData[0x148] = 0x1; //64KB
+    romData[0x149] = 0x3; //32KB
+    romData[0x14B] = 0x1; //Nintendo
+    romData[0x14C] = 0x2;
+
+    fixChecksums(&mut romData);
+    romData
+}
+
+fn fixChecksums(romData: &mut Vec<u8>) {
+    romData[0x14D] = calculateHeaderChecksum(romData);
+
+    let globalChecksum = calculateGlobalChecksum(romData);
+    romData[0x14E] = (globalChecksum >> 8) as u8;
+    romData[0x14F] = globalChecksum as u8;
+}
+
+#[test]
+fn testParseCartridgeHeader() {
+    let romData = romWithHeader();
+    let header = parseCartridgeHeader(&romData).unwrap();
+
+    assert_eq!(header.title, "TESTGAME");
+    assert_eq!(header.manufacturerCode, "");
+    assert_eq!(header.cgbSupport, CGBSupport::DMGOnly);
+    assert_eq!(header.isSGBSupported, false);
+    assert_eq!(header.licenseeCode, LicenseeCode::Old(0x1));
+    assert_eq!(header.cartridgeType, 0x3);
+    assert_eq!(header.romSize, 0x10000);
+    assert_eq!(header.ramSize, 0x8000);
+    assert_eq!(header.version, 0x2);
+    assert_eq!(validateCartridgeHeader(&header, &romData), Ok(()));
+}
+
+#[test]
+fn testParseNewCartridgeHeader() {
+    let mut romData = romWithHeader();
+
+    for (i, byte) in "ABCDEFGHIJKWXYZ".bytes().enumerate() {
+        romData[0x134 + i] = byte;
+    }
+    romData[0x143] = 0xC0; //CGB only
+    romData[0x144] = b'0';
+    romData[0x145] = b'1';
+    romData[0x146] = 0x3; //SGB
+    romData[0x14B] = 0x33; //use new licensee code
+
+    let header = parseCartridgeHeader(&romData).unwrap();
+
+    assert_eq!(header.title, "ABCDEFGHIJK");
+    assert_eq!(header.manufacturerCode, "WXYZ");
+    assert_eq!(header.cgbSupport, CGBSupport::CGBOnly);
+    assert_eq!(header.isSGBSupported, true);
+    assert_eq!(header.licenseeCode, LicenseeCode::New("01".to_string()));
+}
+
+#[test]
+fn testCartridgeHeaderErrors() {
+    assert_eq!(parseCartridgeHeader(&[0; 0x100]).unwrap_err(), CartridgeHeaderError::ROMTooSmall(0x100));
+
+    let mut romData = romWithHeader();
+    romData[0x148] = 0x20;
+    assert_eq!(parseCartridgeHeader(&romData).unwrap_err(), CartridgeHeaderError::InvalidROMSize(0x20));
+
+    let mut romData = romWithHeader();
+    romData[0x149] = 0x6;
+    assert_eq!(parseCartridgeHeader(&romData).unwrap().ramSize, 0); //unknown RAM sizes still load
+
+    let mut romData = romWithHeader();
+    romData[0x104] = 0;
+    let header = parseCartridgeHeader(&romData).unwrap();
+    assert_eq!(validateCartridgeHeader(&header, &romData), Err(CartridgeHeaderError::InvalidLogo));
+
+    let mut romData = romWithHeader();
+    romData[0x14D] = romData[0x14D].wrapping_add(1);
+    let header = parseCartridgeHeader(&romData).unwrap();
+    match validateCartridgeHeader(&header, &romData) {
+        Err(CartridgeHeaderError::HeaderChecksumMismatch{..}) => {},
+        result => panic!("Expected header checksum mismatch. Got: {:?}", result)
+    }
+
+    let mut romData = romWithHeader();
+    romData[0x200] = 0xFF;
+    let header = parseCartridgeHeader(&romData).unwrap();
+    match validateCartridgeHeader(&header, &romData) {
+        Err(CartridgeHeaderError::GlobalChecksumMismatch{..}) => {},
+        result => panic!("Expected global checksum mismatch. Got: {:?}", result)
+    }
+}
+
+#[test]
+fn testCartridgeHasBattery() {
+    let mut romData = romWithHeader();
+    assert!(parseCartridgeHeader(&romData).unwrap().hasBattery()); //MBC1+RAM+BATTERY
+
+    romData[0x147] = 0x2; //MBC1+RAM
+    assert!(!parseCartridgeHeader(&romData).unwrap().hasBattery());
+
+    romData[0x147] = 0x10; //MBC3+TIMER+RAM+BATTERY
+    assert!(parseCartridgeHeader(&romData).unwrap().hasBattery());
+}
@@ -11,102 +11,47 @@
 //started to use assert_eq instead of assert since assert_eq will print out both sides of the
 //equation.
 use gb_cpu::*;
-use gb_memory::MemoryMapState;
+use gb_bus::*;
+use gb_memory::BIOS;
 use gb_util::*;
 use gb_cpu::Flag::*;
 
-use std::fs;
-use std::io;
-use std::io::Read;
-static MBC0_ROM : &'static str = "samples/mbc0.gb";
-
-static BIOS: [u8; 0x100] = [
-    0x31, 0xFE, 0xFF, 0xAF, 0x21, 0xFF, 0x9F, 0x32, 0xCB, 0x7C, 0x20, 0xFB, 0x21, 0x26, 0xFF, 0x0E,
-    0x11, 0x3E, 0x80, 0x32, 0xE2, 0x0C, 0x3E, 0xF3, 0xE2, 0x32, 0x3E, 0x77, 0x77, 0x3E, 0xFC, 0xE0,
-    0x47, 0x11, 0x04, 0x01, 0x21, 0x10, 0x80, 0x1A, 0xCD, 0x95, 0x00, 0xCD, 0x96, 0x00, 0x13, 0x7B,
-    0xFE, 0x34, 0x20, 0xF3, 0x11, 0xD8, 0x00, 0x06, 0x08, 0x1A, 0x13, 0x22, 0x23, 0x05, 0x20, 0xF9,
-    0x3E, 0x19, 0xEA, 0x10, 0x99, 0x21, 0x2F, 0x99, 0x0E, 0x0C, 0x3D, 0x28, 0x08, 0x32, 0x0D, 0x20,
-    0xF9, 0x2E, 0x0F, 0x18, 0xF3, 0x67, 0x3E, 0x64, 0x57, 0xE0, 0x42, 0x3E, 0x91, 0xE0, 0x40, 0x04,
-    0x1E, 0x02, 0x0E, 0x0C, 0xF0, 0x44, 0xFE, 0x90, 0x20, 0xFA, 0x0D, 0x20, 0xF7, 0x1D, 0x20, 0xF2,
-    0x0E, 0x13, 0x24, 0x7C, 0x1E, 0x83, 0xFE, 0x62, 0x28, 0x06, 0x1E, 0xC1, 0xFE, 0x64, 0x20, 0x06,
-    0x7B, 0xE2, 0x0C, 0x3E, 0x87, 0xF2, 0xF0, 0x42, 0x90, 0xE0, 0x42, 0x15, 0x20, 0xD2, 0x05, 0x20,
-    0x4F, 0x16, 0x20, 0x18, 0xCB, 0x4F, 0x06, 0x04, 0xC5, 0xCB, 0x11, 0x17, 0xC1, 0xCB, 0x11, 0x17,
-    0x05, 0x20, 0xF5, 0x22, 0x23, 0x22, 0x23, 0xC9, 0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B,
-    0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D, 0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E,
-    0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99, 0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC,
-    0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E, 0x3c, 0x42, 0xB9, 0xA5, 0xB9, 0xA5, 0x42, 0x4C,
-    0x21, 0x04, 0x01, 0x11, 0xA8, 0x00, 0x1A, 0x13, 0xBE, 0x20, 0xFE, 0x23, 0x7D, 0xFE, 0x34, 0x20,
-    0xF5, 0x06, 0x19, 0x78, 0x86, 0x23, 0x05, 0x20, 0xFB, 0x86, 0x20, 0xFE, 0x3E, 0x01, 0xE0, 0x50
-];
 
 /*
- * I included stripped down versions of read and write word/byte functions since the function
- * signatures will change and I am not testing these functions in this test module.  I am only
- * really testing CPU related functions
+ * The CPU runs on a flat 64KB RAM here, so none of the memory map gets in the way of testing
+ * CPU related functions.  The BIOS sits at 0x0 and everything else starts out as 0
  */
-fn readByteFromMemory(memory: &MemoryMapState, addr: u16) -> u8 {
-
-
-    let i = addr as usize;
-    match addr {
-        0...0xFF if memory.inBios => BIOS[i],  
-        0...0xFF if !memory.inBios => memory.romData[i], 
-        0x100...0x3FFF => memory.romData[i],
-        0x4000...0x7FFF => memory.romData[i], //TODO: Implement bank swapping
-        0xC000...0xDFFF => memory.workingRAM[i - 0xC000],
-        0xE000...0xFDFF => memory.workingRAM[i - 0xE000], //echo of internal RAM 
-        0xFF80...0xFFFE => memory.zeroPageRAM[i - 0xFF80],
-        _ => 0
-    }
-}
-
+fn testingBus() -> FlatBus {
+    let mut mem = FlatBus::new();
 
-fn writeByteToMemory(memory: &mut MemoryMapState, byte: u8, addr: u16) {
-
-    let i = addr as usize;
-    match addr {
-        //vram can only be properly accessed when not being drawn from
-        0xC000...0xDFFF => memory.workingRAM[i - 0xC000] = byte,
-        0xE000...0xFDFF => memory.workingRAM[i - 0xE000] = byte,
-        0xFF80...0xFFFE => memory.zeroPageRAM[i - 0xFF80] = byte,     
-        _ => {}
+    for (i, byte) in BIOS.iter().enumerate() {
+        mem.memory[i] = *byte;
     }
-}
 
-fn readWordFromMemory(memory: &MemoryMapState, addr: u16) -> u16 {
-    debug_assert!(addr + 1 > addr); //check for overflow
-
-    ((readByteFromMemory(memory, addr+1) as u16) << 8)  | 
-        readByteFromMemory(memory, addr) as u16  
+    mem
 }
 
-fn writeWordToMemory(memory: &mut MemoryMapState, word: u16, addr: u16 ) {
-    debug_assert!(addr + 1 > addr); //check for overflow
-
-    writeByteToMemory(memory, lb(word), addr);
-    writeByteToMemory(memory, hb(word), addr+1);
+//named apart from the gb_memory functions since these skip the memory map entirely
+fn readFlatBusByte(memory: &FlatBus, addr: u16) -> u8 {
+    memory.memory[addr as usize]
 }
-fn openROM(fileName: &str) -> io::Result<Vec<u8>> {
-
-    let mut data: Vec<u8> = vec![];
-    let mut f = try!(fs::File::open(fileName)); 
-    try!(f.read_to_end(&mut data));
 
-    Ok(data)
+fn writeFlatBusByte(memory: &mut FlatBus, byte: u8, addr: u16) {
+    memory.memory[addr as usize] = byte;
 }
 
+fn readFlatBusWord(memory: &FlatBus, addr: u16) -> u16 {
+    debug_assert!(addr + 1 > addr); //check for overflow
 
+    ((readFlatBusByte(memory, addr+1) as u16) << 8)  | 
+        readFlatBusByte(memory, addr) as u16  
+}
 
-fn tetrisMemoryMapState() -> MemoryMapState{
-    let mut mem = MemoryMapState::new();
-
-    let romData = match openROM(MBC0_ROM) {
-        Ok(data) => data,
-        Err(err) => panic!("{}", err)
-    };
-    mem.romData = romData;
+fn writeFlatBusWord(memory: &mut FlatBus, word: u16, addr: u16 ) {
+    debug_assert!(addr + 1 > addr); //check for overflow
 
-    mem
+    writeFlatBusByte(memory, lb(word), addr);
+    writeFlatBusByte(memory, hb(word), addr+1);
 }
 
 //returns CPU with PC at 0xC000 which is the first address of RAM.  All addresses before 0xC000 are
@@ -153,14 +98,14 @@ macro_rules! test {
 }
 
 //NOTE(DanB): best for instructions that don't affect flags or require setup in memory
-fn executeInstructionOnClearedState(instruction: u8) -> (CPUState, MemoryMapState) {
+fn executeInstructionOnClearedState(instruction: u8) -> (CPUState, FlatBus) {
     let mut cpu = CPUState::new();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     let(newPC, cyclesTaken) = executeInstruction(instruction, &mut cpu, &mut mem);
     cpu.PC = newPC;
     cpu.instructionCycles = cyclesTaken;
-    cpu.totalCycles += cyclesTaken;
+    cpu.totalCycles += cyclesTaken as u64;
 
     (cpu,mem)
 }
@@ -183,10 +128,10 @@ fn loadImm16() { //0x1
         ($high: ident, $low: ident, $inst: expr) => ({
 
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
-            mem.workingRAM[1] = 0xBB; //write AABB to memory location 1
-            mem.workingRAM[2] = 0xAA;
+            mem.memory[0xC001] = 0xBB; //write AABB to memory location 1
+            mem.memory[0xC002] = 0xAA;
 
             let (newPC, cyclesTaken) = executeInstruction($inst, &mut cpu, &mut mem);
 
@@ -223,7 +168,7 @@ fn loadRegIntoMemory() { //0x2, 0x12
         ($destReg: ident, $addrHigh: ident, $addrLow: ident, $inst: expr) => ({
 
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
             cpu.$destReg = 0xCC;
             cpu.$addrHigh = 0xC0;
@@ -234,13 +179,13 @@ fn loadRegIntoMemory() { //0x2, 0x12
             assert!(newPC == cpu.PC + 1);
             assert!(cyclesTaken == 8);
 
-            assert!(mem.workingRAM[0] == 0xCC);
+            assert!(mem.memory[0xC000] == 0xCC);
         });
 
         ($destReg: ident, $addrHigh: ident, $addrLow: ident, $opOnHL: expr, $inst: expr) => ({
 
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
             cpu.$destReg = 0xCC;
             cpu.$addrHigh = 0xC0;
@@ -257,7 +202,7 @@ fn loadRegIntoMemory() { //0x2, 0x12
             assert!(newPC == cpu.PC + 1);
             assert!(cyclesTaken == 8);
 
-            assert!(mem.workingRAM[0] == 0xCC);
+            assert!(mem.memory[0xC000] == 0xCC);
         })
     }
 
@@ -278,7 +223,7 @@ fn increment16() { //0x3, 0x13
     macro_rules! testIncrement16 {
         ($highReg: ident, $lowReg: ident, $inst: expr) => ({
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
             cpu.$highReg = 0x0C;
             cpu.$lowReg = 0xFF;
@@ -304,7 +249,7 @@ fn increment16() { //0x3, 0x13
 fn increment8() { //0x4, 0xC, 0x14, 0x1C
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     macro_rules! testInc8 {
         ($reg: ident, $instr: expr) => ({
@@ -360,7 +305,7 @@ fn increment8() { //0x4, 0xC, 0x14, 0x1C
 fn decrement8() { //0x5, 0xD, 0x15
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
     macro_rules! testDec8 {
 
         ($reg: ident, $instr: expr) => ({
@@ -414,10 +359,10 @@ fn load8() {//0x6, 0xE, 0x16, 0x1E
     macro_rules! testLoad8 {
         ($reg: ident, $instr: expr) => ({
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
             let oldPC = cpu.PC;
 
-            writeWordToMemory(&mut mem, 0xAA, cpu.PC+1); //load value
+            writeFlatBusWord(&mut mem, 0xAA, cpu.PC+1); //load value
 
             let (newPC, cyclesTaken) = executeInstruction($instr, &mut cpu, &mut mem);
 
@@ -440,7 +385,7 @@ fn load8() {//0x6, 0xE, 0x16, 0x1E
 #[test]
 fn rlca() { //0x7
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     //test rotate 0
     let (newPC, cyclesTaken) = executeInstruction(0x7, &mut cpu, &mut mem);
@@ -448,7 +393,7 @@ fn rlca() { //0x7
     assert!(cpu.A == 0);
 
     assert!(!isFlagSet(Half, cpu.F));
-    assert!(isFlagSet(Zero, cpu.F));
+    assert!(!isFlagSet(Zero, cpu.F)); //rotating A always clears Z, even when A ends up 0
     assert!(!isFlagSet(Neg, cpu.F));
     assert!(!isFlagSet(Carry, cpu.F));
 
@@ -492,17 +437,17 @@ fn rlca() { //0x7
 #[test]
 fn loadSPIntoMemory() {//0x8
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.SP = 0xAAAA;
-    writeWordToMemory(&mut mem, 0xDDDD, cpu.PC + 1);
+    writeFlatBusWord(&mut mem, 0xDDDD, cpu.PC + 1);
 
     //test rotate 0
     let (newPC, cyclesTaken) = executeInstruction(0x8, &mut cpu, &mut mem);
 
     assert!(cyclesTaken == 20);
     assert!(newPC == cpu.PC + 3);
-    assert!(readWordFromMemory(&mut mem, 0xDDDD) == 0xAAAA);
+    assert!(readFlatBusWord(&mut mem, 0xDDDD) == 0xAAAA);
 
 }
 
@@ -511,7 +456,7 @@ fn addToHL() { //0x9, 0x19
     macro_rules! testAddToHL {
         ($highReg: ident, $lowReg: ident, $inst: expr) => ({
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
             //HL has 0x55AA
             cpu.H = 0x55;
@@ -563,7 +508,7 @@ fn addToHL() { //0x9, 0x19
 #[test]
 fn addHLToHL() { //0x29
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     //HL has 0x1077
     cpu.H = 0x10;
@@ -603,7 +548,7 @@ fn addHLToHL() { //0x29
 #[test]
 fn addSPToHL() { //0x39
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     //HL has 0x1077
     cpu.H = 0x10;
@@ -653,9 +598,9 @@ fn loadFromMem8Bit() { //0xA
     macro_rules! testLoadFromMem8 {
         ($destReg: ident, $highAddr: ident, $lowAddr: ident, $inst: expr) => ({
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
-            writeByteToMemory(&mut mem, 0xAA, 0xCCDD); //load AA to CCDD
+            writeFlatBusByte(&mut mem, 0xAA, 0xCCDD); //load AA to CCDD
 
             cpu.$highAddr = 0xCC;
             cpu.$lowAddr = 0xDD;
@@ -671,9 +616,9 @@ fn loadFromMem8Bit() { //0xA
 
         ($destReg: ident, $highAddr: ident, $lowAddr: ident, $opOnHL: expr, $inst: expr) => ({
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
-            writeByteToMemory(&mut mem, 0xAA, 0xCCDD); //load AA to CCDD
+            writeFlatBusByte(&mut mem, 0xAA, 0xCCDD); //load AA to CCDD
 
             cpu.$highAddr = 0xCC;
             cpu.$lowAddr = 0xDD;
@@ -707,7 +652,7 @@ fn decrement16() { //0xB, 0x1B
         ($highReg: ident, $lowReg: ident, $inst: expr) => ({
 
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
             cpu.$highReg = 0x00;
             cpu.$lowReg = 0x00;
@@ -734,7 +679,7 @@ fn decrement16() { //0xB, 0x1B
 fn rotateRight() { //0xF
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     //test rotate 0
     let (newPC, cyclesTaken) = executeInstruction(0xF, &mut cpu, &mut mem);
@@ -742,7 +687,7 @@ fn rotateRight() { //0xF
     assert!(cpu.A == 0);
 
     assert!(!isFlagSet(Half, cpu.F));
-    assert!(isFlagSet(Zero, cpu.F));
+    assert!(!isFlagSet(Zero, cpu.F)); //rotating A always clears Z, even when A ends up 0
     assert!(!isFlagSet(Neg, cpu.F));
     assert!(!isFlagSet(Carry, cpu.F));
 
@@ -792,7 +737,7 @@ fn rotateLeftThroughCarry() { //0x17
     macro_rules! testRLA {
         ($regAVal: expr, $expectedVal: expr, $setC: expr, $isCSet: expr) => ({
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
             cpu.A = $regAVal;
 
@@ -807,12 +752,7 @@ fn rotateLeftThroughCarry() { //0x17
 
             assert!(!isFlagSet(Half, cpu.F));
 
-            if $expectedVal == 0 {
-                assert!(isFlagSet(Zero, cpu.F));
-            }
-            else {
-                assert!(!isFlagSet(Zero, cpu.F));
-            }
+            assert!(!isFlagSet(Zero, cpu.F)); //rotating A always clears Z, even when A ends up 0
 
             assert!(!isFlagSet(Neg, cpu.F));
 
@@ -851,11 +791,11 @@ fn jumpRelative() { //0x18
     //NOTE(DanB): This mostly tests that the signed conversion works
     fn testJR(uOffset: u8, sOffset: i8) {
         let mut cpu = testingCPU();
-        let mut mem = tetrisMemoryMapState();
+        let mut mem = testingBus();
 
 
         //load offset 
-        writeByteToMemory(&mut mem, uOffset, cpu.PC+1);
+        writeFlatBusByte(&mut mem, uOffset, cpu.PC+1);
 
         let (newPC, cyclesTaken) = executeInstruction(0x18, &mut cpu, &mut mem);
 
@@ -873,7 +813,7 @@ fn jumpRelativeWithCondition() { //0x20
 
     fn testJRC(flag: Flag, shouldBeSet: bool, inst: u8) {
         let mut cpu = testingCPU();
-        let mut mem = tetrisMemoryMapState();
+        let mut mem = testingBus();
 
         //should perform jump
         if shouldBeSet {
@@ -884,7 +824,7 @@ fn jumpRelativeWithCondition() { //0x20
         }
 
         //load offset 
-        writeByteToMemory(&mut mem, 0x80, cpu.PC+1);
+        writeFlatBusByte(&mut mem, 0x80, cpu.PC+1);
 
         let (newPC, cyclesTaken) = executeInstruction(inst, &mut cpu, &mut mem);
 
@@ -900,7 +840,7 @@ fn jumpRelativeWithCondition() { //0x20
         }
 
         //load offset 
-        writeByteToMemory(&mut mem, 0x80, cpu.PC+1);
+        writeFlatBusByte(&mut mem, 0x80, cpu.PC+1);
 
         let (newPC, cyclesTaken) = executeInstruction(inst, &mut cpu, &mut mem);
 
@@ -922,7 +862,7 @@ fn rotateRightThroughCarry() { //0x1F
     macro_rules! testRRA {
         ($regAVal: expr, $expectedVal: expr, $setC: expr, $isCSet: expr) => ({
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
             cpu.A = $regAVal;
 
@@ -937,12 +877,7 @@ fn rotateRightThroughCarry() { //0x1F
 
             assert!(!isFlagSet(Half, cpu.F));
 
-            if $expectedVal == 0 {
-                assert!(isFlagSet(Zero, cpu.F));
-            }
-            else {
-                assert!(!isFlagSet(Zero, cpu.F));
-            }
+            assert!(!isFlagSet(Zero, cpu.F)); //rotating A always clears Z, even when A ends up 0
 
             assert!(!isFlagSet(Neg, cpu.F));
 
@@ -982,7 +917,7 @@ fn decimalAdjust() { //0x27
 fn complementA() { //0x2F
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
 
@@ -1003,10 +938,10 @@ fn complementA() { //0x2F
 fn loadImm16IntoSP() { //0x31
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
-    mem.workingRAM[1] = 0xBB; //write AABB to memory location 1
-    mem.workingRAM[2] = 0xAA;
+    mem.memory[0xC001] = 0xBB; //write AABB to memory location 1
+    mem.memory[0xC002] = 0xAA;
 
     let (newPC, cyclesTaken) = executeInstruction(0x31, &mut cpu, &mut mem);
 
@@ -1021,7 +956,7 @@ fn loadImm16IntoSP() { //0x31
 fn incrementSP() { //0x33
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.SP = 0xCFFF;
 
@@ -1038,7 +973,7 @@ fn incrementSP() { //0x33
 fn decrementSP() { //0x3B
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.SP = 0xD000;
 
@@ -1054,21 +989,21 @@ fn decrementSP() { //0x3B
 #[test]
 fn incrementValAtHL() { //0x34
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     //increment value at 0xCCDD
     cpu.H = 0xCC;
     cpu.L = 0xDD;
 
     //test half carry and zero set
-    writeByteToMemory(&mut mem, 0xFF, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0xFF, 0xCCDD);
 
     let (newPC, cyclesTaken) = executeInstruction(0x34, &mut cpu, &mut mem);
 
     assert!(newPC == cpu.PC + 1);
     assert!(cyclesTaken == 12);
 
-    assert!(readByteFromMemory(&mem, 0xCCDD) == 0);
+    assert!(readFlatBusByte(&mem, 0xCCDD) == 0);
 
     assert!(isFlagSet(Half, cpu.F));
     assert!(isFlagSet(Zero, cpu.F));
@@ -1076,14 +1011,14 @@ fn incrementValAtHL() { //0x34
 
     //test half carry and zero clear
 
-    writeByteToMemory(&mut mem, 0x1, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0x1, 0xCCDD);
 
     let (newPC, cyclesTaken) = executeInstruction(0x34, &mut cpu, &mut mem);
 
     assert!(newPC == cpu.PC + 1);
     assert!(cyclesTaken == 12);
 
-    assert!(readByteFromMemory(&mem, 0xCCDD) == 2);
+    assert!(readFlatBusByte(&mem, 0xCCDD) == 2);
 
     assert!(!isFlagSet(Half, cpu.F));
     assert!(!isFlagSet(Zero, cpu.F));
@@ -1095,21 +1030,21 @@ fn incrementValAtHL() { //0x34
 #[test]
 fn decrementValAtHL() { //0x35
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     //decrement value at 0xCCDD
     cpu.H = 0xCC;
     cpu.L = 0xDD;
 
     //test half carry
-    writeByteToMemory(&mut mem, 0, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0, 0xCCDD);
 
     let (newPC, cyclesTaken) = executeInstruction(0x35, &mut cpu, &mut mem);
 
     assert!(newPC == cpu.PC + 1);
     assert!(cyclesTaken == 12);
 
-    assert!(readByteFromMemory(&mem, 0xCCDD) == 0xFF);
+    assert!(readFlatBusByte(&mem, 0xCCDD) == 0xFF);
 
     assert!(isFlagSet(Half, cpu.F));
     assert!(!isFlagSet(Zero, cpu.F));
@@ -1117,14 +1052,14 @@ fn decrementValAtHL() { //0x35
 
     //test zero set
 
-    writeByteToMemory(&mut mem, 0x1, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0x1, 0xCCDD);
 
     let (newPC, cyclesTaken) = executeInstruction(0x35, &mut cpu, &mut mem);
 
     assert!(newPC == cpu.PC + 1);
     assert!(cyclesTaken == 12);
 
-    assert!(readByteFromMemory(&mem, 0xCCDD) == 0);
+    assert!(readFlatBusByte(&mem, 0xCCDD) == 0);
 
     assert!(!isFlagSet(Half, cpu.F));
     assert!(isFlagSet(Zero, cpu.F));
@@ -1132,14 +1067,14 @@ fn decrementValAtHL() { //0x35
 
     //test nothing set
 
-    writeByteToMemory(&mut mem, 0xFF, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0xFF, 0xCCDD);
 
     let (newPC, cyclesTaken) = executeInstruction(0x35, &mut cpu, &mut mem);
 
     assert!(newPC == cpu.PC + 1);
     assert!(cyclesTaken == 12);
 
-    assert!(readByteFromMemory(&mem, 0xCCDD) == 0xFE);
+    assert!(readFlatBusByte(&mem, 0xCCDD) == 0xFE);
 
     assert!(!isFlagSet(Half, cpu.F));
     assert!(!isFlagSet(Zero, cpu.F));
@@ -1152,21 +1087,21 @@ fn decrementValAtHL() { //0x35
 fn loadImm8ToMemPointedAtHL() { //0x36
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     //decrement value at 0xCCDD
     cpu.H = 0xCC;
     cpu.L = 0xDD;
 
     //test half carry
-    writeByteToMemory(&mut mem, 0xAA, cpu.PC + 1);
+    writeFlatBusByte(&mut mem, 0xAA, cpu.PC + 1);
 
     let (newPC, cyclesTaken) = executeInstruction(0x36, &mut cpu, &mut mem);
 
     assert!(newPC == cpu.PC + 2);
     assert!(cyclesTaken == 12);
 
-    assert!(readByteFromMemory(&mem, 0xCCDD) == 0xAA);
+    assert!(readFlatBusByte(&mem, 0xCCDD) == 0xAA);
 }
 
 #[test]
@@ -1183,7 +1118,7 @@ fn setCarry() { //0x37
 #[test]
 fn complementCarry() { //0x3F
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.F = 0xF0;  //set all flags
 
@@ -1215,7 +1150,7 @@ fn complementCarry() { //0x3F
 #[test]
 fn load8BitReg() { //0x40 - 0x7F
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     //used for register to register loading
     macro_rules! load8BitRegFromReg {
@@ -1243,7 +1178,7 @@ fn load8BitReg() { //0x40 - 0x7F
             cpu.H = 0xCC;
             cpu.L = 0xDD;
 
-            writeByteToMemory(&mut mem, 0xAA, 0xCCDD);
+            writeFlatBusByte(&mut mem, 0xAA, 0xCCDD);
 
             let (newPC, cyclesTaken) = executeInstruction($instr, &mut cpu, &mut mem);
 
@@ -1267,7 +1202,7 @@ fn load8BitReg() { //0x40 - 0x7F
 
             let (newPC, cyclesTaken) = executeInstruction($instr, &mut cpu, &mut mem);
 
-            assert!(readByteFromMemory(&mut mem, 0xCCCC) == 0xCC);
+            assert!(readFlatBusByte(&mut mem, 0xCCCC) == 0xCC);
             assert!(newPC == cpu.PC + 1);
             assert!(cyclesTaken == 8);
         });
@@ -1356,6 +1291,102 @@ fn halt() { //0x76
     assert!(cpu.instructionCycles == 4);
 }
 
+#[test]
+fn haltWakesUpWithInterruptsDisabled() {
+    let mut cpu = testingCPU();
+    let mut mem = testingBus();
+
+    writeFlatBusByte(&mut mem, 0x76, cpu.PC); //HALT
+    writeFlatBusByte(&mut mem, 0x3C, cpu.PC+1); //INC A
+    writeFlatBusByte(&mut mem, 0x04, 0xFFFF); //IE: timer
+
+    stepCPU(&mut cpu, &mut mem);
+    assert!(cpu.isHalted);
+
+    stepCPU(&mut cpu, &mut mem);
+    assert!(cpu.isHalted);
+    assert_eq!(cpu.PC, 0xC001);
+
+    //the interrupt wakes the CPU up but doesn't get handled
+    writeFlatBusByte(&mut mem, 0x04, 0xFF0F);
+    stepCPU(&mut cpu, &mut mem);
+
+    assert!(!cpu.isHalted);
+    assert_eq!(cpu.A, 1);
+    assert_eq!(cpu.PC, 0xC002);
+    assert_eq!(readFlatBusByte(&mem, 0xFF0F), 0x04);
+}
+
+#[test]
+fn haltWakesUpAndHandlesInterrupt() {
+    let mut cpu = testingCPU();
+    let mut mem = testingBus();
+
+    cpu.enableInterrupts = true;
+    writeFlatBusByte(&mut mem, 0x76, cpu.PC); //HALT
+    writeFlatBusByte(&mut mem, 0x00, 0x50); //NOP at the timer ISR
+    writeFlatBusByte(&mut mem, 0x04, 0xFFFF); //IE: timer
+
+    stepCPU(&mut cpu, &mut mem);
+    assert!(cpu.isHalted);
+
+    writeFlatBusByte(&mut mem, 0x04, 0xFF0F);
+    stepCPU(&mut cpu, &mut mem);
+
+    //the NOP at the timer ISR ran
+    assert!(!cpu.isHalted);
+    assert_eq!(cpu.PC, 0x51);
+    assert_eq!(readFlatBusWord(&mem, cpu.SP), 0xC001);
+}
+
+#[test]
+fn haltBug() {
+    let mut cpu = testingCPU();
+    let mut mem = testingBus();
+
+    writeFlatBusByte(&mut mem, 0x76, cpu.PC); //HALT
+    writeFlatBusByte(&mut mem, 0x3C, cpu.PC+1); //INC A
+    writeFlatBusByte(&mut mem, 0x04, 0xFFFF); //IE: timer
+    writeFlatBusByte(&mut mem, 0x04, 0xFF0F); //IF: timer
+
+    //an interrupt is already pending with interrupts disabled, so the CPU doesn't halt
+    stepCPU(&mut cpu, &mut mem);
+    assert!(!cpu.isHalted);
+    assert_eq!(cpu.PC, 0xC001);
+
+    //INC A gets read twice
+    stepCPU(&mut cpu, &mut mem);
+    assert_eq!(cpu.A, 1);
+    assert_eq!(cpu.PC, 0xC001);
+
+    stepCPU(&mut cpu, &mut mem);
+    assert_eq!(cpu.A, 2);
+    assert_eq!(cpu.PC, 0xC002);
+}
+
+#[test]
+fn haltBugWithImmediate() {
+    let mut cpu = testingCPU();
+    let mut mem = testingBus();
+
+    writeFlatBusByte(&mut mem, 0x76, cpu.PC); //HALT
+    writeFlatBusByte(&mut mem, 0x3E, cpu.PC+1); //LD A, 0x3C
+    writeFlatBusByte(&mut mem, 0x3C, cpu.PC+2);
+    writeFlatBusByte(&mut mem, 0x04, 0xFFFF); //IE: timer
+    writeFlatBusByte(&mut mem, 0x04, 0xFF0F); //IF: timer
+
+    stepCPU(&mut cpu, &mut mem);
+
+    //the opcode gets read again as the immediate, then the immediate runs as INC A
+    stepCPU(&mut cpu, &mut mem);
+    assert_eq!(cpu.A, 0x3E);
+    assert_eq!(cpu.PC, 0xC002);
+
+    stepCPU(&mut cpu, &mut mem);
+    assert_eq!(cpu.A, 0x3F);
+    assert_eq!(cpu.PC, 0xC003);
+}
+
 #[test]
 fn add8Bit() { //0x80-0x85
 
@@ -1363,7 +1394,7 @@ fn add8Bit() { //0x80-0x85
         //add registers
         ($srcReg: ident, $inst: expr) => ({
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
             cpu.A = 0xAA;
 
@@ -1433,7 +1464,7 @@ fn add8Bit() { //0x80-0x85
 fn add8BitFromMemAtHL() { //0x86
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
 
@@ -1443,7 +1474,7 @@ fn add8BitFromMemAtHL() { //0x86
 
 
     //memory has 0x11
-    writeByteToMemory(&mut mem, 0x11, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0x11, 0xCCDD);
 
     //AA + 11 = BB
     let (newPC, cyclesTaken) = executeInstruction(0x86, &mut cpu, &mut mem);
@@ -1451,7 +1482,7 @@ fn add8BitFromMemAtHL() { //0x86
     assert!(cyclesTaken == 8);
     assert!(newPC == cpu.PC + 1);
     assert!(cpu.A == 0xBB);
-    assert!(readByteFromMemory(&mem, 0xCCDD) == 0x11);
+    assert!(readFlatBusByte(&mem, 0xCCDD) == 0x11);
 
     //no flags set
     assert!(cpu.F == 0);
@@ -1459,7 +1490,7 @@ fn add8BitFromMemAtHL() { //0x86
 
     cpu.A = 0xAE;
 
-    writeByteToMemory(&mut mem, 0x2, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0x2, 0xCCDD);
 
     //AE + 2 = B0
     let (newPC, cyclesTaken) = executeInstruction(0x86, &mut cpu, &mut mem);
@@ -1477,7 +1508,7 @@ fn add8BitFromMemAtHL() { //0x86
     cpu.A = 0xFF;
 
 
-    writeByteToMemory(&mut mem, 0x1, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0x1, 0xCCDD);
 
     //FF + 1 = 0
     let (newPC, cyclesTaken) = executeInstruction(0x86, &mut cpu, &mut mem);
@@ -1497,13 +1528,13 @@ fn add8BitFromMemAtHL() { //0x86
 fn add8BitFromMem() { //0xC6
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
 
 
     //memory has 0x11
-    writeByteToMemory(&mut mem, 0x11, cpu.PC +1);
+    writeFlatBusByte(&mut mem, 0x11, cpu.PC +1);
 
     //AA + 11 = BB
     let (newPC, cyclesTaken) = executeInstruction(0xC6, &mut cpu, &mut mem);
@@ -1518,7 +1549,7 @@ fn add8BitFromMem() { //0xC6
 
     cpu.A = 0xAE;
 
-    writeByteToMemory(&mut mem, 0x2, cpu.PC +1);
+    writeFlatBusByte(&mut mem, 0x2, cpu.PC +1);
 
     //AE + 2 = B0
     let (newPC, cyclesTaken) = executeInstruction(0xC6, &mut cpu, &mut mem);
@@ -1536,7 +1567,7 @@ fn add8BitFromMem() { //0xC6
     cpu.A = 0xFF;
 
 
-    writeByteToMemory(&mut mem, 0x1, cpu.PC + 1);
+    writeFlatBusByte(&mut mem, 0x1, cpu.PC + 1);
 
     //FF + 1 = 0
     let (newPC, cyclesTaken) = executeInstruction(0xC6, &mut cpu, &mut mem);
@@ -1556,7 +1587,7 @@ fn add8BitFromMem() { //0xC6
 fn addAtoA() { //0x87
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0x66;
 
@@ -1614,7 +1645,7 @@ fn addCarry8BitFromRegister() { //0x88-0x8D
         //add registers
         ($srcReg: ident, $inst: expr) => ({
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
             cpu.A = 0xAA;
 
@@ -1688,7 +1719,7 @@ fn addCarry8BitFromRegister() { //0x88-0x8D
 fn addCarry8BitFromMemAtHL() { //0x8E
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
 
@@ -1700,7 +1731,7 @@ fn addCarry8BitFromMemAtHL() { //0x8E
     setFlag(Carry, &mut cpu.F);
 
     //memory has 0x11
-    writeByteToMemory(&mut mem, 0x11, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0x11, 0xCCDD);
 
     //AA + 11 + Carry = BC
     let (newPC, cyclesTaken) = executeInstruction(0x8E, &mut cpu, &mut mem);
@@ -1708,7 +1739,7 @@ fn addCarry8BitFromMemAtHL() { //0x8E
     assert!(cyclesTaken == 8);
     assert!(newPC == cpu.PC + 1);
     assert!(cpu.A == 0xBC);
-    assert!(readByteFromMemory(&mem, 0xCCDD) == 0x11);
+    assert!(readFlatBusByte(&mem, 0xCCDD) == 0x11);
 
     //no flags set
     assert!(cpu.F == 0);
@@ -1716,7 +1747,7 @@ fn addCarry8BitFromMemAtHL() { //0x8E
 
     cpu.A = 0xAE;
 
-    writeByteToMemory(&mut mem, 0x2, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0x2, 0xCCDD);
 
     //AE + 2 = B0
     let (newPC, cyclesTaken) = executeInstruction(0x8E, &mut cpu, &mut mem);
@@ -1734,7 +1765,7 @@ fn addCarry8BitFromMemAtHL() { //0x8E
     cpu.A = 0xFF;
 
 
-    writeByteToMemory(&mut mem, 0x1, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0x1, 0xCCDD);
 
     //FF + 1 = 0
     let (newPC, cyclesTaken) = executeInstruction(0x8E, &mut cpu, &mut mem);
@@ -1754,7 +1785,7 @@ fn addCarry8BitFromMemAtHL() { //0x8E
 fn addCarry8BitFromMem() { //0xCE
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
 
@@ -1763,7 +1794,7 @@ fn addCarry8BitFromMem() { //0xCE
     setFlag(Carry, &mut cpu.F);
 
     //memory has 0x11
-    writeByteToMemory(&mut mem, 0x11, cpu.PC +1);
+    writeFlatBusByte(&mut mem, 0x11, cpu.PC +1);
 
     //AA + 11 + Carry = BC
     let (newPC, cyclesTaken) = executeInstruction(0xCE, &mut cpu, &mut mem);
@@ -1778,7 +1809,7 @@ fn addCarry8BitFromMem() { //0xCE
 
     cpu.A = 0xAE;
 
-    writeByteToMemory(&mut mem, 0x2, cpu.PC +1);
+    writeFlatBusByte(&mut mem, 0x2, cpu.PC +1);
 
     //AE + 2 = B0
     let (newPC, cyclesTaken) = executeInstruction(0xCE, &mut cpu, &mut mem);
@@ -1796,7 +1827,7 @@ fn addCarry8BitFromMem() { //0xCE
     cpu.A = 0xFF;
 
 
-    writeByteToMemory(&mut mem, 0x1, cpu.PC +1);
+    writeFlatBusByte(&mut mem, 0x1, cpu.PC +1);
 
     //FF + 1 = 0
     let (newPC, cyclesTaken) = executeInstruction(0xCE, &mut cpu, &mut mem);
@@ -1816,7 +1847,7 @@ fn addCarry8BitFromMem() { //0xCE
 fn addCarryAtoA() { //0x8F
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0x66;
 
@@ -1875,7 +1906,7 @@ fn sub8Bit() { //0x90-0x95
         //add registers
         ($srcReg: ident, $inst: expr) => ({
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
             cpu.A = 0xAA;
 
@@ -1951,7 +1982,7 @@ fn sub8BitFromMemAtHL() { //0x96
 
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
 
@@ -1961,7 +1992,7 @@ fn sub8BitFromMemAtHL() { //0x96
     //Make sure that setting Carry doesn't affect anything
     setFlag(Carry, &mut cpu.F);
 
-    writeByteToMemory(&mut mem, 0x11, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0x11, 0xCCDD);
 
     //AA - 11 - Carry = 0x98
     let (newPC, cyclesTaken) = executeInstruction(0x96, &mut cpu, &mut mem);
@@ -1978,7 +2009,7 @@ fn sub8BitFromMemAtHL() { //0x96
 
     cpu.A = 0x1;
 
-    writeByteToMemory(&mut mem, 0xFF, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0xFF, 0xCCDD);
 
     //1 - FF = 2
     let (newPC, cyclesTaken) = executeInstruction(0x96, &mut cpu, &mut mem);
@@ -1995,7 +2026,7 @@ fn sub8BitFromMemAtHL() { //0x96
 
     cpu.A = 0xAA;
 
-    writeByteToMemory(&mut mem, 0xAA, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0xAA, 0xCCDD);
 
     //AA - AA = 0
     let (newPC, cyclesTaken) = executeInstruction(0x96, &mut cpu, &mut mem);
@@ -2017,14 +2048,14 @@ fn sub8BitFromMem() { //0x96
 
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
 
     //Make sure that setting Carry doesn't affect anything
     setFlag(Carry, &mut cpu.F);
 
-    writeByteToMemory(&mut mem, 0x11, cpu.PC +1);
+    writeFlatBusByte(&mut mem, 0x11, cpu.PC +1);
 
     //AA - 11 - Carry = 0x98
     let (newPC, cyclesTaken) = executeInstruction(0xD6, &mut cpu, &mut mem);
@@ -2041,7 +2072,7 @@ fn sub8BitFromMem() { //0x96
 
     cpu.A = 0x1;
 
-    writeByteToMemory(&mut mem, 0xFF, cpu.PC +1);
+    writeFlatBusByte(&mut mem, 0xFF, cpu.PC +1);
 
     //1 - FF = 2
     let (newPC, cyclesTaken) = executeInstruction(0xD6, &mut cpu, &mut mem);
@@ -2058,7 +2089,7 @@ fn sub8BitFromMem() { //0x96
 
     cpu.A = 0xAA;
 
-    writeByteToMemory(&mut mem, 0xAA, cpu.PC +1);
+    writeFlatBusByte(&mut mem, 0xAA, cpu.PC +1);
 
     //AA - AA = 0
     let (newPC, cyclesTaken) = executeInstruction(0xD6, &mut cpu, &mut mem);
@@ -2080,7 +2111,7 @@ fn subAFromA() { //0x97
 
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
     //AA - AA = 0
@@ -2106,7 +2137,7 @@ fn subCarry8Bit() { //0x98-0x9D
         //add registers
         ($srcReg: ident, $inst: expr) => ({
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
             //Make sure that we subtract carry
             setFlag(Carry, &mut cpu.F);
@@ -2182,14 +2213,14 @@ fn subCarry8BitFromMemAtHL() { //0x9E
 
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
 
     cpu.H = 0xCC;
     cpu.L = 0xDD;
 
-    writeByteToMemory(&mut mem, 0x11, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0x11, 0xCCDD);
 
     //AA - 11 = 0x99
     let (newPC, cyclesTaken) = executeInstruction(0x9E, &mut cpu, &mut mem);
@@ -2206,7 +2237,7 @@ fn subCarry8BitFromMemAtHL() { //0x9E
 
     cpu.A = 0x1;
 
-    writeByteToMemory(&mut mem, 0xFF, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0xFF, 0xCCDD);
 
     //1 - FF = 2
     let (newPC, cyclesTaken) = executeInstruction(0x9E, &mut cpu, &mut mem);
@@ -2223,7 +2254,7 @@ fn subCarry8BitFromMemAtHL() { //0x9E
 
     cpu.A = 0xAA;
 
-    writeByteToMemory(&mut mem, 0xAA, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0xAA, 0xCCDD);
 
     //AA - AA - Carry = 0xFF
     let (newPC, cyclesTaken) = executeInstruction(0x9E, &mut cpu, &mut mem);
@@ -2245,11 +2276,11 @@ fn subCarry8BitFromMem() { //0xDE
 
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
 
-    writeByteToMemory(&mut mem, 0x11, cpu.PC + 1);
+    writeFlatBusByte(&mut mem, 0x11, cpu.PC + 1);
 
     //AA - 11 = 0x99
     let (newPC, cyclesTaken) = executeInstruction(0xDE, &mut cpu, &mut mem);
@@ -2266,7 +2297,7 @@ fn subCarry8BitFromMem() { //0xDE
 
     cpu.A = 0x1;
 
-    writeByteToMemory(&mut mem, 0xFF, cpu.PC + 1);
+    writeFlatBusByte(&mut mem, 0xFF, cpu.PC + 1);
 
     //1 - FF = 2
     let (newPC, cyclesTaken) = executeInstruction(0xDE, &mut cpu, &mut mem);
@@ -2283,7 +2314,7 @@ fn subCarry8BitFromMem() { //0xDE
 
     cpu.A = 0xAA;
 
-    writeByteToMemory(&mut mem, 0xAA, cpu.PC + 1);
+    writeFlatBusByte(&mut mem, 0xAA, cpu.PC + 1);
 
     //AA - AA - Carry = 0xFF
     let (newPC, cyclesTaken) = executeInstruction(0xDE, &mut cpu, &mut mem);
@@ -2305,7 +2336,7 @@ fn subCarryAFromA() { //0x9F
 
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     //Make sure that adding carry works
     setFlag(Carry, &mut cpu.F);
@@ -2333,7 +2364,7 @@ fn andRegToA() {
     macro_rules! testAnd {
         ($srcReg: ident, $inst: expr) => ({
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
             cpu.A = 0xAA;
 
@@ -2387,7 +2418,7 @@ fn andRegToA() {
 fn andMemAtHLToA() {
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
 
@@ -2395,7 +2426,7 @@ fn andMemAtHLToA() {
     cpu.L = 0xDD;
 
 
-    writeByteToMemory(&mut mem, 0x22, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0x22, 0xCCDD);
 
 
     //AA & 22 = 22 
@@ -2404,7 +2435,7 @@ fn andMemAtHLToA() {
     assert!(cyclesTaken == 8);
     assert!(newPC == cpu.PC + 1);
     assert!(cpu.A == 0x22);
-    assert!(readByteFromMemory(&mem, 0xCCDD) == 0x22);
+    assert!(readFlatBusByte(&mem, 0xCCDD) == 0x22);
 
     assert!(isFlagSet(Half, cpu.F));
     assert!(!isFlagSet(Carry, cpu.F));
@@ -2416,7 +2447,7 @@ fn andMemAtHLToA() {
 
     cpu.A = 0xAA;
 
-    writeByteToMemory(&mut mem, 0x55, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0x55, 0xCCDD);
 
     //AA & 0x55 = 0
     let (newPC, cyclesTaken) = executeInstruction(0xA6, &mut cpu, &mut mem);
@@ -2437,11 +2468,11 @@ fn andMemAtHLToA() {
 fn andMemToA() {
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
 
-    writeByteToMemory(&mut mem, 0x22, cpu.PC +1);
+    writeFlatBusByte(&mut mem, 0x22, cpu.PC +1);
 
 
     //AA & 22 = 22 
@@ -2461,7 +2492,7 @@ fn andMemToA() {
 
     cpu.A = 0xAA;
 
-    writeByteToMemory(&mut mem, 0x55, cpu.PC+1);
+    writeFlatBusByte(&mut mem, 0x55, cpu.PC+1);
 
     //AA & 0x55 = 0
     let (newPC, cyclesTaken) = executeInstruction(0xE6, &mut cpu, &mut mem);
@@ -2482,7 +2513,7 @@ fn andMemToA() {
 fn andAToA() {
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
 
@@ -2522,7 +2553,7 @@ fn xorRegToA() {
     macro_rules! testXOR {
         ($srcReg: ident, $inst: expr) => ({
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
             cpu.A = 0xAA;
 
@@ -2576,7 +2607,7 @@ fn xorRegToA() {
 fn xorMemAtHLToA() {
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
 
@@ -2584,7 +2615,7 @@ fn xorMemAtHLToA() {
     cpu.L = 0xDD;
 
 
-    writeByteToMemory(&mut mem, 0xAA, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0xAA, 0xCCDD);
 
 
     //AA ^ AA = 0
@@ -2593,7 +2624,7 @@ fn xorMemAtHLToA() {
     assert!(cyclesTaken == 8);
     assert!(newPC == cpu.PC + 1);
     assert!(cpu.A == 0);
-    assert!(readByteFromMemory(&mem, 0xCCDD) == 0xAA);
+    assert!(readFlatBusByte(&mem, 0xCCDD) == 0xAA);
 
     assert!(!isFlagSet(Half, cpu.F));
     assert!(!isFlagSet(Carry, cpu.F));
@@ -2607,7 +2638,7 @@ fn xorMemAtHLToA() {
 
     cpu.A = 0xAA;
 
-    writeByteToMemory(&mut mem, 0x55, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0x55, 0xCCDD);
 
     //AA ^ 0x55 =  0xFF
     let (newPC, cyclesTaken) = executeInstruction(0xAE, &mut cpu, &mut mem);
@@ -2627,11 +2658,11 @@ fn xorMemAtHLToA() {
 fn xorMemToA() {
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
 
-    writeByteToMemory(&mut mem, 0xAA, cpu.PC + 1);
+    writeFlatBusByte(&mut mem, 0xAA, cpu.PC + 1);
 
     //AA ^ AA = 0
     let (newPC, cyclesTaken) = executeInstruction(0xEE, &mut cpu, &mut mem);
@@ -2649,7 +2680,7 @@ fn xorMemToA() {
 
     cpu.A = 0xAA;
 
-    writeByteToMemory(&mut mem, 0x55, cpu.PC + 1);
+    writeFlatBusByte(&mut mem, 0x55, cpu.PC + 1);
 
     //AA ^ 0x55 =  0xFF
     let (newPC, cyclesTaken) = executeInstruction(0xEE, &mut cpu, &mut mem);
@@ -2669,7 +2700,7 @@ fn xorMemToA() {
 fn xorAToA() {
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
 
@@ -2694,7 +2725,7 @@ fn orRegToA() {
     macro_rules! testOR {
         ($srcReg: ident, $inst: expr) => ({
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
 
             //0 | 0 = 0
@@ -2744,7 +2775,7 @@ fn orRegToA() {
 fn orMemAtHLToA() {
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0;
 
@@ -2752,7 +2783,7 @@ fn orMemAtHLToA() {
     cpu.L = 0xDD;
 
 
-    writeByteToMemory(&mut mem, 0, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0, 0xCCDD);
 
 
     //0 | 0 = 0
@@ -2774,7 +2805,7 @@ fn orMemAtHLToA() {
 
     cpu.A = 0xAA;
 
-    writeByteToMemory(&mut mem, 0xFF, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0xFF, 0xCCDD);
 
     //AA | 0xFF =  0xFF
     let (newPC, cyclesTaken) = executeInstruction(0xB6, &mut cpu, &mut mem);
@@ -2794,7 +2825,7 @@ fn orMemAtHLToA() {
 fn orMemLToA() {
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0;
 
@@ -2802,7 +2833,7 @@ fn orMemLToA() {
     cpu.L = 0xDD;
 
 
-    writeByteToMemory(&mut mem, 0, cpu.PC +1);
+    writeFlatBusByte(&mut mem, 0, cpu.PC +1);
 
 
     //0 | 0 = 0
@@ -2824,7 +2855,7 @@ fn orMemLToA() {
 
     cpu.A = 0xAA;
 
-    writeByteToMemory(&mut mem, 0xFF, cpu.PC + 1);
+    writeFlatBusByte(&mut mem, 0xFF, cpu.PC + 1);
 
     //AA | 0xFF =  0xFF
     let (newPC, cyclesTaken) = executeInstruction(0xF6, &mut cpu, &mut mem);
@@ -2844,7 +2875,7 @@ fn orMemLToA() {
 fn orAToA() {
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
 
@@ -2870,7 +2901,7 @@ fn compare8Bit() { //0xB8-0xBD
         //add registers
         ($srcReg: ident, $inst: expr) => ({
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
             cpu.A = 0xAA;
 
@@ -2947,7 +2978,7 @@ fn compare8BitFromMemAtHL() { //0xBE
 
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
 
@@ -2957,7 +2988,7 @@ fn compare8BitFromMemAtHL() { //0xBE
     //Make sure that setting Carry doesn't affect anything
     setFlag(Carry, &mut cpu.F);
 
-    writeByteToMemory(&mut mem, 0x11, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0x11, 0xCCDD);
 
     //AA > 11, C and Z clear
     let (newPC, cyclesTaken) = executeInstruction(0xBE, &mut cpu, &mut mem);
@@ -2974,7 +3005,7 @@ fn compare8BitFromMemAtHL() { //0xBE
 
     cpu.A = 0x1;
 
-    writeByteToMemory(&mut mem, 0xFF, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0xFF, 0xCCDD);
 
     //1 < FF, C set Z clear  
     let (newPC, cyclesTaken) = executeInstruction(0xBE, &mut cpu, &mut mem);
@@ -2991,7 +3022,7 @@ fn compare8BitFromMemAtHL() { //0xBE
 
     cpu.A = 0xAA;
 
-    writeByteToMemory(&mut mem, 0xAA, 0xCCDD);
+    writeFlatBusByte(&mut mem, 0xAA, 0xCCDD);
 
     //AA == AA, Z set, C clear
     let (newPC, cyclesTaken) = executeInstruction(0xBE, &mut cpu, &mut mem);
@@ -3013,14 +3044,14 @@ fn compare8BitFromMem() { //0xFE
 
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
 
     //Make sure that setting Carry doesn't affect anything
     setFlag(Carry, &mut cpu.F);
 
-    writeByteToMemory(&mut mem, 0x11, cpu.PC + 1);
+    writeFlatBusByte(&mut mem, 0x11, cpu.PC + 1);
 
     //AA > 11, C and Z clear
     let (newPC, cyclesTaken) = executeInstruction(0xFE, &mut cpu, &mut mem);
@@ -3037,7 +3068,7 @@ fn compare8BitFromMem() { //0xFE
 
     cpu.A = 0x1;
 
-    writeByteToMemory(&mut mem, 0xFF, cpu.PC + 1);
+    writeFlatBusByte(&mut mem, 0xFF, cpu.PC + 1);
 
     //1 < FF, C set Z clear  
     let (newPC, cyclesTaken) = executeInstruction(0xFE, &mut cpu, &mut mem);
@@ -3054,7 +3085,7 @@ fn compare8BitFromMem() { //0xFE
 
     cpu.A = 0xAA;
 
-    writeByteToMemory(&mut mem, 0xAA, cpu.PC + 1);
+    writeFlatBusByte(&mut mem, 0xAA, cpu.PC + 1);
 
     //AA == AA, Z set, C clear
     let (newPC, cyclesTaken) = executeInstruction(0xFE, &mut cpu, &mut mem);
@@ -3076,7 +3107,7 @@ fn compareAToA() { //0xBF
 
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
 
@@ -3099,24 +3130,24 @@ fn compareAToA() { //0xBF
 fn callAndReturn() {
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     let oldPC = cpu.PC;
 
-    writeWordToMemory(&mut mem, 0xCC00, cpu.PC+1); //call address 0xCC00
+    writeFlatBusWord(&mut mem, 0xCC00, cpu.PC+1); //call address 0xCC00
 
     //execute CALL a16
     let (newPC, cyclesTaken) = executeInstruction(0xCD, &mut cpu, &mut mem);
 
     assert_eq!(newPC, 0xCC00);
-    assert_eq!(readWordFromMemory(&mut mem, cpu.SP), oldPC + 3);
+    assert_eq!(readFlatBusWord(&mut mem, cpu.SP), oldPC + 3);
     assert_eq!(cyclesTaken, 24);
 
     //execute RET
     let (newPC, cyclesTaken) = executeInstruction(0xC9, &mut cpu, &mut mem);
 
     assert_eq!(newPC, oldPC + 3);
-    assert_eq!(readWordFromMemory(&mut mem, cpu.SP), 0);
+    assert_eq!(readFlatBusWord(&mut mem, cpu.SP), 0);
     assert_eq!(cyclesTaken, 16);
 
 }
@@ -3124,7 +3155,7 @@ fn callAndReturn() {
 #[test]
 fn restart() {
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     macro_rules! testRestart {
         ($resetAddress: expr, $inst:expr) => ({
@@ -3135,7 +3166,7 @@ fn restart() {
             let (newPC, cyclesTaken) = executeInstruction($inst, &mut cpu, &mut mem);
 
             assert_eq!(newPC, $resetAddress);
-            assert_eq!(readWordFromMemory(&mut mem, cpu.SP), oldPC + 1);
+            assert_eq!(readFlatBusWord(&mut mem, cpu.SP), oldPC + 1);
             assert_eq!(cyclesTaken, 16);
         })
     }
@@ -3156,11 +3187,11 @@ fn returnFromProcConditional() {
 
     fn testRET(flag: Flag, shouldBeSet: bool, inst: u8) {
         let mut cpu = testingCPU();
-        let mut mem = tetrisMemoryMapState();
+        let mut mem = testingBus();
 
         let mut oldPC = cpu.PC;
 
-        writeWordToMemory(&mut mem, 0xCC00, cpu.PC+1); //call address 0xCC00
+        writeFlatBusWord(&mut mem, 0xCC00, cpu.PC+1); //call address 0xCC00
 
         //execute CALL a16
         executeInstruction(0xCD, &mut cpu, &mut mem);
@@ -3177,7 +3208,7 @@ fn returnFromProcConditional() {
         let (newPC, cyclesTaken) = executeInstruction(inst, &mut cpu, &mut mem);
 
         assert_eq!(newPC, oldPC + 3);
-        assert_eq!(readWordFromMemory(&mut mem, cpu.SP), 0);
+        assert_eq!(readFlatBusWord(&mut mem, cpu.SP), 0);
         assert_eq!(cyclesTaken, 20);
 
 
@@ -3199,7 +3230,7 @@ fn returnFromProcConditional() {
         let (newPC, cyclesTaken) = executeInstruction(inst, &mut cpu, &mut mem);
 
         assert_eq!(newPC, cpu.PC + 1);
-        assert_eq!(readWordFromMemory(&mut mem, cpu.SP), oldPC + 3);  //make sure the return address is still on the stack
+        assert_eq!(readFlatBusWord(&mut mem, cpu.SP), oldPC + 3);  //make sure the return address is still on the stack
         assert_eq!(cyclesTaken, 8);
     }
 
@@ -3220,11 +3251,11 @@ fn callConditional() {
 
     fn testCALL(flag: Flag, shouldBeSet: bool, inst: u8) {
         let mut cpu = testingCPU();
-        let mut mem = tetrisMemoryMapState();
+        let mut mem = testingBus();
 
         let oldPC = cpu.PC;
 
-        writeWordToMemory(&mut mem, 0xCC00, cpu.PC+1); //call address 0xCC00
+        writeFlatBusWord(&mut mem, 0xCC00, cpu.PC+1); //call address 0xCC00
 
         if shouldBeSet {
             setFlag(flag, &mut cpu.F);
@@ -3238,7 +3269,7 @@ fn callConditional() {
         let (newPC, cyclesTaken) = executeInstruction(inst, &mut cpu, &mut mem);
 
         assert_eq!(newPC, 0xCC00);
-        assert_eq!(readWordFromMemory(&mut mem, cpu.SP), oldPC + 3);
+        assert_eq!(readFlatBusWord(&mut mem, cpu.SP), oldPC + 3);
         assert_eq!(cyclesTaken, 24);
 
 
@@ -3271,9 +3302,9 @@ fn pop16() {
     macro_rules! testPop16 {
         ($highReg: ident, $lowReg: ident, $inst: expr) => ({
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
-            writeWordToMemory(&mut mem, 0xAABB, 0xCCD0);
+            writeFlatBusWord(&mut mem, 0xAABB, 0xCCD0);
             cpu.SP = 0xCCD0;
 
             let (newPC, cyclesTaken) = executeInstruction($inst, &mut cpu, &mut mem);
@@ -3297,7 +3328,7 @@ fn push16() {
     macro_rules! testPush16 {
         ($highReg: ident, $lowReg: ident, $inst: expr) => ({
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
             cpu.SP = 0xCCD0;
 
@@ -3309,7 +3340,7 @@ fn push16() {
             let (newPC, cyclesTaken) = executeInstruction($inst, &mut cpu, &mut mem);
 
             assert_eq!(cpu.SP, 0xCCCE);
-            assert_eq!(readWordFromMemory(&mut mem, cpu.SP), 0xAABB);
+            assert_eq!(readFlatBusWord(&mut mem, cpu.SP), 0xAABB);
             assert_eq!(cyclesTaken, 16);
             assert_eq!(newPC, cpu.PC+1);
 
@@ -3329,7 +3360,7 @@ fn jumpAbsoluteConditional() {
 
     fn testJRC(flag: Flag, shouldBeSet: bool, inst: u8) {
         let mut cpu = testingCPU();
-        let mut mem = tetrisMemoryMapState();
+        let mut mem = testingBus();
 
         //should perform jump
         if shouldBeSet {
@@ -3340,7 +3371,7 @@ fn jumpAbsoluteConditional() {
         }
 
         //load address to jump to 
-        writeWordToMemory(&mut mem, 0xAABB, cpu.PC+1);
+        writeFlatBusWord(&mut mem, 0xAABB, cpu.PC+1);
 
         let (newPC, cyclesTaken) = executeInstruction(inst, &mut cpu, &mut mem);
 
@@ -3356,7 +3387,7 @@ fn jumpAbsoluteConditional() {
         }
 
         //load address 
-        writeWordToMemory(&mut mem, 0xAABB, cpu.PC+1);
+        writeFlatBusWord(&mut mem, 0xAABB, cpu.PC+1);
 
         let (newPC, cyclesTaken) = executeInstruction(inst, &mut cpu, &mut mem);
 
@@ -3376,9 +3407,9 @@ fn jumpAbsoluteConditional() {
 #[test]
 fn jumpAbsolute() {
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
-    writeWordToMemory(&mut mem, 0xAABB, cpu.PC+1);
+    writeFlatBusWord(&mut mem, 0xAABB, cpu.PC+1);
 
     let (newPC, cyclesTaken) = executeInstruction(0xC3, &mut cpu, &mut mem);
 
@@ -3389,15 +3420,15 @@ fn jumpAbsolute() {
 #[test]
 fn loadAIntoHighMem() { //E0
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
 
-    writeByteToMemory(&mut mem, 0xBB, cpu.PC+1); 
+    writeFlatBusByte(&mut mem, 0xBB, cpu.PC+1); 
 
     let (newPC, cyclesTaken) = executeInstruction(0xE0, &mut cpu, &mut mem);
 
-    assert_eq!(readByteFromMemory(&mut mem, 0xFFBB), 0xAA);
+    assert_eq!(readFlatBusByte(&mut mem, 0xFFBB), 0xAA);
     assert_eq!(newPC, cpu.PC + 2);
     assert_eq!(cyclesTaken, 12);
 
@@ -3406,7 +3437,7 @@ fn loadAIntoHighMem() { //E0
 #[test]
 fn loadAIntoHighMemAtC() { //E2
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
 
@@ -3414,7 +3445,7 @@ fn loadAIntoHighMemAtC() { //E2
     cpu.C = 0xBB;
     let (newPC, cyclesTaken) = executeInstruction(0xE2, &mut cpu, &mut mem);
 
-    assert_eq!(readByteFromMemory(&mut mem, 0xFFBB), 0xAA);
+    assert_eq!(readFlatBusByte(&mut mem, 0xFFBB), 0xAA);
     assert_eq!(newPC, cpu.PC + 1);
     assert_eq!(cyclesTaken, 8);
 
@@ -3424,11 +3455,11 @@ fn loadAIntoHighMemAtC() { //E2
 fn addToSPSigned() { //E8
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     let oldSP = cpu.SP;
 
-    writeByteToMemory(&mut mem, -2i8 as u8, cpu.PC+1);
+    writeFlatBusByte(&mut mem, -2i8 as u8, cpu.PC+1);
     cpu.F = 0xF0; //set all the flags
 
     let (newPC, cyclesTaken) = executeInstruction(0xE8, &mut cpu, &mut mem);
@@ -3437,11 +3468,12 @@ fn addToSPSigned() { //E8
     assert_eq!(newPC, cpu.PC + 2);
     assert_eq!(cyclesTaken, 16);
 
-    assert_eq!(cpu.F, 0);
+    //0xFE + 0xFE carries out of both bit 3 and bit 7 of the low byte
+    assert_eq!(cpu.F, 0x30);
 
     cpu.SP = 0xFEF8;
 
-    writeByteToMemory(&mut mem, 0x8, cpu.PC+1);
+    writeFlatBusByte(&mut mem, 0x8, cpu.PC+1);
     cpu.F = 0xF0; //set all the flags
 
     let (newPC, cyclesTaken) = executeInstruction(0xE8, &mut cpu, &mut mem);
@@ -3460,7 +3492,7 @@ fn addToSPSigned() { //E8
 #[test]
 fn jumpUsingHL() {//E9
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.H = 0xAA;
     cpu.L = 0xBB;
@@ -3476,15 +3508,15 @@ fn jumpUsingHL() {//E9
 #[test]
 fn loadAIntoMem() { //EA
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
 
-    writeWordToMemory(&mut mem, 0xCCBB, cpu.PC+1); 
+    writeFlatBusWord(&mut mem, 0xCCBB, cpu.PC+1); 
 
     let (newPC, cyclesTaken) = executeInstruction(0xEA, &mut cpu, &mut mem);
 
-    assert_eq!(readByteFromMemory(&mut mem, 0xCCBB), 0xAA);
+    assert_eq!(readFlatBusByte(&mut mem, 0xCCBB), 0xAA);
     assert_eq!(newPC, cpu.PC + 3);
     assert_eq!(cyclesTaken, 16);
 
@@ -3493,13 +3525,13 @@ fn loadAIntoMem() { //EA
 #[test]
 fn loadHighMemIntoA() { //F0
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
 
-    writeByteToMemory(&mut mem, 0xBB, cpu.PC+1); 
+    writeFlatBusByte(&mut mem, 0xBB, cpu.PC+1); 
 
     //A will have 0xAA
-    writeByteToMemory(&mut mem, 0xAA, 0xFFBB); 
+    writeFlatBusByte(&mut mem, 0xAA, 0xFFBB); 
 
     let (newPC, cyclesTaken) = executeInstruction(0xF0, &mut cpu, &mut mem);
 
@@ -3512,18 +3544,18 @@ fn loadHighMemIntoA() { //F0
 #[test]
 fn loadHighMemAtCIntoA() { //F2
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.C = 0xBB;
 
     //A will have 0xAA
-    writeByteToMemory(&mut mem, 0xAA, 0xFFBB); 
+    writeFlatBusByte(&mut mem, 0xAA, 0xFFBB); 
 
     let (newPC, cyclesTaken) = executeInstruction(0xF2, &mut cpu, &mut mem);
 
     assert_eq!(cpu.A, 0xAA);
-    assert_eq!(newPC, cpu.PC + 2);
-    assert_eq!(cyclesTaken, 12);
+    assert_eq!(newPC, cpu.PC + 1);
+    assert_eq!(cyclesTaken, 8);
 
 }
 
@@ -3535,10 +3567,10 @@ fn disableInterrupts() {
 fn loadSPPlusImmIntoSP() { //F8
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.SP = 0xFF00;
-    writeByteToMemory(&mut mem, -2i8 as u8, cpu.PC+1);
+    writeFlatBusByte(&mut mem, -2i8 as u8, cpu.PC+1);
     cpu.F = 0xF0; //set all the flags
 
     let (newPC, cyclesTaken) = executeInstruction(0xF8, &mut cpu, &mut mem);
@@ -3551,7 +3583,7 @@ fn loadSPPlusImmIntoSP() { //F8
 
     cpu.SP = 0xFEF8;
 
-    writeByteToMemory(&mut mem, 0x8, cpu.PC+1);
+    writeFlatBusByte(&mut mem, 0x8, cpu.PC+1);
     cpu.F = 0xF0; //set all the flags
 
     let (newPC, cyclesTaken) = executeInstruction(0xF8, &mut cpu, &mut mem);
@@ -3570,7 +3602,7 @@ fn loadSPPlusImmIntoSP() { //F8
 #[test]
 fn loadHLIntoSP() { //F9
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.H = 0xAA;
     cpu.L = 0xBB;
@@ -3585,12 +3617,12 @@ fn loadHLIntoSP() { //F9
 #[test]
 fn loadMemIntoA() { //FA
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
     cpu.A = 0xAA;
 
-    writeWordToMemory(&mut mem, 0xCCBB, cpu.PC+1); 
-    writeByteToMemory(&mut mem, 0xAA, 0xCCBB); 
+    writeFlatBusWord(&mut mem, 0xCCBB, cpu.PC+1); 
+    writeFlatBusByte(&mut mem, 0xAA, 0xCCBB); 
 
     let (newPC, cyclesTaken) = executeInstruction(0xFA, &mut cpu, &mut mem);
 
@@ -3610,10 +3642,10 @@ fn rotateLeftCB() { //0xCB00-0xCB07
     macro_rules! testRLC {
         ($reg:ident, $inst: expr) => ({
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
 
-            writeByteToMemory(&mut mem, $inst, cpu.PC + 1);
+            writeFlatBusByte(&mut mem, $inst, cpu.PC + 1);
 
             //test rotate 0
             let (newPC, cyclesTaken) = executeInstruction(0xCB, &mut cpu, &mut mem);
@@ -3678,10 +3710,10 @@ fn rotateLeftCB() { //0xCB00-0xCB07
 fn rotateLeftCBAtHL() {
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
 
-    writeByteToMemory(&mut mem, 6, cpu.PC + 1);
+    writeFlatBusByte(&mut mem, 6, cpu.PC + 1);
 
 
     //Byte at CCBB
@@ -3691,7 +3723,7 @@ fn rotateLeftCBAtHL() {
     //test rotate 0
     let (newPC, cyclesTaken) = executeInstruction(0xCB, &mut cpu, &mut mem);
 
-    assert_eq!(readByteFromMemory(&mut mem, 0xCCBB), 0);
+    assert_eq!(readFlatBusByte(&mut mem, 0xCCBB), 0);
 
     assert!(!isFlagSet(Half, cpu.F));
     assert!(isFlagSet(Zero, cpu.F));
@@ -3702,12 +3734,12 @@ fn rotateLeftCBAtHL() {
     assert!(cyclesTaken == 16);
 
     //test C set
-    writeByteToMemory(&mut mem, 0x88, 0xCCBB);
+    writeFlatBusByte(&mut mem, 0x88, 0xCCBB);
 
 
     let (newPC, cyclesTaken) = executeInstruction(0xCB, &mut cpu, &mut mem);
 
-    assert_eq!(readByteFromMemory(&mut mem, 0xCCBB), 0x11);
+    assert_eq!(readFlatBusByte(&mut mem, 0xCCBB), 0x11);
 
     assert!(!isFlagSet(Half, cpu.F));
     assert!(!isFlagSet(Zero, cpu.F));
@@ -3719,11 +3751,11 @@ fn rotateLeftCBAtHL() {
     assert!(cyclesTaken == 16);
 
     //test C clear
-    writeByteToMemory(&mut mem, 0x7F, 0xCCBB);
+    writeFlatBusByte(&mut mem, 0x7F, 0xCCBB);
 
     let (newPC, cyclesTaken) = executeInstruction(0xCB, &mut cpu, &mut mem);
 
-    assert_eq!(readByteFromMemory(&mut mem, 0xCCBB), 0xFE);
+    assert_eq!(readFlatBusByte(&mut mem, 0xCCBB), 0xFE);
 
     assert!(!isFlagSet(Half, cpu.F));
     assert!(!isFlagSet(Zero, cpu.F));
@@ -3740,10 +3772,10 @@ fn rotateRightCB() { //CB08 - CB0D and CB0F
     macro_rules! testRRC {
         ($reg:ident, $inst: expr) => ({
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
 
-            writeByteToMemory(&mut mem, $inst, cpu.PC + 1);
+            writeFlatBusByte(&mut mem, $inst, cpu.PC + 1);
             //test rotate 0
             let (newPC, cyclesTaken) = executeInstruction(0xCB, &mut cpu, &mut mem);
 
@@ -3808,10 +3840,10 @@ fn rotateRightCB() { //CB08 - CB0D and CB0F
 fn rotateRightCBAtHL() {//E
 
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
 
-    writeByteToMemory(&mut mem, 0xE, cpu.PC + 1);
+    writeFlatBusByte(&mut mem, 0xE, cpu.PC + 1);
 
 
     //Byte at CCBB
@@ -3821,7 +3853,7 @@ fn rotateRightCBAtHL() {//E
     //test rotate 0
     let (newPC, cyclesTaken) = executeInstruction(0xCB, &mut cpu, &mut mem);
 
-    assert_eq!(readByteFromMemory(&mut mem, 0xCCBB), 0);
+    assert_eq!(readFlatBusByte(&mut mem, 0xCCBB), 0);
 
     assert!(!isFlagSet(Half, cpu.F));
     assert!(isFlagSet(Zero, cpu.F));
@@ -3832,12 +3864,12 @@ fn rotateRightCBAtHL() {//E
     assert!(cyclesTaken == 16);
 
     //test C set
-    writeByteToMemory(&mut mem, 0x11, 0xCCBB);
+    writeFlatBusByte(&mut mem, 0x11, 0xCCBB);
 
 
     let (newPC, cyclesTaken) = executeInstruction(0xCB, &mut cpu, &mut mem);
 
-    assert_eq!(readByteFromMemory(&mut mem, 0xCCBB), 0x88);
+    assert_eq!(readFlatBusByte(&mut mem, 0xCCBB), 0x88);
 
     assert!(!isFlagSet(Half, cpu.F));
     assert!(!isFlagSet(Zero, cpu.F));
@@ -3849,11 +3881,11 @@ fn rotateRightCBAtHL() {//E
     assert!(cyclesTaken == 16);
 
     //test C clear
-    writeByteToMemory(&mut mem, 0x76, 0xCCBB);
+    writeFlatBusByte(&mut mem, 0x76, 0xCCBB);
 
     let (newPC, cyclesTaken) = executeInstruction(0xCB, &mut cpu, &mut mem);
 
-    assert_eq!(readByteFromMemory(&mut mem, 0xCCBB), 0x3B);
+    assert_eq!(readFlatBusByte(&mut mem, 0xCCBB), 0x3B);
 
     assert!(!isFlagSet(Half, cpu.F));
     assert!(!isFlagSet(Zero, cpu.F));
@@ -3866,13 +3898,13 @@ fn rotateRightCBAtHL() {//E
 
 #[test]
 fn rotateLeftThroughCarryCB() { //CB10 - CB15 and CB17
-    let mut mem = tetrisMemoryMapState(); //pulled this out so it doesn't overflow stack memory
+    let mut mem = testingBus(); //pulled this out so it doesn't overflow stack memory
 
     macro_rules! testRLA {
         ($regAVal: expr, $expectedVal: expr, $setC: expr, $isCSet: expr, $reg:ident, $inst:expr) => ({
             let mut cpu = testingCPU();
 
-            writeByteToMemory(&mut mem, $inst, cpu.PC + 1);
+            writeFlatBusByte(&mut mem, $inst, cpu.PC + 1);
 
             cpu.$reg = $regAVal;
 
@@ -3941,10 +3973,10 @@ fn rotateLeftThroughCarryAtHLCB() { //CB16
     macro_rules! testRLA {
         ($regAVal: expr, $expectedVal: expr, $setC: expr, $isCSet: expr) => ({
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
-            writeByteToMemory(&mut mem, 0x16, cpu.PC+1);
-            writeByteToMemory(&mut mem, $regAVal, 0xCCBB);
+            writeFlatBusByte(&mut mem, 0x16, cpu.PC+1);
+            writeFlatBusByte(&mut mem, $regAVal, 0xCCBB);
 
 
             cpu.H = 0xCC;
@@ -3957,7 +3989,7 @@ fn rotateLeftThroughCarryAtHLCB() { //CB16
             //test rotate 0
             let (newPC, cyclesTaken) = executeInstruction(0xCB, &mut cpu, &mut mem);
 
-            assert!(readByteFromMemory(&mut mem, 0xCCBB) == $expectedVal);
+            assert!(readFlatBusByte(&mut mem, 0xCCBB) == $expectedVal);
 
             assert!(!isFlagSet(Half, cpu.F));
 
@@ -4002,13 +4034,13 @@ fn rotateLeftThroughCarryAtHLCB() { //CB16
 
 #[test]
 fn rotateRightThroughCarryCB() { //CB18 - CB1D and CB1F
-    let mut mem = tetrisMemoryMapState(); //pulled this out so it doesn't overflow stack memory
+    let mut mem = testingBus(); //pulled this out so it doesn't overflow stack memory
 
     macro_rules! testRR {
         ($regAVal: expr, $expectedVal: expr, $setC: expr, $isCSet: expr, $reg:ident, $inst:expr) => ({
             let mut cpu = testingCPU();
 
-            writeByteToMemory(&mut mem, $inst, cpu.PC + 1);
+            writeFlatBusByte(&mut mem, $inst, cpu.PC + 1);
 
             cpu.$reg = $regAVal;
 
@@ -4078,10 +4110,10 @@ fn rotateRightThroughCarryAtHLCB() { //CB1E
     macro_rules! testRR {
         ($regAVal: expr, $expectedVal: expr, $setC: expr, $isCSet: expr) => ({
             let mut cpu = testingCPU();
-            let mut mem = tetrisMemoryMapState();
+            let mut mem = testingBus();
 
-            writeByteToMemory(&mut mem, 0x1E, cpu.PC+1);
-            writeByteToMemory(&mut mem, $regAVal, 0xCCBB);
+            writeFlatBusByte(&mut mem, 0x1E, cpu.PC+1);
+            writeFlatBusByte(&mut mem, $regAVal, 0xCCBB);
 
 
             cpu.H = 0xCC;
@@ -4094,7 +4126,7 @@ fn rotateRightThroughCarryAtHLCB() { //CB1E
             //test rotate 0
             let (newPC, cyclesTaken) = executeInstruction(0xCB, &mut cpu, &mut mem);
 
-            assert!(readByteFromMemory(&mut mem, 0xCCBB) == $expectedVal);
+            assert!(readFlatBusByte(&mut mem, 0xCCBB) == $expectedVal);
 
             assert!(!isFlagSet(Half, cpu.F));
 
@@ -4143,7 +4175,7 @@ fn rotateRightThroughCarryAtHLCB() { //CB1E
 #[test]
 fn cbInstructions() {
     let mut cpu = testingCPU();
-    let mut mem = tetrisMemoryMapState();
+    let mut mem = testingBus();
 
 
     //inner scope for macros to work
@@ -4161,7 +4193,7 @@ fn cbInstructions() {
                     6 =>{
                         cpu.H = 0xCC;
                         cpu.L = 0xBB;
-                        writeByteToMemory(&mut mem, $value, 0xCCBB);
+                        writeFlatBusByte(&mut mem, $value, 0xCCBB);
                     },    
                     7 => cpu.A = $value,
                     _ => panic!("Unreachable")
@@ -4179,7 +4211,7 @@ fn cbInstructions() {
                     4 => cpu.H,
                     5 => cpu.L,
                     // we will be using address 0xCCBB to store (HL) values
-                    6 => readByteFromMemory(&mut mem, 0xCCBB),
+                    6 => readFlatBusByte(&mut mem, 0xCCBB),
                     7 => cpu.A,
                     _ => panic!("Unreachable")
 
@@ -4193,7 +4225,10 @@ fn cbInstructions() {
 
         macro_rules! testCyclesTaken {
             ($value: expr, $inst: expr) => ({
-                if ($inst & 0xF) % 8 == 6 {
+                if $inst >= 0x40 && $inst <= 0x7F && ($inst & 0xF) % 8 == 6 {
+                    assert_eq!($value, 12); //BIT n, (HL) doesn't write back
+                }
+                else if ($inst & 0xF) % 8 == 6 {
                     assert_eq!($value, 16);
                 }
                 else {
@@ -4207,7 +4242,7 @@ fn cbInstructions() {
 
         for i in 0x20..0xFF {
             println!("Testing Instruction: {:X}", i); 
-            writeByteToMemory(&mut mem, i, cpu.PC + 1);
+            writeFlatBusByte(&mut mem, i, cpu.PC + 1);
             cpu.F = 0xF0; //set all flags
 
             macro_rules! executeInstruction {
@@ -0,0 +1,144 @@
+use gb_gameboy::*;
+use gb_memory::*;
+use gb_mbc::*;
+use gb_lcd::*;
+use gb_savestate::*;
+use tests::common::*;
+
+
+#[test]
+fn testSaveState() {
+    let mut gb = gameBoyWithMBC1("");
+
+    gb.cpu.PC = 0x150;
+    gb.cpu.A = 0x12;
+    gb.cpu.totalCycles = 0x123456789;
+    writeByteToMemory(&mut gb.mem, 0xAB, 0xC123); //working RAM
+    writeByteToMemory(&mut gb.mem, 0xCD, 0xFF90); //zero page
+    writeByteToMemory(&mut gb.mem, 0xE4, 0xFF47); //background palette
+    writeByteToMemory(&mut gb.mem, 0xA, 0x0000); //enable cart RAM
+    writeByteToMemory(&mut gb.mem, 0x3, 0x2000); //ROM bank 3
+    writeByteToMemory(&mut gb.mem, 0xEF, 0xA010); 
+    gb.mem.lcd.videoRAM[0x10] = 0x55;
+    gb.mem.lcd.screen[10][20] = BLACK;
+
+    let state = gb.save_state();
+
+    let mut loadedGB = gameBoyWithMBC1("");
+    loadedGB.load_state(&state[..]).unwrap();
+
+    assert_eq!(loadedGB.cpu.PC, 0x150);
+    assert_eq!(loadedGB.cpu.A, 0x12);
+    assert_eq!(loadedGB.cpu.totalCycles, 0x123456789);
+    assert_eq!(readByteFromMemory(&loadedGB.mem, 0xC123), 0xAB);
+    assert_eq!(readByteFromMemory(&loadedGB.mem, 0xFF90), 0xCD);
+    assert_eq!(readByteFromMemory(&loadedGB.mem, 0xFF47), 0xE4);
+    assert_eq!(readByteFromMemory(&loadedGB.mem, 0xA010), 0xEF);
+    assert_eq!(loadedGB.mem.lcd.videoRAM[0x10], 0x55);
+    assert!(loadedGB.mem.lcd.screen[10][20] == BLACK);
+
+    //saving the loaded state gives back the same bytes
+    assert_eq!(loadedGB.save_state(), state);
+}
+
+#[test]
+fn testBadSaveState() {
+    let mut gb = gameBoyWithMBC1("");
+    let mut state = gb.save_state();
+
+    gb.cpu.PC = 0x150;
+
+    assert_eq!(gb.load_state(&state[..10]), Err(SaveStateError::UnexpectedEnd));
+    assert_eq!(gb.cpu.PC, 0x150); //failed loads don't change anything
+
+    state[0] = 0;
+    assert_eq!(gb.load_state(&state[..]), Err(SaveStateError::BadMagic));
+
+    let otherGB = GameBoyState::new(); //MBC0
+    let state = otherGB.save_state();
+    match gb.load_state(&state[..]) {
+        Err(SaveStateError::MBCMismatch{..}) => {},
+        result => panic!("Expected MBC mismatch. Got: {:?}", result)
+    }
+}
+
+#[test]
+fn testRunFrame() {
+    let mut gb = gameBoyWithMBC1(""); //ROM is all NOPs
+    gb.cpu.PC = 0x150;
+
+    //LCD is off, so a frame's worth of cycles gets run
+    let events = gb.run_frame();
+    assert_eq!(events.cycles, CYCLES_PER_FRAME);
+    assert!(!events.isVBlankReached);
+
+    writeByteToMemory(&mut gb.mem, 0x80, 0xFF40);
+
+    let events = gb.run_frame();
+    assert!(events.isVBlankReached);
+    assert_eq!(gb.mem.lcd.currScanLine, 144);
+
+    //the next frame runs from one VBlank to the next
+    let events = gb.run_frame();
+    assert!(events.isVBlankReached);
+    assert!(events.cycles > CYCLES_PER_FRAME / 2 && events.cycles <= CYCLES_PER_FRAME);
+    assert_eq!(gb.mem.lcd.currScanLine, 144);
+
+    //the timer is stepped too
+    assert!(readByteFromMemory(&gb.mem, 0xFF04) != 0);
+}
+
+#[test]
+fn testBreakpoints() {
+    let mut gb = gameBoyWithMBC1("");
+    gb.cpu.PC = 0x150;
+    gb.breakpoints.push(0x160);
+
+    let events = gb.run_frame();
+    assert!(events.isBreakpointHit);
+    assert_eq!(events.cycles, 16 * 4);
+    assert_eq!(gb.cpu.PC, 0x160);
+
+    //running again continues past the breakpoint
+    let events = gb.run_cycles(100);
+    assert!(!events.isBreakpointHit);
+    assert_eq!(events.cycles, 100);
+    assert_eq!(gb.cpu.PC, 0x160 + 25);
+}
+
+#[test]
+fn testDMA() {
+    let mut gb = gameBoyWithMBC1("");
+    gb.cpu.PC = 0x150;
+
+    for i in 0..0xA0 {
+        writeByteToMemory(&mut gb.mem, i as u8, 0xC000 + i);
+    }
+    writeByteToMemory(&mut gb.mem, 0xC0, 0xFF46);
+
+    gb.run_cycles(0xA0 * 4 + 4);
+    assert!(!gb.mem.isDMAOccurring);
+    assert_eq!(gb.mem.lcd.oam[0x9F], 0x9F);
+}
+
+#[test]
+fn testLDBBBreakpoint() {
+    let mut rom = vec![0; 0x10000];
+    rom[0x155] = 0x40; //LD B,B
+
+    let mut gb = GameBoyState::new();
+    gb.mem.cartridge = Box::new(MBC1Cartridge::new(rom, 0));
+    gb.mem.inBios = false;
+    gb.cpu.PC = 0x150;
+
+    //only a breakpoint when enabled
+    let events = gb.run_cycles(10 * 4);
+    assert!(!events.isBreakpointHit);
+
+    gb.cpu.PC = 0x150;
+    gb.isLDBBBreakpointEnabled = true;
+
+    let events = gb.run_frame();
+    assert!(events.isBreakpointHit);
+    assert_eq!(gb.cpu.PC, 0x156);
+}
@@ -85,3 +85,72 @@ fn testOAM() {
 
     assert_eq!(readByteFromMemory(&mem,0xFE9F), 0x19); 
 }
+
+#[test]
+fn testWindowRegisters() {
+    let mut mem = MemoryMapState::new();
+
+    writeByteToMemory(&mut mem, 0x60, 0xFF40); //window enabled using tile map 1
+
+    assert_eq!(mem.lcd.windowTileMap, 1);
+    assert_eq!(mem.lcd.isWindowEnabled, true);
+    assert_eq!(readByteFromMemory(&mem,0xFF40), 0x60); 
+
+    writeByteToMemory(&mut mem, 40, 0xFF4A); //WY
+    writeByteToMemory(&mut mem, 87, 0xFF4B); //WX
+
+    assert_eq!(mem.lcd.wy, 40);
+    assert_eq!(mem.lcd.wx, 87);
+    assert_eq!(readByteFromMemory(&mem,0xFF4A), 40); 
+    assert_eq!(readByteFromMemory(&mem,0xFF4B), 87); 
+}
+
+#[test]
+fn testWindowDrawing() {
+    let mut mem = MemoryMapState::new();
+    let mut requestedInterrupts = 0u8;
+
+    mem.lcd.mode = VBlank;
+    writeByteToMemory(&mut mem, 0xE4, 0xFF47); //normal palette
+
+    //tile 1 is all color 3
+    for i in 0..16 {
+        writeByteToMemory(&mut mem, 0xFF, 0x8010 + i);
+    }
+
+    //window tile map is all tile 1, background tile map stays tile 0
+    for i in 0..0x400 {
+        writeByteToMemory(&mut mem, 1, 0x9C00 + i);
+    }
+
+    writeByteToMemory(&mut mem, 0, 0xFF4A); //WY
+    writeByteToMemory(&mut mem, 87, 0xFF4B); //WX: window starts at pixel 80
+    writeByteToMemory(&mut mem, 0xF1, 0xFF40); //LCD, window, tile set 1 and background enabled
+
+    mem.lcd.mode = ScanOAM;
+    stepLCD(&mut mem.lcd, &mut requestedInterrupts, 80);
+    stepLCD(&mut mem.lcd, &mut requestedInterrupts, 172);
+
+    assert_eq!(mem.lcd.screenBackBuffer[0][79], WHITE);
+    assert_eq!(mem.lcd.screenBackBuffer[0][80], BLACK);
+    assert_eq!(mem.lcd.screenBackBuffer[0][159], BLACK);
+    assert_eq!(mem.lcd.windowLine, 1);
+}
+
+#[test]
+fn testScreenToPGM() {
+    let mut screen = BLANK_SCREEN;
+    screen[0][1] = BLACK;
+    screen[1][0] = LIGHT_GRAY;
+
+    let pgm = screenToPGM(&screen);
+    let header = b"P5\n160 144\n255\n";
+
+    assert_eq!(&pgm[..header.len()], &header[..]);
+    assert_eq!(pgm.len(), header.len() + SCREEN_WIDTH * SCREEN_HEIGHT);
+
+    let pixels = &pgm[header.len()..];
+    assert_eq!(pixels[0], 255);
+    assert_eq!(pixels[1], 0);
+    assert_eq!(pixels[SCREEN_WIDTH], 170);
+}
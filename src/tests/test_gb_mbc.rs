@@ -0,0 +1,313 @@
+use gb_mbc::*;
+use gb_memory::*;
+use gb_cpu::CLOCK_SPEED_HZ;
+
+//makes a ROM where the first byte of each bank is the bank number
+fn bankNumberedROM(numBanks: usize) -> Vec<u8> {
+    let mut romData = vec![0u8; numBanks * ROM_BANK_SIZE];
+
+    for bank in 0..numBanks {
+        romData[bank * ROM_BANK_SIZE] = bank as u8;
+    }
+
+    romData
+}
+
+fn romWithCartridgeType(cartridgeType: u8, numBanks: usize) -> Vec<u8> {
+    let mut romData = bankNumberedROM(numBanks);
+    romData[0x147] = cartridgeType;
+
+    romData
+}
+
+#[test]
+fn testCartridgeFromROM() {
+    let types = [(0x00, MemoryBankControllerType::MBC0),
+                 (0x01, MemoryBankControllerType::MBC1),
+                 (0x06, MemoryBankControllerType::MBC2),
+                 (0x10, MemoryBankControllerType::MBC3),
+                 (0x1E, MemoryBankControllerType::MBC5)];
+
+    for &(cartridgeType, mbcType) in types.iter() {
+        match cartridgeFromROM(romWithCartridgeType(cartridgeType, 2)) {
+            Ok(cartridge) => assert_eq!(cartridge.mbc_type(), mbcType),
+            Err(err) => panic!("{}", err)
+        }
+    }
+
+    assert!(cartridgeFromROM(romWithCartridgeType(0xFC, 2)).is_err()); //pocket camera
+    assert!(cartridgeFromROM(vec![0; 0x100]).is_err()); //no header
+}
+
+#[test]
+fn testMemoryMapUsesCartridge() {
+    let mut memory = MemoryMapState::new();
+    memory.inBios = false;
+    memory.cartridge = match cartridgeFromROM(romWithCartridgeType(0x11, 8)) {
+        Ok(cartridge) => cartridge,
+        Err(err) => panic!("{}", err)
+    };
+
+    writeByteToMemory(&mut memory, 0x5, 0x2000); 
+    assert_eq!(readByteFromMemory(&memory, 0x4000), 5);
+    assert_eq!(readByteFromMemory(&memory, 0x0000), 0);
+}
+
+#[test]
+fn testMBC0IgnoresControlWrites() {
+    let mut cart = MBC0Cartridge::new(bankNumberedROM(2), 0);
+
+    cart.write_control(0x2000, 0x5);
+    assert_eq!(cart.read_rom(0x4000), 1);
+}
+
+#[test]
+fn testMBC2() {
+    let mut cart = MBC2Cartridge::new(bankNumberedROM(16));
+
+    assert_eq!(cart.read_rom(0x4000), 1);
+
+    cart.write_control(0x2100, 0x5); //address bit 8 set selects ROM bank
+    assert_eq!(cart.read_rom(0x4000), 5);
+
+    cart.write_control(0x2100, 0x0); //bank 0 maps to bank 1
+    assert_eq!(cart.read_rom(0x4000), 1);
+
+    cart.write_control(0x2000, 0x3); //address bit 8 clear does not change the bank
+    assert_eq!(cart.read_rom(0x4000), 1);
+    assert_eq!(cart.isCartRAMEnabled, false);
+
+    cart.write_control(0x0000, 0x1A); //only the low nibble enables RAM
+    assert_eq!(cart.isCartRAMEnabled, true);
+
+    cart.write_ram(0xA001, 0xAB); 
+    assert_eq!(cart.cartRAM[1], 0xB); //RAM is only 4 bits wide
+    assert_eq!(cart.read_ram(0xA001), 0xFB); //upper bits read as 1
+    assert_eq!(cart.read_ram(0xA201), 0xFB); //RAM repeats every 512 bytes
+}
+
+#[test]
+fn testMBC3() {
+    let mut cart = MBC3Cartridge::new(bankNumberedROM(128), 0x8000);
+
+    cart.write_control(0x2000, 0x7F); 
+    assert_eq!(cart.read_rom(0x4000), 0x7F);
+
+    cart.write_control(0x2000, 0x0); //bank 0 maps to bank 1
+    assert_eq!(cart.read_rom(0x4000), 1);
+
+    cart.write_control(0x0000, 0xA); //enable RAM
+    cart.write_control(0x4000, 0x3); //RAM bank 3
+    cart.write_ram(0xA000, 0xAB); 
+    assert_eq!(cart.cartRAM[0x6000], 0xAB);
+    assert_eq!(cart.read_ram(0xA000), 0xAB);
+}
+
+#[test]
+fn testMBC3RealTimeClock() {
+    let mut cart = MBC3Cartridge::new(bankNumberedROM(2), 0);
+
+    cart.write_control(0x0000, 0xA); //enable RAM and RTC
+
+    cart.write_control(0x4000, 0x8); //select seconds
+    cart.write_ram(0xA000, 59); 
+    cart.write_control(0x4000, 0x9); //select minutes
+    cart.write_ram(0xA000, 59); 
+    cart.write_control(0x4000, 0xA); //select hours
+    cart.write_ram(0xA000, 23); 
+    cart.write_control(0x4000, 0xB); //select day low
+    cart.write_ram(0xA000, 0xFF); 
+    cart.write_control(0x4000, 0xC); //select day high
+    cart.write_ram(0xA000, 0x1); 
+
+    cart.step(CLOCK_SPEED_HZ as u32);
+
+    //registers don't change until latched
+    assert_eq!(cart.read_ram(0xA000), 0x1);
+
+    cart.write_control(0x6000, 0x0); 
+    cart.write_control(0x6000, 0x1); 
+
+    //day counter overflowed
+    assert_eq!(cart.read_ram(0xA000), 0x80);
+    cart.write_control(0x4000, 0x8); 
+    assert_eq!(cart.read_ram(0xA000), 0);
+
+    //clock does not advance when halted
+    cart.write_control(0x4000, 0xC); 
+    cart.write_ram(0xA000, 0x40); 
+    cart.step(CLOCK_SPEED_HZ as u32);
+    assert_eq!(cart.rtc.seconds, 0);
+}
+
+#[test]
+fn testMBC5() {
+    let mut cart = MBC5Cartridge::new(bankNumberedROM(512), 0x20000, false);
+
+    cart.write_control(0x2000, 0x0); //bank 0 is selectable
+    assert_eq!(cart.read_rom(0x4000), 0);
+
+    cart.write_control(0x3000, 0x1); //bank 0x100 
+    assert_eq!(cart.currentMBCBank, 0x100);
+    assert_eq!(cart.read_rom(0x4000), 0); //low byte of bank number
+
+    cart.write_control(0x2000, 0x23); 
+    assert_eq!(cart.currentMBCBank, 0x123);
+    assert_eq!(cart.read_rom(0x4000), 0x23);
+
+    cart.write_control(0x0000, 0xA); //enable RAM
+    cart.write_control(0x4000, 0xF); //RAM bank 15
+    cart.write_ram(0xA000, 0xAB); 
+    assert_eq!(cart.cartRAM[0x1E000], 0xAB);
+    assert_eq!(cart.is_rumble_motor_on(), false);
+}
+
+#[test]
+fn testMBC5Rumble() {
+    let mut memory = MemoryMapState::new();
+    memory.cartridge = Box::new(MBC5Cartridge::new(bankNumberedROM(2), 0, true));
+
+    writeByteToMemory(&mut memory, 0xA, 0x4000); //motor on, RAM bank 2
+    assert_eq!(memory.isRumbleMotorOn(), true);
+
+    writeByteToMemory(&mut memory, 0x2, 0x4000); 
+    assert_eq!(memory.isRumbleMotorOn(), false);
+}
+
+#[test]
+fn testMBC1() {
+    let mut cart = MBC1Cartridge::new(bankNumberedROM(128), 0x8000);
+
+    cart.write_control(0x2000, 0x0); //bank 0 maps to bank 1
+    assert_eq!(cart.read_rom(0x4000), 1);
+
+    cart.write_control(0x2000, 0x1F); 
+    cart.write_control(0x4000, 0x3); //upper bits of the ROM bank
+    assert_eq!(cart.read_rom(0x4000), 0x7F);
+    assert_eq!(cart.read_rom(0x0000), 0); //mode 0 always has bank 0 at 0x0000
+
+    cart.write_control(0x2000, 0x20); //only the low 5 bits are checked for 0
+    assert_eq!(cart.read_rom(0x4000), 0x61);
+
+    cart.write_control(0x6000, 0x1); //mode 1
+    assert_eq!(cart.read_rom(0x0000), 0x60);
+    assert_eq!(cart.read_rom(0x4000), 0x61);
+
+    cart.write_control(0x0000, 0xA); //enable RAM
+    cart.write_ram(0xA000, 0xAB); 
+    assert_eq!(cart.cartRAM[0x6000], 0xAB); //RAM bank 3 in mode 1
+
+    cart.write_control(0x6000, 0x0); //mode 0 only uses RAM bank 0
+    assert_eq!(cart.read_ram(0xA000), 0);
+}
+
+#[test]
+fn testMBC1BankMasking() {
+    let mut cart = MBC1Cartridge::new(bankNumberedROM(8), 0);
+
+    cart.write_control(0x2000, 0x1D); 
+    assert_eq!(cart.read_rom(0x4000), 5);
+
+    cart.write_control(0x4000, 0x1); //upper bits past the end of the ROM are ignored
+    assert_eq!(cart.read_rom(0x4000), 5);
+}
+
+#[test]
+fn testMBC1MultiCart() {
+    let mut romData = bankNumberedROM(64);
+    let logo: Vec<u8> = (0..0x30).map(|i| i as u8 + 1).collect();
+
+    for (i, byte) in logo.iter().enumerate() {
+        romData[0x104 + i] = *byte;
+        romData[0x40104 + i] = *byte; 
+    }
+
+    let mut cart = MBC1Cartridge::new(romData.clone(), 0);
+    assert!(cart.isMultiCart);
+
+    cart.write_control(0x4000, 0x1); 
+    cart.write_control(0x2000, 0x2); 
+    assert_eq!(cart.read_rom(0x4000), 0x12); //secondary register is bits 4-5
+
+    cart.write_control(0x6000, 0x1); 
+    assert_eq!(cart.read_rom(0x0000), 0x10);
+
+    romData[0x40104] = 0; //no second game
+    let cart = MBC1Cartridge::new(romData, 0);
+    assert!(!cart.isMultiCart);
+}
+
+#[test]
+fn testCartRAMSize() {
+    let mut romData = romWithCartridgeType(0x3, 8); //MBC1+RAM+BATTERY
+    romData[0x148] = 0x2; //128KB
+
+    let mut cart = cartridgeFromROM(romData.clone()).unwrap();
+    assert_eq!(cart.save_data().len(), 0);
+
+    cart.write_control(0x0000, 0xA); //enable RAM
+    cart.write_ram(0xA000, 0xAB); 
+    assert_eq!(cart.read_ram(0xA000), 0xFF); //no RAM
+
+    romData[0x149] = 0x7; //unknown size loads without RAM
+    let cart = cartridgeFromROM(romData.clone()).unwrap();
+    assert_eq!(cart.save_data().len(), 0);
+
+    romData[0x149] = 0x1; //2KB
+    let mut cart = cartridgeFromROM(romData.clone()).unwrap();
+    assert_eq!(cart.save_data().len(), 0x800);
+
+    cart.write_control(0x0000, 0xA); 
+    cart.write_ram(0xA000, 0xAB); 
+    assert_eq!(cart.read_ram(0xA800), 0xAB); //2KB RAM repeats
+
+    romData[0x149] = 0x2; //8KB
+    let mut cart = cartridgeFromROM(romData).unwrap();
+    assert_eq!(cart.save_data().len(), 0x2000);
+
+    cart.write_control(0x0000, 0xA); 
+    cart.write_ram(0xA000, 0xAB); 
+    cart.write_control(0x6000, 0x1); //mode 1
+    cart.write_control(0x4000, 0x3); //RAM bank 3 doesn't exist 
+    assert_eq!(cart.read_ram(0xA000), 0xAB);
+
+    cart.write_control(0x0000, 0x0); //disabled RAM reads as 0xFF
+    assert_eq!(cart.read_ram(0xA000), 0xFF);
+}
+
+#[test]
+fn testMBC3SaveData() {
+    let mut cart = MBC3Cartridge::new(bankNumberedROM(2), 0x2000);
+    cart.cartRAM[0x10] = 0xAB;
+    cart.rtc.minutes = 30;
+    cart.rtc.days = 0x1FF;
+    cart.rtc.isDayCarrySet = true;
+
+    let data = cart.save_data();
+    assert_eq!(data.len(), 0x2000 + RTC_FOOTER_SIZE);
+
+    let mut loadedCart = MBC3Cartridge::new(bankNumberedROM(2), 0x2000);
+    loadedCart.load_save_data(&data[..]);
+    assert_eq!(loadedCart.cartRAM[0x10], 0xAB);
+    assert_eq!(loadedCart.rtc.minutes, 30);
+    assert_eq!(loadedCart.rtc.days, 0x1FF);
+    assert_eq!(loadedCart.rtc.isDayCarrySet, true);
+}
+
+#[test]
+fn testAdvanceRTC() {
+    let mut rtc = RealTimeClock::new();
+    rtc.hours = 23;
+    rtc.days = 0x1FE;
+
+    advanceRTC(&mut rtc, (2 * 24 * 60 * 60) + 61);
+    assert_eq!(rtc.seconds, 1);
+    assert_eq!(rtc.minutes, 1);
+    assert_eq!(rtc.hours, 23);
+    assert_eq!(rtc.days, 0);
+    assert_eq!(rtc.isDayCarrySet, true);
+
+    rtc.isHalted = true;
+    advanceRTC(&mut rtc, 60);
+    assert_eq!(rtc.minutes, 1);
+}
@@ -1,6 +1,9 @@
+use std::env;
+use std::fs;
 
 use gb_memory::*;
 use gb_util::*;
+use gb_mbc::*;
 static MBC0_ROM : &'static str = "samples/mbc0.gb";
 
 #[test]
@@ -13,7 +16,7 @@ fn testReadAndWriteByte() {
     assert!(romData.len() == 0x8000); //type 0 carts are 32kb long
 
     let mut memory = MemoryMapState::new();
-    memory.romData = romData;
+    memory.cartridge = Box::new(MBC0Cartridge::new(romData, 0));
 
     assert!(readByteFromMemory(&memory,0) == 0x31); //reading from bios
 
@@ -59,7 +62,7 @@ fn testReadAndWriteWord() {
     assert!(romData.len() == 0x8000); //type 0 carts are 32kb long
 
     let mut memory = MemoryMapState::new();
-    memory.romData = romData;
+    memory.cartridge = Box::new(MBC0Cartridge::new(romData, 0));
 
 
 
@@ -97,6 +100,45 @@ fn testBIOSControls() {
 
 }
 
+#[test]
+fn testSaveFile() {
+    let fileName = env::temp_dir().join("gbEmuTestSaveFile.sav");
+    let fileName = fileName.to_str().unwrap();
+
+    let mut memory = MemoryMapState::new();
+    memory.cartridge = Box::new(MBC0Cartridge::new(vec![0; 0x8000], 0x2000));
+    memory.inBios = false;
+
+    writeByteToMemory(&mut memory, 0xAB, 0xA010);
+    assert!(memory.isCartRAMDirty);
+
+    writeSaveFile(&mut memory, fileName).unwrap();
+    assert!(!memory.isCartRAMDirty);
+
+    let mut loadedMemory = MemoryMapState::new();
+    loadedMemory.cartridge = Box::new(MBC0Cartridge::new(vec![0; 0x8000], 0x2000));
+    loadSaveFile(&mut loadedMemory, fileName).unwrap();
+    assert_eq!(readByteFromMemory(&loadedMemory, 0xA010), 0xAB);
 
+    fs::remove_file(fileName).unwrap();
+}
 
+#[test]
+fn testCartRAMDirtyOnlyWhenStored() {
+    let mut memory = MemoryMapState::new();
+    memory.cartridge = Box::new(MBC1Cartridge::new(vec![0; 0x10000], 0x2000));
+    memory.inBios = false;
 
+    writeByteToMemory(&mut memory, 0xAB, 0xA010); //RAM is disabled
+    assert!(!memory.isCartRAMDirty);
+
+    writeByteToMemory(&mut memory, 0xA, 0x0000); //enable cart RAM
+    writeByteToMemory(&mut memory, 0xAB, 0xA010);
+    assert!(memory.isCartRAMDirty);
+
+    let mut noRAMMemory = MemoryMapState::new();
+    noRAMMemory.cartridge = Box::new(MBC1Cartridge::new(vec![0; 0x10000], 0));
+    writeByteToMemory(&mut noRAMMemory, 0xA, 0x0000);
+    writeByteToMemory(&mut noRAMMemory, 0xAB, 0xA010);
+    assert!(!noRAMMemory.isCartRAMDirty);
+}
@@ -0,0 +1,47 @@
+use gb_rewind::*;
+use gb_gameboy::*;
+
+#[test]
+fn testRewindDelta() {
+    let from = vec![1, 2, 3, 4, 5, 6, 7, 8];
+    let to = vec![1, 2, 0, 0, 5, 6, 7, 9];
+
+    let delta = encodeDelta(&from[..], &to[..]);
+    let mut state = to.clone();
+    applyDelta(&mut state[..], &delta[..]);
+    assert_eq!(state, from);
+
+    //unchanged states make tiny deltas
+    assert_eq!(encodeDelta(&from[..], &from[..]).len(), 8);
+}
+
+#[test]
+fn testRewind() {
+    let mut gb = GameBoyState::new();
+    let mut rewind = RewindBuffer::new(2, 2);
+
+    assert!(!stepRewindBack(&mut rewind, &mut gb));
+
+    //snapshots are taken on every second frame
+    for frame in 1..9 {
+        gb.cpu.A = frame;
+        recordRewindFrame(&mut rewind, &gb);
+    }
+
+    //only 3 snapshots fit: frames 4, 6 and 8
+    assert_eq!(rewind.deltas.len(), 2);
+
+    for &expectedA in [8, 6, 4, 4].iter() {
+        assert!(stepRewindBack(&mut rewind, &mut gb));
+        assert_eq!(gb.cpu.A, expectedA);
+    }
+
+    //recording picks up from the state rewound to
+    gb.cpu.A = 10;
+    recordRewindFrame(&mut rewind, &gb);
+    recordRewindFrame(&mut rewind, &gb);
+    assert!(stepRewindBack(&mut rewind, &mut gb));
+    assert_eq!(gb.cpu.A, 10);
+    assert!(stepRewindBack(&mut rewind, &mut gb));
+    assert_eq!(gb.cpu.A, 4);
+}
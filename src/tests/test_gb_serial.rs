@@ -0,0 +1,75 @@
+use gb_serial::*;
+use gb_memory::*;
+
+//always sends back the same byte, on either clock
+struct EchoDevice {
+    byte: u8
+}
+
+impl SerialDevice for EchoDevice {
+    fn transfer_byte(&mut self, _byte: u8) -> u8 {
+        self.byte
+    }
+
+    fn external_clock_transfer(&mut self, _byte: u8) -> Option<u8> {
+        Some(self.byte)
+    }
+}
+
+#[test]
+fn testInternalClockTransfer() {
+    let mut mem = MemoryMapState::new();
+    let capture = SerialCapture::new();
+    let output = capture.output.clone();
+    mem.serial.device = Box::new(capture);
+
+    writeByteToMemory(&mut mem, 0x42, 0xFF01);
+    writeByteToMemory(&mut mem, 0x81, 0xFF02);
+    assert_eq!(readByteFromMemory(&mem, 0xFF02), 0xFF);
+
+    //a bit is shifted every 512 cycles
+    stepSerial(&mut mem.serial, &mut mem.requestedInterrupts, 8 * 512 - 4);
+    assert_eq!(mem.requestedInterrupts, 0);
+    assert_eq!(readByteFromMemory(&mem, 0xFF01), 0x7F);
+
+    stepSerial(&mut mem.serial, &mut mem.requestedInterrupts, 4);
+    assert_eq!(mem.requestedInterrupts, SERIAL_INTERRUPT);
+    assert_eq!(readByteFromMemory(&mem, 0xFF01), 0xFF); //nothing is sent back by a capture
+    assert_eq!(readByteFromMemory(&mem, 0xFF02), 0x7F);
+
+    assert_eq!(*output.borrow(), vec![0x42]);
+}
+
+#[test]
+fn testTransferShiftsBits() {
+    let mut mem = MemoryMapState::new();
+    mem.serial.device = Box::new(EchoDevice { byte: 0x0F });
+
+    writeByteToMemory(&mut mem, 0x42, 0xFF01);
+    writeByteToMemory(&mut mem, 0x81, 0xFF02);
+
+    //halfway through, the top 4 bits have been shifted out and the incoming top 4 bits shifted in
+    stepSerial(&mut mem.serial, &mut mem.requestedInterrupts, 4 * 512);
+    assert_eq!(readByteFromMemory(&mem, 0xFF01), 0x20);
+
+    stepSerial(&mut mem.serial, &mut mem.requestedInterrupts, 4 * 512);
+    assert_eq!(readByteFromMemory(&mem, 0xFF01), 0x0F);
+}
+
+#[test]
+fn testExternalClockTransfer() {
+    //nothing is plugged in to clock the transfer, so it never finishes
+    let mut mem = MemoryMapState::new();
+    writeByteToMemory(&mut mem, 0x42, 0xFF01);
+    writeByteToMemory(&mut mem, 0x80, 0xFF02);
+
+    stepSerial(&mut mem.serial, &mut mem.requestedInterrupts, 100000);
+    assert_eq!(mem.requestedInterrupts, 0);
+    assert_eq!(readByteFromMemory(&mem, 0xFF02), 0xFE);
+
+    //the device clocks it
+    mem.serial.device = Box::new(EchoDevice { byte: 0x99 });
+    stepSerial(&mut mem.serial, &mut mem.requestedInterrupts, 4);
+    assert_eq!(mem.requestedInterrupts, SERIAL_INTERRUPT);
+    assert_eq!(readByteFromMemory(&mem, 0xFF01), 0x99);
+}
@@ -0,0 +1,111 @@
+use gb_timer::*;
+
+fn enabledTimer(clockSelect: u8) -> TimerState {
+    let mut timer = TimerState::new();
+    writeTimerRegister(&mut timer, 0x4 | clockSelect, 0xFF07);
+    timer
+}
+
+#[test]
+fn testDivider() {
+    let mut timer = TimerState::new();
+    let mut interrupts = 0u8;
+
+    stepTimer(&mut timer, &mut interrupts, 255);
+    assert_eq!(readTimerRegister(&timer, 0xFF04), 0);
+
+    stepTimer(&mut timer, &mut interrupts, 1);
+    assert_eq!(readTimerRegister(&timer, 0xFF04), 1);
+
+    //any write resets DIV
+    stepTimer(&mut timer, &mut interrupts, 256 * 4);
+    writeTimerRegister(&mut timer, 0x12, 0xFF04);
+    assert_eq!(readTimerRegister(&timer, 0xFF04), 0);
+
+    //timer is disabled
+    assert_eq!(readTimerRegister(&timer, 0xFF05), 0);
+    assert_eq!(readTimerRegister(&timer, 0xFF07), 0xF8);
+}
+
+#[test]
+fn testTIMAClockSelect() {
+    for &(clockSelect, cyclesPerIncrement) in [(0, 1024), (1, 16), (2, 64), (3, 256)].iter() {
+        let mut timer = enabledTimer(clockSelect);
+        let mut interrupts = 0u8;
+
+        stepTimer(&mut timer, &mut interrupts, cyclesPerIncrement - 4);
+        assert_eq!(timer.tima, 0);
+
+        stepTimer(&mut timer, &mut interrupts, 4);
+        assert_eq!(timer.tima, 1);
+
+        stepTimer(&mut timer, &mut interrupts, cyclesPerIncrement * 9);
+        assert_eq!(timer.tima, 10);
+    }
+}
+
+#[test]
+fn testTIMAOverflow() {
+    let mut timer = enabledTimer(1);
+    let mut interrupts = 0u8;
+    writeTimerRegister(&mut timer, 0xAB, 0xFF06);
+    writeTimerRegister(&mut timer, 0xFF, 0xFF05);
+
+    //TIMA reads 0 for a machine cycle before getting reloaded and requesting the interrupt
+    stepTimer(&mut timer, &mut interrupts, 16);
+    assert_eq!(timer.tima, 0);
+    assert_eq!(interrupts, 0);
+
+    stepTimer(&mut timer, &mut interrupts, 4);
+    assert_eq!(timer.tima, 0xAB);
+    assert_eq!(interrupts, TIMER_INTERRUPT);
+
+    //TIMA writes are ignored on the reload cycle, but TMA writes go through to TIMA
+    writeTimerRegister(&mut timer, 0x11, 0xFF05);
+    assert_eq!(timer.tima, 0xAB);
+    writeTimerRegister(&mut timer, 0x22, 0xFF06);
+    assert_eq!(timer.tima, 0x22);
+}
+
+#[test]
+fn testTIMAWriteCancelsReload() {
+    let mut timer = enabledTimer(1);
+    let mut interrupts = 0u8;
+    writeTimerRegister(&mut timer, 0xAB, 0xFF06);
+    writeTimerRegister(&mut timer, 0xFF, 0xFF05);
+
+    stepTimer(&mut timer, &mut interrupts, 16);
+    writeTimerRegister(&mut timer, 0x42, 0xFF05);
+
+    stepTimer(&mut timer, &mut interrupts, 4);
+    assert_eq!(timer.tima, 0x42);
+    assert_eq!(interrupts, 0);
+}
+
+#[test]
+fn testTimerGlitches() {
+    let mut interrupts = 0u8;
+
+    //resetting DIV while the selected bit is set increments TIMA
+    let mut timer = enabledTimer(1);
+    stepTimer(&mut timer, &mut interrupts, 8);
+    writeTimerRegister(&mut timer, 0, 0xFF04);
+    assert_eq!(timer.tima, 1);
+
+    //but not while it is clear
+    stepTimer(&mut timer, &mut interrupts, 4);
+    writeTimerRegister(&mut timer, 0, 0xFF04);
+    assert_eq!(timer.tima, 1);
+
+    //disabling the timer while the selected bit is set increments TIMA
+    let mut timer = enabledTimer(0);
+    stepTimer(&mut timer, &mut interrupts, 512);
+    writeTimerRegister(&mut timer, 0x0, 0xFF07);
+    assert_eq!(timer.tima, 1);
+
+    //as does switching from a set bit to a clear one
+    let mut timer = enabledTimer(0);
+    stepTimer(&mut timer, &mut interrupts, 512);
+    writeTimerRegister(&mut timer, 0x5, 0xFF07);
+    assert_eq!(timer.tima, 1);
+}
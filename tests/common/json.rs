@@ -0,0 +1,192 @@
+//Just enough of a JSON parser to read test vectors
+
+#[derive(PartialEq, Debug)]
+pub enum JSON {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JSON>),
+    Object(Vec<(String, JSON)>)
+}
+
+impl JSON {
+    pub fn get(&self, key: &str) -> Option<&JSON> {
+        match *self {
+            JSON::Object(ref members) => members.iter().find(|member| member.0 == key).map(|member| &member.1),
+            _ => None
+        }
+    }
+
+    pub fn asU64(&self) -> Option<u64> {
+        match *self {
+            JSON::Number(n) if n >= 0. && n.fract() == 0. => Some(n as u64),
+            _ => None
+        }
+    }
+
+    pub fn asStr(&self) -> Option<&str> {
+        match *self {
+            JSON::String(ref s) => Some(s),
+            _ => None
+        }
+    }
+
+    pub fn asArray(&self) -> Option<&[JSON]> {
+        match *self {
+            JSON::Array(ref elements) => Some(&elements[..]),
+            _ => None
+        }
+    }
+}
+
+struct JSONParser<'a> {
+    text: &'a [u8],
+    pos: usize
+}
+
+impl<'a> JSONParser<'a> {
+    fn error<T>(&self, message: &str) -> Result<T, String> {
+        Err(format!("{} at byte {}", message, self.pos))
+    }
+
+    fn skipWhitespace(&mut self) {
+        while self.pos < self.text.len() && (self.text[self.pos] as char).is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skipWhitespace();
+        self.text.get(self.pos).map(|c| *c)
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), String> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        }
+        else {
+            self.error(&format!("Expected '{}'", c as char))
+        }
+    }
+
+    fn parseLiteral(&mut self, literal: &str, value: JSON) -> Result<JSON, String> {
+        if self.text[self.pos..].starts_with(literal.as_bytes()) {
+            self.pos += literal.len();
+            Ok(value)
+        }
+        else {
+            self.error("Unknown literal")
+        }
+    }
+
+    fn parseNumber(&mut self) -> Result<JSON, String> {
+        let start = self.pos;
+        while self.pos < self.text.len() && b"+-0123456789.eE".contains(&self.text[self.pos]) {
+            self.pos += 1;
+        }
+
+        match String::from_utf8_lossy(&self.text[start..self.pos]).parse() {
+            Ok(n) => Ok(JSON::Number(n)),
+            Err(_) => self.error("Bad number")
+        }
+    }
+
+    fn parseString(&mut self) -> Result<String, String> {
+        try!(self.expect(b'"'));
+        let mut bytes = vec![];
+
+        loop {
+            match self.text.get(self.pos).map(|c| *c) {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(String::from_utf8_lossy(&bytes[..]).into_owned());
+                },
+                Some(b'\\') => {
+                    //test vectors never need anything fancier than simple escapes
+                    match self.text.get(self.pos + 1).map(|c| *c) {
+                        Some(b'n') => bytes.push(b'\n'),
+                        Some(b't') => bytes.push(b'\t'),
+                        Some(c) => bytes.push(c),
+                        None => return self.error("Unterminated string")
+                    }
+                    self.pos += 2;
+                },
+                Some(c) => {
+                    bytes.push(c);
+                    self.pos += 1;
+                },
+                None => return self.error("Unterminated string")
+            }
+        }
+    }
+
+    fn parseValue(&mut self) -> Result<JSON, String> {
+        match self.peek() {
+            Some(b'{') => {
+                self.pos += 1;
+                let mut members = vec![];
+
+                if self.peek() == Some(b'}') {
+                    self.pos += 1;
+                    return Ok(JSON::Object(members));
+                }
+
+                loop {
+                    let key = try!(self.parseString());
+                    try!(self.expect(b':'));
+                    members.push((key, try!(self.parseValue())));
+
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b'}') => {
+                            self.pos += 1;
+                            return Ok(JSON::Object(members));
+                        },
+                        _ => return self.error("Expected ',' or '}'")
+                    }
+                }
+            },
+            Some(b'[') => {
+                self.pos += 1;
+                let mut elements = vec![];
+
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                    return Ok(JSON::Array(elements));
+                }
+
+                loop {
+                    elements.push(try!(self.parseValue()));
+
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b']') => {
+                            self.pos += 1;
+                            return Ok(JSON::Array(elements));
+                        },
+                        _ => return self.error("Expected ',' or ']'")
+                    }
+                }
+            },
+            Some(b'"') => Ok(JSON::String(try!(self.parseString()))),
+            Some(b't') => self.parseLiteral("true", JSON::Bool(true)),
+            Some(b'f') => self.parseLiteral("false", JSON::Bool(false)),
+            Some(b'n') => self.parseLiteral("null", JSON::Null),
+            Some(_) => self.parseNumber(),
+            None => self.error("Unexpected end")
+        }
+    }
+}
+
+pub fn parseJSON(text: &str) -> Result<JSON, String> {
+    let mut parser = JSONParser { text: text.as_bytes(), pos: 0 };
+    let value = try!(parser.parseValue());
+
+    if parser.peek().is_some() {
+        return parser.error("Trailing characters");
+    }
+
+    Ok(value)
+}
@@ -0,0 +1,127 @@
+//helpers shared by the test ROM suites
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::env;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::rc::Rc;
+
+use gbEmu::gb_gameboy::*;
+use gbEmu::gb_memory::*;
+use gbEmu::gb_mbc::*;
+use gbEmu::gb_serial::*;
+use gbEmu::gb_lcd::*;
+
+pub mod json;
+
+const GOLDEN_IMAGE_DIR: &'static str = "tests/golden";
+const GOLDEN_DIFF_DIR: &'static str = "target/golden-diffs";
+
+/*
+ * Loads a ROM into a new Game Boy that starts at the cartridge's entry point
+ *
+ * Args:
+ *      fileName: Path to the ROM, relative to the root of the repo
+ *
+ * Return: The Game Boy, ready to run
+ *
+ */
+pub fn gameBoyWithROM(fileName: &str) -> Box<GameBoyState> {
+    let romData = openROM(fileName).unwrap_or_else(|err| panic!("Could not open {}. Error: {}", fileName, err));
+
+    let mut gb = Box::new(GameBoyState::new());
+    gb.mem.cartridge = cartridgeFromROM(romData).unwrap();
+    gb.skipBootScreen();
+
+    gb
+}
+
+//Plugs a capture into the link port.  Returns where the bytes sent over it will go
+pub fn captureSerialOutput(gb: &mut GameBoyState) -> Rc<RefCell<Vec<u8>>> {
+    let capture = SerialCapture::new();
+    let output = capture.output.clone();
+    gb.mem.serial.device = Box::new(capture);
+
+    output
+}
+
+//Loads a ROM and runs it for a number of frames.  Each frame ends at VBlank, so the screen is fully drawn
+pub fn runROMForFrames(fileName: &str, numFrames: u32) -> Box<GameBoyState> {
+    let mut gb = gameBoyWithROM(fileName);
+
+    for _ in 0..numFrames {
+        gb.run_frame();
+        gb.takeAudioSamples();
+    }
+
+    gb
+}
+
+fn writeFile(fileName: &str, data: &[u8]) {
+    File::create(fileName).and_then(|mut f| f.write_all(data))
+        .unwrap_or_else(|err| panic!("Could not write {}. Error: {}", fileName, err));
+}
+
+//red where the pixels differ, a faded copy of the expected screen everywhere else
+fn goldenDiffImage(expectedPixels: &[u8], actualPixels: &[u8]) -> Vec<u8> {
+    let mut ppm = format!("P6\n{} {}\n255\n", SCREEN_WIDTH, SCREEN_HEIGHT).into_bytes();
+
+    for (expected, actual) in expectedPixels.iter().zip(actualPixels.iter()) {
+        if expected == actual {
+            let faded = 128 + expected / 2;
+            ppm.extend([faded, faded, faded].iter());
+        }
+        else {
+            ppm.extend([255, 0, 0].iter());
+        }
+    }
+
+    ppm
+}
+
+/*
+ * Compares a screen against the reference image tests/golden/<name>.pgm, which is a PGM written by
+ * screenToPGM() (e.g. with gbemu-headless --screenshot).  On a mismatch, the actual screen and a diff
+ * image with the differing pixels in red get written to target/golden-diffs.
+ * Set UPDATE_GOLDEN_IMAGES=1 to write the screen as the new reference instead
+ *
+ * Args:
+ *      screen: The screen to check
+ *      name: Name of the reference image without the extension
+ *
+ */
+pub fn assertScreenMatchesGoldenImage(screen: &LCDScreen, name: &str) {
+    let goldenFileName = format!("{}/{}.pgm", GOLDEN_IMAGE_DIR, name);
+    let actual = screenToPGM(screen);
+
+    if env::var("UPDATE_GOLDEN_IMAGES").is_ok() {
+        writeFile(&goldenFileName, &actual[..]);
+        return;
+    }
+
+    let mut expected = vec![];
+    File::open(&goldenFileName).and_then(|mut f| f.read_to_end(&mut expected))
+        .unwrap_or_else(|err| panic!("Could not read {}. Error: {}", goldenFileName, err));
+
+    assert!(expected.len() == actual.len(), "{} is not a {}x{} PGM", goldenFileName, SCREEN_WIDTH, SCREEN_HEIGHT);
+
+    //the header is the same size in both, so the pixels are at the end of each
+    let pixelStart = actual.len() - SCREEN_WIDTH * SCREEN_HEIGHT;
+    let expectedPixels = &expected[pixelStart..];
+    let actualPixels = &actual[pixelStart..];
+
+    let numDifferentPixels = expectedPixels.iter().zip(actualPixels.iter()).filter(|&(e, a)| e != a).count();
+    if numDifferentPixels == 0 {
+        return;
+    }
+
+    fs::create_dir_all(GOLDEN_DIFF_DIR).unwrap();
+    let actualFileName = format!("{}/{}.actual.pgm", GOLDEN_DIFF_DIR, name);
+    let diffFileName = format!("{}/{}.diff.ppm", GOLDEN_DIFF_DIR, name);
+    writeFile(&actualFileName, &actual[..]);
+    writeFile(&diffFileName, &goldenDiffImage(expectedPixels, actualPixels)[..]);
+
+    panic!("{} pixels differ from {}. Actual screen written to {} and the difference to {}",
+           numDifferentPixels, goldenFileName, actualFileName, diffFileName);
+}
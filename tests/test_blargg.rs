@@ -0,0 +1,98 @@
+/*
+ * Runs Blargg's CPU test ROMs in samples/.  They print their results over the serial port,
+ * ending with "Passed" or "Failed"
+ *
+ */
+#![allow(non_snake_case)]
+
+extern crate gbEmu;
+
+mod common;
+
+use common::*;
+
+//cpu_instrs runs every test back to back and needs about a minute of Game Boy time
+const MAX_FRAMES: u32 = 2 * 60 * 60;
+
+fn runBlarggROM(fileName: &str) {
+    let mut gb = gameBoyWithROM(fileName);
+    let output = captureSerialOutput(&mut gb);
+
+    for _ in 0..MAX_FRAMES {
+        gb.run_frame();
+        gb.takeAudioSamples();
+
+        let text = String::from_utf8_lossy(&output.borrow()[..]).into_owned();
+
+        if text.contains("Passed") {
+            return;
+        }
+
+        if text.contains("Failed") {
+            panic!("{} failed after {} cycles. Output:\n{}", fileName, gb.cpu.totalCycles, text);
+        }
+    }
+
+    panic!("{} did not finish after {} cycles. Output:\n{}",
+           fileName, gb.cpu.totalCycles, String::from_utf8_lossy(&output.borrow()[..]));
+}
+
+#[test]
+fn test01Special() {
+    runBlarggROM("samples/01-special.gb");
+}
+
+#[test]
+fn test02Interrupts() {
+    runBlarggROM("samples/02-interrupts.gb");
+}
+
+#[test]
+fn test03OpSPHL() {
+    runBlarggROM("samples/03-op sp,hl.gb");
+}
+
+#[test]
+fn test04OpRImm() {
+    runBlarggROM("samples/04-op r,imm.gb");
+}
+
+#[test]
+fn test05OpRP() {
+    runBlarggROM("samples/05-op rp.gb");
+}
+
+#[test]
+fn test06LdRR() {
+    runBlarggROM("samples/06-ld r,r.gb");
+}
+
+#[test]
+fn test07JrJpCallRetRst() {
+    runBlarggROM("samples/07-jr,jp,call,ret,rst.gb");
+}
+
+#[test]
+fn test08MiscInstrs() {
+    runBlarggROM("samples/08-misc instrs.gb");
+}
+
+#[test]
+fn test09OpRR() {
+    runBlarggROM("samples/09-op r,r.gb");
+}
+
+#[test]
+fn test10BitOps() {
+    runBlarggROM("samples/10-bit ops.gb");
+}
+
+#[test]
+fn test11OpAHL() {
+    runBlarggROM("samples/11-op a,(hl).gb");
+}
+
+#[test]
+fn testCPUInstrs() {
+    runBlarggROM("samples/cpu_instrs.gb");
+}
@@ -0,0 +1,47 @@
+/*
+ * Golden image tests for the PPU.  Each runs a ROM for a fixed number of frames and compares the
+ * screen against a reference image in tests/golden.  See assertScreenMatchesGoldenImage()
+ *
+ */
+#![allow(non_snake_case)]
+
+extern crate gbEmu;
+
+mod common;
+
+use std::path::Path;
+
+use common::*;
+
+fn checkGoldenImage(romFileName: &str, numFrames: u32, goldenName: &str) {
+    let gb = runROMForFrames(romFileName, numFrames);
+    assertScreenMatchesGoldenImage(&gb.mem.lcd.screen, goldenName);
+}
+
+//background text
+#[test]
+fn testBlarggTextScreen() {
+    checkGoldenImage("samples/01-special.gb", 400, "01-special");
+}
+
+//background tiles
+#[test]
+fn testTicTacToeTitleScreen() {
+    checkGoldenImage("samples/ttt.gb", 400, "ttt");
+}
+
+//sprites over a scrolling background
+#[test]
+fn testOpus5Screen() {
+    checkGoldenImage("samples/opus5.gb", 400, "opus5");
+}
+
+//dmg-acid2 isn't shipped, so this is ignored by default.  Put the ROM at samples/dmg-acid2.gb and its
+//reference screen at tests/golden/dmg-acid2.pgm, then run it with --ignored
+#[test]
+#[ignore]
+fn testDMGAcid2() {
+    assert!(Path::new("samples/dmg-acid2.gb").exists(), "samples/dmg-acid2.gb not found");
+
+    checkGoldenImage("samples/dmg-acid2.gb", 60, "dmg-acid2");
+}
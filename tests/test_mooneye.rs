@@ -0,0 +1,115 @@
+/*
+ * Runs mooneye-gb style test ROMs.  Drop them anywhere under samples/mooneye
+ * (or the directory in MOONEYE_ROM_DIR) and each one gets run.
+ *
+ * These ROMs run LD B,B once they are done.  If they passed, B, C, D, E, H and L hold the
+ * Fibonacci numbers 3, 5, 8, 13, 21 and 34.  If they failed, they all hold 0x42
+ *
+ * The ROMs aren't part of the repo, so this is ignored by default and fails if it can't find any.
+ * Run with --ignored --nocapture to see the result of every ROM
+ */
+#![allow(non_snake_case)]
+
+extern crate gbEmu;
+
+mod common;
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use common::*;
+
+const DEFAULT_ROM_DIR: &'static str = "samples/mooneye";
+const MAX_FRAMES: u32 = 60 * 60;
+
+const PASS_SIGNATURE: [u8; 6] = [3, 5, 8, 13, 21, 34];
+
+#[derive(PartialEq)]
+enum MooneyeResult {
+    Passed,
+    Failed([u8; 6]), //B, C, D, E, H and L
+    TimedOut
+}
+
+impl fmt::Display for MooneyeResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MooneyeResult::Passed => write!(f, "passed"),
+            MooneyeResult::Failed(registers) =>
+                write!(f, "failed. B: {:X}, C: {:X}, D: {:X}, E: {:X}, H: {:X}, L: {:X}",
+                       registers[0], registers[1], registers[2], registers[3], registers[4], registers[5]),
+            MooneyeResult::TimedOut => write!(f, "timed out without reaching LD B,B")
+        }
+    }
+}
+
+fn findROMs(dir: &Path, roms: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return
+    };
+
+    for entry in entries {
+        let path = entry.unwrap().path();
+
+        if path.is_dir() {
+            findROMs(&path, roms);
+        }
+        else if path.extension().map_or(false, |ext| ext == "gb") {
+            roms.push(path);
+        }
+    }
+}
+
+fn runMooneyeROM(fileName: &str) -> MooneyeResult {
+    let mut gb = gameBoyWithROM(fileName);
+    gb.isLDBBBreakpointEnabled = true;
+
+    for _ in 0..MAX_FRAMES {
+        let events = gb.run_frame();
+        gb.takeAudioSamples();
+
+        if events.isBreakpointHit {
+            let registers = [gb.cpu.B, gb.cpu.C, gb.cpu.D, gb.cpu.E, gb.cpu.H, gb.cpu.L];
+
+            return if registers == PASS_SIGNATURE {
+                MooneyeResult::Passed
+            }
+            else {
+                MooneyeResult::Failed(registers)
+            };
+        }
+    }
+
+    MooneyeResult::TimedOut
+}
+
+#[test]
+#[ignore]
+fn testMooneyeROMs() {
+    let romDir = env::var("MOONEYE_ROM_DIR").unwrap_or(DEFAULT_ROM_DIR.to_string());
+
+    let mut roms = vec![];
+    findROMs(Path::new(&romDir), &mut roms);
+    roms.sort();
+
+    assert!(!roms.is_empty(), "No mooneye ROMs found in {}", romDir);
+
+    let mut failures = vec![];
+
+    for rom in roms.iter() {
+        let fileName = rom.to_string_lossy().into_owned();
+        let result = runMooneyeROM(&fileName);
+
+        println!("{}: {}", fileName, result);
+
+        if result != MooneyeResult::Passed {
+            failures.push(format!("{}: {}", fileName, result));
+        }
+    }
+
+    assert!(failures.is_empty(), "{} of {} mooneye ROMs failed:\n{}",
+            failures.len(), roms.len(), failures.join("\n"));
+}
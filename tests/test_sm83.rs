@@ -0,0 +1,274 @@
+/*
+ * Checks executeInstruction against single step SM83 test vectors, one JSON file per opcode
+ * (00.json, 01.json, ..., cb 00.json, ...).  Put them in tests/sm83 (or the directory in SM83_TEST_DIR).
+ *
+ * Each file is an array of tests like:
+ *      { "name": "cb 12 0003",
+ *        "initial": { "pc": .., "sp": .., "a": .., "b": .., "c": .., "d": .., "e": .., "f": .., "h": .., "l": ..,
+ *                     "ime": .., "ram": [[addr, value], ...] },
+ *        "final": { same as initial },
+ *        "cycles": [ one entry per machine cycle ] }
+ *
+ * Every test runs on a flat 64KB bus, so nothing but the instruction itself touches memory.
+ * The vectors aren't part of the repo, so this is ignored by default and fails if it can't find any.
+ * Run with --ignored --nocapture to see how every opcode did
+ */
+#![allow(non_snake_case)]
+
+extern crate gbEmu;
+
+mod common;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use gbEmu::gb_bus::*;
+use gbEmu::gb_cpu::*;
+
+use common::json::*;
+
+const DEFAULT_TEST_DIR: &'static str = "tests/sm83";
+
+//only this many failures get reported per opcode so one broken instruction doesn't bury the rest
+const MAX_FAILURES_REPORTED_PER_OPCODE: usize = 5;
+
+//STOP waits on the joypad and speed switch, which a flat bus doesn't have
+const SKIPPED_OPCODES: [u8; 1] = [0x10];
+
+const EI_OPCODE: u8 = 0xFB;
+const HALT_OPCODE: u8 = 0x76;
+
+fn field(state: &JSON, name: &str, testName: &str) -> u64 {
+    state.get(name).and_then(|value| value.asU64())
+        .unwrap_or_else(|| panic!("{}: missing or bad \"{}\"", testName, name))
+}
+
+fn ramOf<'a>(state: &'a JSON, testName: &str) -> Vec<(u16, u8)> {
+    let ram = state.get("ram").and_then(|ram| ram.asArray())
+        .unwrap_or_else(|| panic!("{}: missing or bad \"ram\"", testName));
+
+    ram.iter().map(|entry| {
+        match entry.asArray() {
+            Some(pair) if pair.len() == 2 && pair[0].asU64().is_some() && pair[1].asU64().is_some() =>
+                (pair[0].asU64().unwrap() as u16, pair[1].asU64().unwrap() as u8),
+            _ => panic!("{}: bad ram entry", testName)
+        }
+    }).collect()
+}
+
+//the opcode bytes a test file covers, taken from a file name like "cb 12.json"
+fn opcodeBytesFromFileName(path: &Path) -> Option<Vec<u8>> {
+    let stem = match path.file_stem() {
+        Some(stem) => stem.to_string_lossy().into_owned(),
+        None => return None
+    };
+
+    let bytes: Vec<Option<u8>> = stem.split_whitespace().map(|byte| u8::from_str_radix(byte, 16).ok()).collect();
+
+    if bytes.is_empty() || bytes.iter().any(|byte| byte.is_none()) {
+        None
+    }
+    else {
+        Some(bytes.into_iter().map(|byte| byte.unwrap()).collect())
+    }
+}
+
+//whether the instruction's bytes are in the test's initial RAM at the given address
+fn isOpcodeAt(ram: &[(u16, u8)], opcodeBytes: &[u8], addr: u16) -> bool {
+    opcodeBytes.iter().enumerate().all(|(i, byte)| {
+        let byteAddr = addr.wrapping_add(i as u16);
+        ram.iter().any(|&(ramAddr, value)| ramAddr == byteAddr && value == *byte)
+    })
+}
+
+/*
+ * Some test suites model the fetch of the next opcode overlapping the current instruction,
+ * so their PC starts one past the opcode and ends one past the next instruction.
+ * The byte after an opcode is often the opcode again (e.g. 3E 3E), so this can't be guessed
+ * for every test.  Instead it is taken from the first test in the file where the opcode is only
+ * found at one of the 2 places
+ *
+ * Args:
+ *      tests: Every test vector in a file
+ *      opcodeBytes: Bytes of the instruction being tested
+ *
+ * Return: How far past the opcode PC starts (0 or 1), or None if no test tells them apart
+ *
+ */
+fn pcAdjustmentForTests(tests: &[JSON], opcodeBytes: &[u8]) -> Option<u16> {
+    for test in tests.iter() {
+        let name = test.get("name").and_then(|name| name.asStr()).unwrap_or("unnamed");
+        let initial = test.get("initial").unwrap_or_else(|| panic!("{}: missing \"initial\"", name));
+        let pc = field(initial, "pc", name) as u16;
+        let ram = ramOf(initial, name);
+
+        match (isOpcodeAt(&ram, opcodeBytes, pc), isOpcodeAt(&ram, opcodeBytes, pc.wrapping_sub(1))) {
+            (true, false) => return Some(0),
+            (false, true) => return Some(1),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/*
+ * Runs one test vector
+ *
+ * Args:
+ *      test: The test vector
+ *      opcodeBytes: Bytes of the instruction being tested
+ *      pcAdjustment: How far past the opcode PC starts, from pcAdjustmentForTests
+ *
+ * Return: Ok if the CPU ended up in the final state in the right number of cycles,
+ *         otherwise every difference that was found
+ *
+ */
+fn runTest(test: &JSON, opcodeBytes: &[u8], pcAdjustment: u16) -> Result<(), String> {
+    let name = test.get("name").and_then(|name| name.asStr()).unwrap_or("unnamed").to_string();
+    let initial = test.get("initial").unwrap_or_else(|| panic!("{}: missing \"initial\"", name));
+    let expected = test.get("final").unwrap_or_else(|| panic!("{}: missing \"final\"", name));
+    let expectedCycles = test.get("cycles").and_then(|cycles| cycles.asArray())
+        .unwrap_or_else(|| panic!("{}: missing \"cycles\"", name)).len() as u32 * 4;
+
+    let mut cpu = CPUState::new();
+    let mut bus = FlatBus::new();
+
+    cpu.PC = field(initial, "pc", &name) as u16;
+    cpu.SP = field(initial, "sp", &name) as u16;
+    cpu.A = field(initial, "a", &name) as u8;
+    cpu.B = field(initial, "b", &name) as u8;
+    cpu.C = field(initial, "c", &name) as u8;
+    cpu.D = field(initial, "d", &name) as u8;
+    cpu.E = field(initial, "e", &name) as u8;
+    cpu.F = field(initial, "f", &name) as u8;
+    cpu.H = field(initial, "h", &name) as u8;
+    cpu.L = field(initial, "l", &name) as u8;
+    cpu.enableInterrupts = field(initial, "ime", &name) != 0;
+
+    for (addr, value) in ramOf(initial, &name) {
+        bus.memory[addr as usize] = value;
+    }
+
+    cpu.PC = cpu.PC.wrapping_sub(pcAdjustment);
+
+    let (newPC, cyclesTaken) = executeInstruction(opcodeBytes[0], &mut cpu, &mut bus);
+    cpu.PC = newPC.wrapping_add(pcAdjustment);
+    cpu.F &= 0xF0; //stepCPU does this after every instruction
+
+    let mut differences = vec![];
+
+    {
+        let mut compare = |register: &str, actual: u64| {
+            let expectedValue = field(expected, register, &name);
+
+            if actual != expectedValue {
+                differences.push(format!("{}: expected {:X}, got {:X}", register, expectedValue, actual));
+            }
+        };
+
+        compare("pc", cpu.PC as u64);
+        compare("sp", cpu.SP as u64);
+        compare("a", cpu.A as u64);
+        compare("b", cpu.B as u64);
+        compare("c", cpu.C as u64);
+        compare("d", cpu.D as u64);
+        compare("e", cpu.E as u64);
+        compare("f", cpu.F as u64);
+        compare("h", cpu.H as u64);
+        compare("l", cpu.L as u64);
+    }
+
+    //EI takes effect immediately here instead of after the next instruction,
+    //so an EI that is still pending counts as enabled
+    let isIMEPending = opcodeBytes[0] == EI_OPCODE && expected.get("ei").and_then(|ei| ei.asU64()).map_or(false, |ei| ei != 0);
+    let expectedIME = field(expected, "ime", &name) != 0 || isIMEPending;
+    if cpu.enableInterrupts != expectedIME {
+        differences.push(format!("ime: expected {}, got {}", expectedIME, cpu.enableInterrupts));
+    }
+
+    //the vectors don't say whether the CPU halted.  Nothing is pending on the flat bus unless the test
+    //sets IE and IF, and HALT only skips halting (the HALT bug) when something is pending with IME off
+    if opcodeBytes[0] == HALT_OPCODE && opcodeBytes.len() == 1 {
+        let isHaltExpected = cpu.enableInterrupts || bus.pending_interrupts() == 0;
+        if cpu.isHalted != isHaltExpected {
+            differences.push(format!("halted: expected {}, got {}", isHaltExpected, cpu.isHalted));
+        }
+    }
+
+    for (addr, value) in ramOf(expected, &name) {
+        if bus.memory[addr as usize] != value {
+            differences.push(format!("[{:04X}]: expected {:X}, got {:X}", addr, value, bus.memory[addr as usize]));
+        }
+    }
+
+    if cyclesTaken != expectedCycles {
+        differences.push(format!("cycles: expected {}, got {}", expectedCycles, cyclesTaken));
+    }
+
+    if differences.is_empty() {
+        Ok(())
+    }
+    else {
+        Err(format!("{}: {}", name, differences.join(", ")))
+    }
+}
+
+fn readTestFile(path: &Path) -> JSON {
+    let mut text = String::new();
+    File::open(path).and_then(|mut f| f.read_to_string(&mut text))
+        .unwrap_or_else(|err| panic!("Could not read {}. Error: {}", path.display(), err));
+
+    parseJSON(&text).unwrap_or_else(|err| panic!("Could not parse {}. Error: {}", path.display(), err))
+}
+
+#[test]
+#[ignore]
+fn testSM83SingleStep() {
+    let testDir = env::var("SM83_TEST_DIR").unwrap_or(DEFAULT_TEST_DIR.to_string());
+
+    let mut testFiles: Vec<PathBuf> = match fs::read_dir(&testDir) {
+        Ok(entries) => entries.map(|entry| entry.unwrap().path())
+                              .filter(|path| path.extension().map_or(false, |ext| ext == "json"))
+                              .collect(),
+        Err(_) => vec![]
+    };
+    testFiles.sort();
+
+    assert!(!testFiles.is_empty(), "No SM83 test vectors found in {}", testDir);
+
+    let mut failures = vec![];
+    let mut testsRun = 0;
+
+    for path in testFiles.iter() {
+        let opcodeBytes = opcodeBytesFromFileName(path)
+            .unwrap_or_else(|| panic!("{} is not named after an opcode", path.display()));
+
+        if opcodeBytes.len() == 1 && SKIPPED_OPCODES.contains(&opcodeBytes[0]) {
+            println!("{}: skipped", path.display());
+            continue;
+        }
+
+        let tests = readTestFile(path);
+        let tests = tests.asArray().unwrap_or_else(|| panic!("{} is not an array of tests", path.display()));
+
+        let pcAdjustment = pcAdjustmentForTests(tests, &opcodeBytes)
+            .unwrap_or_else(|| panic!("{}: could not tell where the opcode starts relative to PC", path.display()));
+
+        let fileFailures: Vec<String> = tests.iter().filter_map(|test| runTest(test, &opcodeBytes, pcAdjustment).err()).collect();
+
+        println!("{}: {} of {} passed", path.display(), tests.len() - fileFailures.len(), tests.len());
+
+        testsRun += tests.len();
+        if !fileFailures.is_empty() {
+            failures.push(format!("{} ({} failed):\n    {}", path.display(), fileFailures.len(),
+                                  fileFailures.iter().take(MAX_FAILURES_REPORTED_PER_OPCODE)
+                                              .cloned().collect::<Vec<String>>().join("\n    ")));
+        }
+    }
+
+    assert!(failures.is_empty(), "{} of {} SM83 test files had failures out of {} tests:\n{}",
+            failures.len(), testFiles.len(), testsRun, failures.join("\n"));
+}